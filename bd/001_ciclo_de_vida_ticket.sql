@@ -0,0 +1,12 @@
+-- Ciclo de vida completo dos tickets (substitui Aberto/Fechado)
+
+-- Amplia o ENUM mantendo 'Aberto' para migrar os dados existentes
+ALTER TABLE Tickets MODIFY Ticket_Status
+    ENUM('Aberto', 'Novo', 'Em Atendimento', 'Aguardando Cliente', 'Resolvido', 'Fechado', 'Reaberto')
+    NOT NULL DEFAULT 'Novo';
+
+UPDATE Tickets SET Ticket_Status = 'Novo' WHERE Ticket_Status = 'Aberto';
+
+ALTER TABLE Tickets MODIFY Ticket_Status
+    ENUM('Novo', 'Em Atendimento', 'Aguardando Cliente', 'Resolvido', 'Fechado', 'Reaberto')
+    NOT NULL DEFAULT 'Novo';
@@ -0,0 +1,14 @@
+-- Conversa (comentários e respostas) dos tickets
+
+CREATE TABLE IF NOT EXISTS Ticket_Comments (
+    ID_Comment INT AUTO_INCREMENT PRIMARY KEY,
+    ID_Ticket INT NOT NULL,
+    ID_Parent_Comment INT NULL,
+    ID_User_Author INT NOT NULL,
+    Comment_Body TEXT NOT NULL,
+    Comment_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_Comment_Ticket FOREIGN KEY (ID_Ticket) REFERENCES Tickets (ID_Ticket) ON DELETE CASCADE,
+    CONSTRAINT FK_Comment_Parent FOREIGN KEY (ID_Parent_Comment) REFERENCES Ticket_Comments (ID_Comment) ON DELETE CASCADE,
+    CONSTRAINT FK_Comment_Author FOREIGN KEY (ID_User_Author) REFERENCES Users (ID_User),
+    INDEX IDX_Comment_Ticket (ID_Ticket, Comment_Created_At)
+);
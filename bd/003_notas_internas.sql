@@ -0,0 +1,4 @@
+-- Notas internas: mensagens do ticket visíveis apenas para a equipa
+
+ALTER TABLE Ticket_Comments
+    ADD COLUMN Comment_Is_Internal BOOLEAN NOT NULL DEFAULT FALSE AFTER Comment_Body;
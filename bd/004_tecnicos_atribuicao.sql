@@ -0,0 +1,9 @@
+-- Papel de técnico e atribuição de tickets
+
+ALTER TABLE Users MODIFY User_Role
+    ENUM('Cliente', 'Tecnico', 'Administrador') NOT NULL DEFAULT 'Cliente';
+
+ALTER TABLE Tickets
+    ADD COLUMN ID_User_Assigned INT NULL AFTER ID_User_Requesting,
+    ADD CONSTRAINT FK_Ticket_Assigned FOREIGN KEY (ID_User_Assigned) REFERENCES Users (ID_User) ON DELETE SET NULL,
+    ADD INDEX IDX_Ticket_Assigned (ID_User_Assigned, Ticket_Status);
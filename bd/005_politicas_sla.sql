@@ -0,0 +1,22 @@
+-- Políticas de SLA e prazos calculados por ticket
+
+CREATE TABLE IF NOT EXISTS SLA_Policies (
+    ID_Policy INT AUTO_INCREMENT PRIMARY KEY,
+    Policy_Priority ENUM('baixa', 'média', 'alta') NOT NULL,
+    Policy_Category ENUM('Software', 'Hardware', 'Redes', 'Acesso') NULL, -- NULL = todas as categorias
+    Policy_Response_Minutes INT NOT NULL,
+    Policy_Resolution_Minutes INT NOT NULL,
+    UNIQUE KEY UQ_Policy (Policy_Priority, Policy_Category)
+);
+
+INSERT INTO SLA_Policies (Policy_Priority, Policy_Category, Policy_Response_Minutes, Policy_Resolution_Minutes) VALUES
+    ('alta', NULL, 60, 480),
+    ('média', NULL, 240, 1440),
+    ('baixa', NULL, 480, 4320);
+
+ALTER TABLE Tickets
+    ADD COLUMN Ticket_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN Ticket_Response_Due DATETIME NULL,
+    ADD COLUMN Ticket_Resolution_Due DATETIME NULL,
+    ADD COLUMN Ticket_First_Response_At DATETIME NULL,
+    ADD COLUMN Ticket_Resolved_At DATETIME NULL;
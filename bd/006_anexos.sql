@@ -0,0 +1,20 @@
+-- Anexos de tickets e de comentários.
+-- O conteúdo fica no armazenamento configurado (pasta local ou bucket S3);
+-- aqui ficam apenas os metadados e a chave de armazenamento.
+
+CREATE TABLE IF NOT EXISTS Ticket_Attachments (
+    ID_Attachment INT AUTO_INCREMENT PRIMARY KEY,
+    ID_Ticket INT NOT NULL,
+    ID_Comment INT NULL,
+    Attachment_File_Name VARCHAR(255) NOT NULL,
+    Attachment_Mime_Type VARCHAR(127) NOT NULL,
+    Attachment_Size BIGINT NOT NULL,
+    ID_User_Uploader INT NOT NULL,
+    Attachment_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    Attachment_Storage_Key VARCHAR(255) NOT NULL,
+    CONSTRAINT UQ_Attachment_Storage_Key UNIQUE (Attachment_Storage_Key),
+    CONSTRAINT FK_Attachment_Ticket FOREIGN KEY (ID_Ticket) REFERENCES Tickets (ID_Ticket) ON DELETE CASCADE,
+    CONSTRAINT FK_Attachment_Comment FOREIGN KEY (ID_Comment) REFERENCES Ticket_Comments (ID_Comment) ON DELETE CASCADE,
+    CONSTRAINT FK_Attachment_Uploader FOREIGN KEY (ID_User_Uploader) REFERENCES Users (ID_User),
+    INDEX IDX_Attachment_Ticket (ID_Ticket, Attachment_Created_At)
+);
@@ -0,0 +1,8 @@
+-- Índices para os filtros, a ordenação e a paginação por cursor das listagens de tickets
+
+CREATE INDEX IDX_Ticket_Created ON Tickets (Ticket_Created_At, ID_Ticket);
+CREATE INDEX IDX_Ticket_Status_Created ON Tickets (Ticket_Status, Ticket_Created_At);
+CREATE INDEX IDX_Ticket_Priority ON Tickets (Ticket_Priority);
+CREATE INDEX IDX_Ticket_Category ON Tickets (Ticket_category);
+CREATE INDEX IDX_Ticket_Requesting_Status ON Tickets (ID_User_Requesting, Ticket_Status);
+CREATE INDEX IDX_Ticket_Assigned_Status ON Tickets (ID_User_Assigned, Ticket_Status);
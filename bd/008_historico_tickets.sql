@@ -0,0 +1,27 @@
+-- Histórico (auditoria) de todas as alterações dos tickets.
+-- As entradas são imutáveis: os triggers abaixo recusam UPDATE e DELETE.
+
+CREATE TABLE IF NOT EXISTS Ticket_History (
+    ID_History INT AUTO_INCREMENT PRIMARY KEY,
+    ID_Ticket INT NOT NULL,
+    ID_User_Actor INT NULL,
+    History_Action VARCHAR(32) NOT NULL,
+    History_Old_Value TEXT NULL,
+    History_New_Value TEXT NULL,
+    History_Is_Internal BOOLEAN NOT NULL DEFAULT FALSE,
+    History_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_History_Ticket FOREIGN KEY (ID_Ticket) REFERENCES Tickets (ID_Ticket),
+    CONSTRAINT FK_History_Actor FOREIGN KEY (ID_User_Actor) REFERENCES Users (ID_User),
+    INDEX IDX_History_Ticket (ID_Ticket, History_Created_At)
+);
+
+CREATE TRIGGER TRG_Ticket_History_No_Update BEFORE UPDATE ON Ticket_History
+    FOR EACH ROW SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = 'Ticket_History é imutável';
+
+CREATE TRIGGER TRG_Ticket_History_No_Delete BEFORE DELETE ON Ticket_History
+    FOR EACH ROW SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = 'Ticket_History é imutável';
+
+-- Regista a abertura dos tickets já existentes
+INSERT INTO Ticket_History (ID_Ticket, ID_User_Actor, History_Action, History_New_Value, History_Created_At)
+SELECT ID_Ticket, ID_User_Requesting, 'criado', LEFT(Ticket_Title, 200), Ticket_Created_At
+FROM Tickets;
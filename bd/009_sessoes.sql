@@ -0,0 +1,23 @@
+-- Sessões de login e refresh tokens.
+-- Os access tokens (JWT, 15 minutos) levam o ID da sessão e deixam de valer quando
+-- ela é revogada. Os refresh tokens são de uso único e guardados apenas como hash SHA-256.
+
+CREATE TABLE IF NOT EXISTS User_Sessions (
+    ID_Session CHAR(64) PRIMARY KEY,
+    ID_User INT NOT NULL,
+    Session_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    Session_Revoked_At DATETIME NULL,
+    CONSTRAINT FK_Session_User FOREIGN KEY (ID_User) REFERENCES Users (ID_User),
+    INDEX IDX_Session_User (ID_User)
+);
+
+CREATE TABLE IF NOT EXISTS Refresh_Tokens (
+    ID_Refresh_Token INT AUTO_INCREMENT PRIMARY KEY,
+    ID_Session CHAR(64) NOT NULL,
+    Token_Hash CHAR(64) NOT NULL,
+    Token_Expires_At DATETIME NOT NULL,
+    Token_Used_At DATETIME NULL,
+    Token_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_Refresh_Session FOREIGN KEY (ID_Session) REFERENCES User_Sessions (ID_Session) ON DELETE CASCADE,
+    CONSTRAINT UQ_Refresh_Token_Hash UNIQUE (Token_Hash)
+);
@@ -0,0 +1,14 @@
+-- Pedidos de redefinição de senha ("esqueci a senha").
+-- Cada token é de uso único, expira em 60 minutos e é guardado apenas como hash SHA-256.
+
+CREATE TABLE IF NOT EXISTS Password_Resets (
+    ID_Password_Reset INT AUTO_INCREMENT PRIMARY KEY,
+    ID_User INT NOT NULL,
+    Reset_Token_Hash CHAR(64) NOT NULL,
+    Reset_Expires_At DATETIME NOT NULL,
+    Reset_Used_At DATETIME NULL,
+    Reset_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_Password_Reset_User FOREIGN KEY (ID_User) REFERENCES Users (ID_User),
+    CONSTRAINT UQ_Password_Reset_Token_Hash UNIQUE (Reset_Token_Hash),
+    INDEX IDX_Password_Reset_User (ID_User)
+);
@@ -0,0 +1,16 @@
+-- Verificação em duas etapas (TOTP, RFC 6238) e códigos de recuperação.
+-- User_Totp_Last_Step guarda o último passo de 30 s aceite, para que um código não seja usado duas vezes.
+
+ALTER TABLE Users
+    ADD COLUMN User_Totp_Secret VARCHAR(64) NULL,
+    ADD COLUMN User_Totp_Enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN User_Totp_Last_Step BIGINT NULL;
+
+CREATE TABLE IF NOT EXISTS User_Recovery_Codes (
+    ID_Recovery_Code INT AUTO_INCREMENT PRIMARY KEY,
+    ID_User INT NOT NULL,
+    Code_Hash CHAR(64) NOT NULL,
+    Code_Used_At DATETIME NULL,
+    CONSTRAINT FK_Recovery_Code_User FOREIGN KEY (ID_User) REFERENCES Users (ID_User),
+    INDEX IDX_Recovery_Code_User (ID_User, Code_Hash)
+);
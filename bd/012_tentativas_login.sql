@@ -0,0 +1,8 @@
+-- Tentativas de login falhadas, por conta e por IP, para atrasos progressivos e bloqueio temporário.
+
+CREATE TABLE IF NOT EXISTS Login_Throttle (
+    Throttle_Key VARCHAR(320) PRIMARY KEY, -- "conta:<email>", "2fa:<id>" ou "ip:<endereço>"
+    Throttle_Failures INT UNSIGNED NOT NULL,
+    Throttle_Last_Failure_At DATETIME NOT NULL,
+    Throttle_Locked_Until DATETIME NULL
+);
@@ -0,0 +1,7 @@
+-- Contas ativas/desativadas, geridas pelos administradores em /admin/users.
+-- O registo público passa a criar apenas clientes; reveja os administradores e técnicos
+-- existentes (SELECT ID_User, User_Email, User_Role FROM Users WHERE User_Role <> 'Cliente'),
+-- pois antes qualquer pessoa podia registar-se como Administrador.
+
+ALTER TABLE Users
+    ADD COLUMN User_Active BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,15 @@
+-- Convites para contas da equipa (técnicos e administradores).
+-- O link enviado leva um token assinado com o ID do convite, válido por 7 dias e de uso único.
+
+CREATE TABLE IF NOT EXISTS Staff_Invitations (
+    ID_Invitation INT AUTO_INCREMENT PRIMARY KEY,
+    Invitation_Email VARCHAR(255) NOT NULL,
+    Invitation_Role VARCHAR(32) NOT NULL,
+    ID_User_Invited_By INT NULL,
+    Invitation_Expires_At DATETIME NOT NULL,
+    Invitation_Accepted_At DATETIME NULL,
+    Invitation_Revoked_At DATETIME NULL,
+    Invitation_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_Invitation_Invited_By FOREIGN KEY (ID_User_Invited_By) REFERENCES Users (ID_User),
+    INDEX IDX_Invitation_Email (Invitation_Email)
+);
@@ -0,0 +1,18 @@
+-- Verificação do email das contas criadas no registo público.
+-- As contas existentes (e as criadas por administradores ou por convite) ficam verificadas;
+-- o registo público grava User_Email_Verified = FALSE até o link enviado ser aberto.
+
+ALTER TABLE Users ADD COLUMN User_Email_Verified BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- Cada token é de uso único, expira em 48 horas e é guardado apenas como hash SHA-256.
+CREATE TABLE IF NOT EXISTS Email_Verifications (
+    ID_Email_Verification INT AUTO_INCREMENT PRIMARY KEY,
+    ID_User INT NOT NULL,
+    Verification_Token_Hash CHAR(64) NOT NULL,
+    Verification_Expires_At DATETIME NOT NULL,
+    Verification_Used_At DATETIME NULL,
+    Verification_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_Email_Verification_User FOREIGN KEY (ID_User) REFERENCES Users (ID_User),
+    CONSTRAINT UQ_Email_Verification_Token_Hash UNIQUE (Verification_Token_Hash),
+    INDEX IDX_Email_Verification_User (ID_User)
+);
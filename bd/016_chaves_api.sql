@@ -0,0 +1,23 @@
+-- Chaves de API para integrações (scripts de monitorização, etc.) e contas de serviço.
+-- As chaves são guardadas apenas como hash SHA-256; o prefixo identifica-as nas listagens.
+
+-- Contas de serviço: utilizadores não humanos, que só acedem com chaves de API
+ALTER TABLE Users ADD COLUMN User_Service_Account BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS Api_Keys (
+    ID_Api_Key INT AUTO_INCREMENT PRIMARY KEY,
+    ID_User INT NOT NULL,
+    Api_Key_Name VARCHAR(100) NOT NULL,
+    Api_Key_Prefix CHAR(12) NOT NULL,
+    Api_Key_Hash CHAR(64) NOT NULL,
+    Api_Key_Scopes VARCHAR(255) NOT NULL, -- separados por espaços, ex.: "tickets:read tickets:write"
+    Api_Key_Expires_At DATETIME NULL, -- NULL = sem expiração
+    Api_Key_Last_Used_At DATETIME NULL,
+    Api_Key_Revoked_At DATETIME NULL,
+    ID_User_Created_By INT NULL,
+    Api_Key_Created_At DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT FK_Api_Key_User FOREIGN KEY (ID_User) REFERENCES Users (ID_User),
+    CONSTRAINT FK_Api_Key_Created_By FOREIGN KEY (ID_User_Created_By) REFERENCES Users (ID_User),
+    CONSTRAINT UQ_Api_Key_Hash UNIQUE (Api_Key_Hash),
+    INDEX IDX_Api_Key_User (ID_User)
+);
@@ -0,0 +1,31 @@
+-- Permissões dos papéis. Cada papel é um conjunto de permissões que os administradores
+-- editam em /admin/roles; os valores abaixo reproduzem o comportamento anterior.
+
+CREATE TABLE IF NOT EXISTS Role_Permissions (
+    User_Role ENUM('Cliente', 'Tecnico', 'Administrador') NOT NULL,
+    Permission VARCHAR(64) NOT NULL,
+    PRIMARY KEY (User_Role, Permission)
+);
+
+INSERT IGNORE INTO Role_Permissions (User_Role, Permission) VALUES
+    ('Cliente', 'ticket.create'),
+
+    ('Tecnico', 'ticket.create'),
+    ('Tecnico', 'ticket.read.all'),
+    ('Tecnico', 'ticket.list.assigned'),
+    ('Tecnico', 'ticket.internal'),
+    ('Tecnico', 'ticket.transition.any'),
+
+    ('Administrador', 'ticket.create'),
+    ('Administrador', 'ticket.read.all'),
+    ('Administrador', 'ticket.list.assigned'),
+    ('Administrador', 'ticket.list.all'),
+    ('Administrador', 'ticket.internal'),
+    ('Administrador', 'ticket.transition.any'),
+    ('Administrador', 'ticket.set_priority'),
+    ('Administrador', 'ticket.assign'),
+    ('Administrador', 'sla.manage'),
+    ('Administrador', 'user.manage'),
+    ('Administrador', 'invitation.manage'),
+    ('Administrador', 'service_account.manage'),
+    ('Administrador', 'role.manage');
@@ -1,18 +1,27 @@
 
 use async_trait::async_trait;
 use axum::{
-    extract::FromRequestParts,
-    http::{header, request::Parts, StatusCode},
-    response::{IntoResponse, Response},
+    extract::{FromRequestParts, OriginalUri, Request, State},
+    http::{header, request::Parts, Method, StatusCode},
+    middleware::Next,
+    response::{Html, IntoResponse, Redirect, Response},
     Json,
 };
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::Serialize;
+use std::str::FromStr;
 use std::sync::Arc;
 
 // Importa as structs Claims e AppState dos seus respectivos módulos.
-use crate::services::auth_service::Claims; 
-use crate::models::appstate::AppState;
+use crate::services::auth_service::{jwt_decode_and_validate, Claims};
+use crate::services::session_cookie::{cookie_value, csrf_token_valid, session_cookie_decode, CSRF_HEADER, SESSION_COOKIE};
+use crate::models::{
+    api_key::{required_scope, scopes_allow, ApiKey, API_KEY_PREFIX},
+    appstate::AppState,
+    permission::{Permission, PermissionSet, RolePermissions},
+    session::UserSession,
+    user::UserRole,
+};
 
 // Estrutura para erros de autenticação retornados como JSON.
 #[derive(Serialize)]
@@ -20,9 +29,41 @@ pub struct AuthError {
     message: String,
 }
 
-// Extractor para obter as Claims de um utilizador autenticado a partir de um JWT.
+// Extractor para obter as Claims de um utilizador autenticado a partir de um JWT
+// ou de uma chave de API (cabeçalho X-API-Key, ou Authorization: Bearer hdk_...).
 pub struct AuthUser(pub Claims);
 
+fn auth_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(AuthError { message: message.to_string() })).into_response()
+}
+
+// Autentica um pedido feito com uma chave de API. A chave só dá acesso às rotas cobertas
+// pelos seus âmbitos, e age com o papel atual do seu dono.
+async fn authenticate_api_key(parts: &Parts, state: &AppState, secret: &str) -> Result<Claims, Response> {
+    let auth = match ApiKey::authenticate(state, secret).await {
+        Ok(Some(auth)) => auth,
+        Ok(None) => return Err(auth_error(StatusCode::UNAUTHORIZED, "Chave de API inválida, expirada ou revogada.")),
+        Err(_) => {
+            // Em produção, logar o erro detalhado.
+            return Err(auth_error(StatusCode::INTERNAL_SERVER_ERROR, "Erro ao validar a chave de API."));
+        }
+    };
+
+    // Dentro de routers aninhados o URI perde o prefixo; o original tem o caminho completo
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.path())
+        .unwrap_or_else(|| parts.uri.path());
+    match required_scope(&parts.method, path) {
+        Some(scope) if scopes_allow(&auth.scopes, &scope) => {
+            Ok(Claims::for_api_key(auth.user_id, &auth.user_role, auth.api_key_id))
+        }
+        Some(scope) => Err(auth_error(StatusCode::FORBIDDEN, &format!("A chave de API não tem o âmbito {}.", scope))),
+        None => Err(auth_error(StatusCode::FORBIDDEN, "Esta rota não aceita chaves de API.")),
+    }
+}
+
 #[async_trait]
 impl FromRequestParts<Arc<AppState>> for AuthUser {
     type Rejection = Response; // O tipo de rejeição é uma Response completa.
@@ -31,6 +72,28 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
         parts: &mut Parts,
         state: &Arc<AppState>,
     ) -> Result<Self, Self::Rejection> {
+        // Pedido já autenticado pelo middleware de permissões da rota
+        if let Some(user) = parts.extensions.get::<Authorized>() {
+            return Ok(AuthUser(user.claims.clone()));
+        }
+
+        // Chave de API, no cabeçalho próprio ou como Bearer com o prefixo das chaves.
+        let api_key = parts
+            .headers
+            .get("x-api-key")
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| {
+                parts
+                    .headers
+                    .get(header::AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("Bearer "))
+                    .filter(|token| token.starts_with(API_KEY_PREFIX))
+            });
+        if let Some(secret) = api_key {
+            return authenticate_api_key(parts, state, secret.trim()).await.map(AuthUser);
+        }
+
         // Tenta obter o valor do cabeçalho Authorization.
         let auth_header_value = parts
             .headers
@@ -52,6 +115,15 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
                 }
             }
             None => {
+                // Sem o cabeçalho, o navegador pode estar autenticado pelo cookie da sessão.
+                if let Some(value) = cookie_value(&parts.headers, SESSION_COOKIE) {
+                    let claims = session_cookie_claims(parts, state, value)
+                        .map_err(|(status, message)| auth_error(status, message))?;
+                    return check_session(state, claims)
+                        .await
+                        .map(AuthUser)
+                        .map_err(|(status, message)| auth_error(status, message));
+                }
                 // Se o cabeçalho Authorization estiver ausente, retorna erro 401.
                 let body = Json(AuthError {
                     message: "Token de autorização ausente.".to_string(),
@@ -61,11 +133,7 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
         };
 
         // Decodifica e valida o token JWT.
-        let token_data = decode::<Claims>(
-            &token_str,
-            &DecodingKey::from_secret(state.jwt_secret.as_ref()),
-            &Validation::default(), // Utiliza validações padrão (algoritmo, expiração).
-        )
+        let claims = jwt_decode_and_validate(&state.jwt_keys, &token_str)
         .map_err(|jwt_error| {
             // Em caso de erro na decodificação/validação, determina a mensagem e retorna 401.
             // Em produção, o 'jwt_error' detalhado deve ser logado.
@@ -81,7 +149,172 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
             (StatusCode::UNAUTHORIZED, body).into_response()
         })?;
 
-        // Se tudo estiver OK, retorna as claims encapsuladas em AuthUser.
-        Ok(AuthUser(token_data.claims))
+        // O token só vale enquanto a sua sessão não for revogada (logout, troca de senha, etc.).
+        check_session(state, claims)
+            .await
+            .map(AuthUser)
+            .map_err(|(status, message)| auth_error(status, message))
+    }
+}
+
+// Extractor do utilizador autenticado com as permissões atuais do seu papel,
+// para os handlers cujo comportamento depende delas (ex.: ver notas internas).
+#[derive(Clone)]
+pub struct Authorized {
+    pub claims: Claims,
+    pub permissions: PermissionSet,
+}
+
+impl Authorized {
+    pub fn can(&self, permission: Permission) -> bool {
+        self.permissions.contains(permission)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for Authorized {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(user) = parts.extensions.get::<Authorized>() {
+            return Ok(user.clone());
+        }
+
+        let AuthUser(claims) = AuthUser::from_request_parts(parts, state).await?;
+        // As permissões são lidas a cada pedido, por isso uma alteração em /admin/roles vale de imediato
+        let permissions = match UserRole::from_str(&claims.role) {
+            Ok(role) => RolePermissions::for_role(state, &role)
+                .await
+                .map_err(|_| auth_error(StatusCode::INTERNAL_SERVER_ERROR, "Erro ao carregar as permissões."))?,
+            Err(_) => PermissionSet::default(),
+        };
+        let user = Authorized { claims, permissions };
+        parts.extensions.insert(user.clone());
+        Ok(user)
+    }
+}
+
+// Middleware que exige uma permissão numa rota:
+//     .route("/users", get(handler).route_layer(middleware::from_fn_with_state((state.clone(), Permission::UserManage), require_permission)))
+// O utilizador autenticado fica no pedido, para os extractors do handler não repetirem a validação.
+pub async fn require_permission(
+    State((state, permission)): State<(Arc<AppState>, Permission)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let user = match Authorized::from_request_parts(&mut parts, &state).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    if !user.can(permission) {
+        return auth_error(StatusCode::FORBIDDEN, &format!("Acesso Negado. Requer a permissão {}.", permission));
+    }
+    next.run(Request::from_parts(parts, body)).await
+}
+
+// Claims do cookie da sessão. Como o navegador envia o cookie em qualquer pedido para o site,
+// os pedidos que alteram dados têm de trazer também o token CSRF no cabeçalho X-CSRF-Token.
+fn session_cookie_claims(parts: &Parts, state: &AppState, value: &str) -> Result<Claims, (StatusCode, &'static str)> {
+    let claims = session_cookie_decode(&state.jwt_secret, value)
+        .ok_or((StatusCode::UNAUTHORIZED, "Sessão expirada. Faça login novamente."))?;
+    let safe_method = matches!(parts.method, Method::GET | Method::HEAD | Method::OPTIONS);
+    if !safe_method {
+        let csrf = parts.headers.get(CSRF_HEADER).and_then(|value| value.to_str().ok()).unwrap_or_default();
+        if !csrf_token_valid(&state.jwt_secret, &claims.sid, csrf) {
+            return Err((StatusCode::FORBIDDEN, "Token CSRF ausente ou inválido."));
+        }
+    }
+    Ok(claims)
+}
+
+// Confirma que a sessão das claims não foi revogada
+async fn check_session(state: &AppState, claims: Claims) -> Result<Claims, (StatusCode, &'static str)> {
+    let user_id = claims.sub.parse::<i32>().unwrap_or_default();
+    match UserSession::is_active(state, &claims.sid, user_id).await {
+        Ok(true) => Ok(claims),
+        Ok(false) => Err((StatusCode::UNAUTHORIZED, "Sessão encerrada. Faça login novamente.")),
+        Err(_) => {
+            // Em produção, logar o erro detalhado.
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Erro ao validar a sessão."))
+        }
+    }
+}
+
+// Extractor das páginas HTML: exige o cookie da sessão e, sem ele (ou com a sessão
+// encerrada), redireciona para o login, que depois volta à página pedida.
+pub struct PageUser(pub Claims);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for PageUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let claims = cookie_value(&parts.headers, SESSION_COOKIE).and_then(|value| session_cookie_decode(&state.jwt_secret, value));
+        let result = match claims {
+            Some(claims) => check_session(state, claims).await,
+            None => Err((StatusCode::UNAUTHORIZED, "")),
+        };
+        match result {
+            Ok(claims) => Ok(PageUser(claims)),
+            Err((StatusCode::UNAUTHORIZED, _)) => {
+                let requested = parts
+                    .extensions
+                    .get::<OriginalUri>()
+                    .map(|uri| uri.0.clone())
+                    .unwrap_or_else(|| parts.uri.clone());
+                let next = requested.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+                Err(Redirect::to(&format!("/login?next={}", utf8_percent_encode(next, NON_ALPHANUMERIC))).into_response())
+            }
+            Err((status, message)) => Err((status, Html(format!("<h1>{}</h1>", message))).into_response()),
+        }
+    }
+}
+
+// Destino do parâmetro "next" do login, aceite apenas se for um caminho deste site.
+// Os navegadores tratam "\" como "/" e ignoram tabs e quebras de linha no URL, por isso
+// "/\evil.com" ou "/\t/evil.com" levariam a outro site.
+pub fn internal_redirect(next: &str) -> Option<&str> {
+    let internal = next.starts_with('/')
+        && !next.starts_with("//")
+        && !next.contains('\\')
+        && !next.chars().any(|c| c.is_control() || c.is_whitespace());
+    internal.then_some(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_redirect_keeps_paths_of_this_site() {
+        assert_eq!(internal_redirect("/"), Some("/"));
+        assert_eq!(internal_redirect("/tickets/5?aba=historico#fim"), Some("/tickets/5?aba=historico#fim"));
+        assert_eq!(internal_redirect("/admin/dashboard?voltar=https://exemplo.pt"), Some("/admin/dashboard?voltar=https://exemplo.pt"));
+    }
+
+    #[test]
+    fn internal_redirect_rejects_other_sites() {
+        for next in [
+            "",
+            "//evil.com",
+            "///evil.com",
+            "https://evil.com",
+            "javascript:alert(1)",
+            "evil.com/login",
+            "/\\evil.com",
+            "\\\\evil.com",
+            "/\t/evil.com",
+            "/\n/evil.com",
+            " //evil.com",
+        ] {
+            assert_eq!(internal_redirect(next), None, "{:?}", next);
+        }
     }
 }
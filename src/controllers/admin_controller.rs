@@ -1,26 +1,50 @@
 use std::sync::Arc;
 use axum::{
-    extract::{Path, State, Json},
+    extract::{Path, Query, State, Json},
     http::StatusCode,
-    response::{Html, IntoResponse},
+    response::{Html, IntoResponse, Redirect, Response},
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use chrono::Utc;
 use serde_json; // Para serde_json::json! e serde_json::to_string
 use tera::Context;
 
 use crate::{
     models::{
         appstate::AppState,
-        ticket::{Ticket, Priority},
-        user::UserRole,
+        ticket::{Ticket, Priority, StatusTicket, TicketListQuery, TicketScope},
+        sla::{SlaPolicy, TicketSla},
+        user::{NewUserPayload, User, UserRole},
+        invitation::Invitation,
+        permission::{Permission, RolePermissions, PERMISSIONS},
+    },
+    auth::{AuthUser, Authorized, PageUser},
+    services::{
+        auth_service::{invite_token_gen, password_hash},
+        notification_service::{notify_ticket_event, send_invitation, TicketEvent},
     },
-    auth::AuthUser,
 };
 
-// Handler para servir a página HTML base do painel de admin
+// Handler para servir a página HTML base do painel de admin (só para quem pode usar o painel)
 pub async fn admin_dashboard_page_handler(
     State(state): State<Arc<AppState>>,
+    PageUser(claims): PageUser, // Protegido pelo cookie da sessão
 ) -> impl IntoResponse {
+    let permissions = match UserRole::from_str(&claims.role) {
+        Ok(role) => match RolePermissions::for_role(&state, &role).await {
+            Ok(permissions) => permissions,
+            Err(_) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Html("<h1>Erro ao carregar as permissões</h1>".to_string())).into_response();
+            }
+        },
+        Err(_) => Default::default(),
+    };
+    if !permissions.contains(Permission::TicketListAssigned) && !permissions.contains(Permission::TicketListAll) {
+        // Os clientes têm a sua própria página inicial
+        return Redirect::to("/new_ticket").into_response();
+    }
+
     let mut context = Context::new();
     context.insert("static_path", "/static");
 
@@ -44,18 +68,84 @@ pub async fn admin_dashboard_page_handler(
     }
 }
 
-// Handler de API para buscar os dados do dashboard do admin
+// Ticket como exibido no dashboard, com os próximos estados permitidos
+// e a situação dos prazos de SLA
+#[derive(Serialize)]
+struct AdminTicketView {
+    #[serde(flatten)]
+    ticket: Ticket,
+    ticket_allowed_transitions: &'static [StatusTicket],
+    ticket_sla: TicketSla,
+}
+
+// Handler de API para buscar os dados do dashboard do admin.
+// Com a permissão ticket.list.all pesquisa em todos os tickets; com ticket.list.assigned,
+// apenas nos atribuídos ao utilizador. Filtros, ordenação e paginação por cursor vêm
+// na query string (ver TicketListQuery).
 pub async fn get_admin_dashboard_data_api(
     State(state): State<Arc<AppState>>,
-    AuthUser(claims): AuthUser, // Protegido
+    user: Authorized, // Protegido
+    Query(query): Query<TicketListQuery>,
 ) -> impl IntoResponse {
-    if claims.role != UserRole::Administrador.to_string() {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": "Acesso Negado. Somente administradores."}))).into_response();
+    let list_all = user.can(Permission::TicketListAll);
+    if !list_all && !user.can(Permission::TicketListAssigned) {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": "Acesso Negado. Somente a equipa de suporte."}))).into_response();
     }
+    let claims = &user.claims;
 
-    match Ticket::get_all_tickets(state.clone()).await {
-        Ok(tickets) => {
-            (StatusCode::OK, Json(serde_json::json!({ "tickets": tickets }))).into_response()
+    let mut filter = match query.parse() {
+        Ok(filter) => filter,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": message}))).into_response();
+        }
+    };
+    let scope = if list_all {
+        TicketScope::All
+    } else {
+        match claims.sub.parse::<i32>() {
+            Ok(user_id) => {
+                filter.assignee_id = None; // O escopo já restringe aos tickets do técnico
+                TicketScope::Assignee(user_id)
+            }
+            Err(_) => {
+                return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "ID de utilizador inválido no token."}))).into_response();
+            }
+        }
+    };
+    let tickets_result = Ticket::search_tickets(state.clone(), scope, &filter).await;
+
+    // A lista da equipa alimenta o seletor de atribuição (apenas com a permissão ticket.assign)
+    let staff = if user.can(Permission::TicketAssign) {
+        match User::get_staff_users(state.clone()).await {
+            Ok(staff) => staff,
+            Err(_) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro ao carregar a equipa"}))).into_response();
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    match tickets_result {
+        Ok(page) => {
+            let now = Utc::now().naive_utc();
+            let tickets: Vec<AdminTicketView> = page
+                .tickets
+                .into_iter()
+                .map(|ticket| AdminTicketView {
+                    ticket_allowed_transitions: ticket.ticket_status.allowed_transitions(),
+                    ticket_sla: TicketSla::evaluate(&ticket, now, &state.calendar),
+                    ticket,
+                })
+                .collect();
+            (StatusCode::OK, Json(serde_json::json!({
+                "tickets": tickets,
+                "total": page.total,
+                "next_cursor": page.next_cursor,
+                "staff": staff,
+                "can_set_priority": user.can(Permission::TicketSetPriority),
+                "can_assign": user.can(Permission::TicketAssign),
+            }))).into_response()
         }
         Err(_) => {
             // Logar o erro 'e'
@@ -77,9 +167,12 @@ pub async fn set_ticket_priority_handler(
     Path(ticket_id): Path<i32>,
     Json(payload): Json<SetPriorityPayload>,
 ) -> impl IntoResponse {
-    if claims.role != UserRole::Administrador.to_string() {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": "Acesso Negado."}))).into_response();
-    }
+    let actor_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "ID de utilizador inválido no token."}))).into_response();
+        }
+    };
 
     // Converte a string de prioridade do payload para o enum Priority
     let new_priority_enum = match payload.priority.as_str() {
@@ -91,9 +184,26 @@ pub async fn set_ticket_priority_handler(
         }
     };
 
-    match Ticket::update_ticket_priority(state, ticket_id, new_priority_enum).await {
+    // Prioridade anterior, para o email de notificação
+    let old_priority = match Ticket::get_ticket_by_id(state.clone(), ticket_id).await {
+        Ok(Some(ticket)) => ticket.ticket_priority,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": "Ticket não encontrado"}))).into_response();
+        }
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar prioridade"}))).into_response();
+        }
+    };
+
+    match Ticket::update_ticket_priority(state.clone(), ticket_id, new_priority_enum, actor_id).await {
         Ok(result) => {
             if result.rows_affected() > 0 {
+                if old_priority != new_priority_enum {
+                    notify_ticket_event(state, ticket_id, actor_id, TicketEvent::PriorityChanged {
+                        old: old_priority,
+                        new: new_priority_enum,
+                    });
+                }
                 (StatusCode::OK, Json(serde_json::json!({"mensagem": "Prioridade atualizada com sucesso"}))).into_response()
             } else {
                 (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": "Ticket não encontrado"}))).into_response()
@@ -105,3 +215,470 @@ pub async fn set_ticket_priority_handler(
         }
     }
 }
+
+// Struct para o payload de atribuição de ticket
+#[derive(Deserialize, Debug)]
+pub struct AssignTicketPayload {
+    assignee_id: Option<i32>, // None remove a atribuição
+}
+
+// Handler para atribuir um ticket a um técnico (POST /admin/tickets/:id/assign)
+pub async fn assign_ticket_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser, // Protegido
+    Path(ticket_id): Path<i32>,
+    Json(payload): Json<AssignTicketPayload>,
+) -> impl IntoResponse {
+    let actor_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "ID de utilizador inválido no token."}))).into_response();
+        }
+    };
+
+    // Só membros da equipa podem ser responsáveis por um ticket
+    if let Some(assignee_id) = payload.assignee_id {
+        match User::get_user_by_id(assignee_id, state.clone()).await {
+            Ok(Some(user)) if user.user_role.is_staff() && user.user_active && !user.user_service_account => {}
+            Ok(_) => {
+                return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "Responsável inválido: deve ser um técnico ou administrador ativo"}))).into_response();
+            }
+            Err(_) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atribuir ticket"}))).into_response();
+            }
+        }
+    }
+
+    match Ticket::assign_ticket(state, ticket_id, payload.assignee_id, actor_id).await {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                (StatusCode::OK, Json(serde_json::json!({"mensagem": "Responsável atualizado com sucesso"}))).into_response()
+            } else {
+                (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": "Ticket não encontrado"}))).into_response()
+            }
+        }
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atribuir ticket"}))).into_response()
+        }
+    }
+}
+
+// Handler de API para listar as políticas de SLA (GET /admin/sla-policies)
+pub async fn list_sla_policies_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+) -> impl IntoResponse {
+    match SlaPolicy::get_all_policies(state).await {
+        Ok(policies) => (StatusCode::OK, Json(serde_json::json!({ "policies": policies }))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro ao carregar políticas de SLA"}))).into_response()
+        }
+    }
+}
+
+// Handler para criar ou atualizar uma política de SLA (POST /admin/sla-policies)
+pub async fn save_sla_policy_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+    Json(payload): Json<SlaPolicy>,
+) -> impl IntoResponse {
+    if payload.policy_response_minutes <= 0
+        || payload.policy_resolution_minutes < payload.policy_response_minutes
+    {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "Metas inválidas: a resolução não pode ser anterior à primeira resposta"}))).into_response();
+    }
+
+    match SlaPolicy::save_policy_in_db(&payload, state).await {
+        Ok(()) => (StatusCode::OK, Json(serde_json::json!({"mensagem": "Política de SLA salva com sucesso"}))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao salvar política de SLA"}))).into_response()
+        }
+    }
+}
+
+// Handler de API para listar os utilizadores (GET /admin/users)
+pub async fn list_users_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+) -> impl IntoResponse {
+    match User::get_all_users(state).await {
+        Ok(users) => (StatusCode::OK, Json(serde_json::json!({ "users": users }))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro ao carregar utilizadores"}))).into_response()
+        }
+    }
+}
+
+// Só se atribui (ou retira) um papel cujas permissões o próprio utilizador já tem,
+// para que a gestão de utilizadores não sirva para ganhar permissões
+pub async fn can_grant_role(state: &AppState, user: &Authorized, role: &UserRole) -> Result<bool, sqlx::Error> {
+    Ok(user.permissions.covers(&RolePermissions::for_role(state, role).await?))
+}
+
+// Handler para um administrador criar um utilizador com qualquer papel (POST /admin/users).
+// É a única forma de criar técnicos e administradores: o registo público cria apenas clientes.
+pub async fn create_user_handler(
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Protegido
+    Json(payload): Json<NewUserPayload>,
+) -> impl IntoResponse {
+    let role = match UserRole::from_str(&payload.user_role) {
+        Ok(role) => role,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": message}))).into_response();
+        }
+    };
+    match can_grant_role(&state, &user, &role).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": "O papel tem permissões que não possui"}))).into_response();
+        }
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao criar utilizador"}))).into_response();
+        }
+    }
+    let user_name = payload.user_name.trim();
+    let user_email = payload.user_email.trim();
+    if user_name.is_empty() || user_email.is_empty() || payload.user_password.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "Nome, e-mail e senha são obrigatórios"}))).into_response();
+    }
+
+    let new_user = User::build_user(
+        user_name.to_string(),
+        user_email.to_string(),
+        password_hash(&payload.user_password),
+        role,
+    );
+    match User::save_user_in_db(&new_user, state).await {
+        Ok(result) => (StatusCode::CREATED, Json(serde_json::json!({
+            "mensagem": "Utilizador criado com sucesso",
+            "user_id": result.last_insert_id(),
+        }))).into_response(),
+        Err(e) => {
+            if e.as_database_error().is_some_and(|db_err| db_err.is_unique_violation()) {
+                return (StatusCode::CONFLICT, Json(serde_json::json!({"erro": "Este e-mail já está registado"}))).into_response();
+            }
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao criar utilizador"}))).into_response()
+        }
+    }
+}
+
+// Struct para o payload de alteração de papel
+#[derive(Deserialize, Debug)]
+pub struct SetRolePayload {
+    role: String, // "Cliente", "Tecnico" ou "Administrador"
+}
+
+// Handler para alterar o papel de um utilizador (POST /admin/users/:id/role)
+pub async fn set_user_role_handler(
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Protegido
+    Path(user_id): Path<i32>,
+    Json(payload): Json<SetRolePayload>,
+) -> impl IntoResponse {
+    // Ninguém altera o próprio papel: quem faz a alteração mantém as suas permissões,
+    // pelo que o sistema nunca fica sem um administrador
+    if user.claims.sub == user_id.to_string() {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "Não pode alterar o seu próprio papel"}))).into_response();
+    }
+
+    let role = match UserRole::from_str(&payload.role) {
+        Ok(role) => role,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": message}))).into_response();
+        }
+    };
+
+    // O papel atual e o novo não podem ter permissões que o autor da alteração não tem
+    let current_role = match User::get_user_by_id(user_id, state.clone()).await {
+        Ok(Some(target)) => target.user_role,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": "Utilizador não encontrado"}))).into_response();
+        }
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar papel"}))).into_response();
+        }
+    };
+    for checked in [current_role, role] {
+        match can_grant_role(&state, &user, &checked).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": "O papel tem permissões que não possui"}))).into_response();
+            }
+            Err(_) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar papel"}))).into_response();
+            }
+        }
+    }
+
+    match User::update_user_role(state, user_id, role).await {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                (StatusCode::OK, Json(serde_json::json!({"mensagem": "Papel atualizado com sucesso"}))).into_response()
+            } else {
+                (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": "Utilizador não encontrado"}))).into_response()
+            }
+        }
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar papel"}))).into_response()
+        }
+    }
+}
+
+// Handler para desativar uma conta (POST /admin/users/:id/deactivate).
+// A conta deixa de conseguir entrar e as sessões abertas são encerradas de imediato.
+pub async fn deactivate_user_handler(
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Protegido
+    Path(user_id): Path<i32>,
+) -> impl IntoResponse {
+    set_user_active(state, user, user_id, false).await
+}
+
+// Handler para reativar uma conta desativada (POST /admin/users/:id/activate)
+pub async fn activate_user_handler(
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Protegido
+    Path(user_id): Path<i32>,
+) -> impl IntoResponse {
+    set_user_active(state, user, user_id, true).await
+}
+
+async fn set_user_active(
+    state: Arc<AppState>,
+    user: Authorized,
+    user_id: i32,
+    active: bool,
+) -> Response {
+    if user.claims.sub == user_id.to_string() {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "Não pode desativar a sua própria conta"}))).into_response();
+    }
+
+    // Só se ativa ou desativa contas cujo papel não tem permissões além das do autor
+    let target_role = match User::get_user_by_id(user_id, state.clone()).await {
+        Ok(Some(target)) => target.user_role,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": "Utilizador não encontrado"}))).into_response();
+        }
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar a conta"}))).into_response();
+        }
+    };
+    match can_grant_role(&state, &user, &target_role).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": "O papel da conta tem permissões que não possui"}))).into_response();
+        }
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar a conta"}))).into_response();
+        }
+    }
+
+    match User::set_user_active(state, user_id, active).await {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                let message = if active { "Conta reativada com sucesso" } else { "Conta desativada com sucesso" };
+                (StatusCode::OK, Json(serde_json::json!({"mensagem": message}))).into_response()
+            } else {
+                (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": "Utilizador não encontrado"}))).into_response()
+            }
+        }
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar a conta"}))).into_response()
+        }
+    }
+}
+
+// Struct para o payload de um convite
+#[derive(Deserialize, Debug)]
+pub struct InvitationPayload {
+    email: String,
+    role: String, // "Tecnico" ou "Administrador"
+}
+
+// Handler para convidar um membro da equipa (POST /admin/invitations).
+// O convidado recebe por email um link assinado e escolhe o nome e a senha em /register/invite.
+pub async fn create_invitation_handler(
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Protegido
+    Json(payload): Json<InvitationPayload>,
+) -> impl IntoResponse {
+    let actor_id = match user.claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "ID de utilizador inválido no token."}))).into_response();
+        }
+    };
+
+    let role = match UserRole::from_str(&payload.role) {
+        Ok(role) if role.is_staff() => role,
+        _ => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "Papel inválido: os convites são para técnicos ou administradores"}))).into_response();
+        }
+    };
+    match can_grant_role(&state, &user, &role).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": "O papel tem permissões que não possui"}))).into_response();
+        }
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao criar convite"}))).into_response();
+        }
+    }
+    let email = payload.email.trim().to_lowercase();
+    if !email.contains('@') {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": "E-mail inválido"}))).into_response();
+    }
+
+    match User::get_user_by_email(email.clone(), state.clone()).await {
+        Ok(_) => {
+            return (StatusCode::CONFLICT, Json(serde_json::json!({"erro": "Este e-mail já tem conta; altere o papel em /admin/users"}))).into_response();
+        }
+        Err(sqlx::Error::RowNotFound) => {}
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao criar convite"}))).into_response();
+        }
+    }
+
+    match Invitation::create(state.clone(), &email, role, actor_id).await {
+        Ok(invitation) => {
+            let token = invite_token_gen(&state.jwt_secret, invitation.invitation_id, invitation.invitation_expires_at.and_utc());
+            let invite_path = format!("/register/invite?token={}", token);
+            let email_sent = state.mailer.is_some();
+            send_invitation(state.clone(), &invitation, invite_path.clone());
+            // O link também é devolvido, para ser partilhado manualmente se o email não estiver configurado
+            (StatusCode::CREATED, Json(serde_json::json!({
+                "mensagem": if email_sent { "Convite enviado com sucesso" } else { "Convite criado; o envio de emails não está configurado" },
+                "invitation": invitation,
+                "invite_path": invite_path,
+                "email_sent": email_sent,
+            }))).into_response()
+        }
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao criar convite"}))).into_response()
+        }
+    }
+}
+
+// Handler de API para listar os convites pendentes (GET /admin/invitations)
+pub async fn list_invitations_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+) -> impl IntoResponse {
+    match Invitation::get_pending(state).await {
+        Ok(invitations) => (StatusCode::OK, Json(serde_json::json!({ "invitations": invitations }))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro ao carregar convites"}))).into_response()
+        }
+    }
+}
+
+// Handler para revogar um convite pendente (POST /admin/invitations/:id/revoke)
+pub async fn revoke_invitation_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+    Path(invitation_id): Path<i32>,
+) -> impl IntoResponse {
+    match Invitation::revoke(state, invitation_id).await {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                (StatusCode::OK, Json(serde_json::json!({"mensagem": "Convite revogado com sucesso"}))).into_response()
+            } else {
+                (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": "Convite não encontrado ou já utilizado"}))).into_response()
+            }
+        }
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao revogar convite"}))).into_response()
+        }
+    }
+}
+
+// Handler de API para listar os papéis e as suas permissões (GET /admin/roles)
+pub async fn list_roles_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+) -> impl IntoResponse {
+    let permissions: Vec<serde_json::Value> = PERMISSIONS
+        .iter()
+        .map(|permission| serde_json::json!({"permission": permission, "description": permission.description()}))
+        .collect();
+
+    match RolePermissions::get_all(&state).await {
+        Ok(roles) => (StatusCode::OK, Json(serde_json::json!({ "roles": roles, "permissions": permissions }))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro ao carregar os papéis"}))).into_response()
+        }
+    }
+}
+
+// Struct para o payload da definição de um papel
+#[derive(Deserialize, Debug)]
+pub struct RolePermissionsPayload {
+    permissions: Vec<String>, // Ex.: ["ticket.create", "ticket.read.all"]
+}
+
+// Handler para substituir as permissões de um papel (POST /admin/roles/:role/permissions).
+// A alteração vale no pedido seguinte de cada utilizador com esse papel.
+pub async fn set_role_permissions_handler(
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Protegido
+    Path(role): Path<String>,
+    Json(payload): Json<RolePermissionsPayload>,
+) -> impl IntoResponse {
+    let role = match UserRole::from_str(&role) {
+        Ok(role) => role,
+        Err(message) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({"erro": message}))).into_response();
+        }
+    };
+
+    let mut permissions: Vec<Permission> = Vec::new();
+    for value in payload.permissions.iter().map(|value| value.trim()) {
+        match Permission::from_str(value) {
+            Ok(permission) if !permissions.contains(&permission) => permissions.push(permission),
+            Ok(_) => {}
+            Err(message) => {
+                return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"erro": message}))).into_response();
+            }
+        }
+    }
+
+    // O próprio papel não é editável: nem para ganhar permissões, nem para perder role.manage por engano
+    if user.claims.role == role.to_string() {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": "Não pode alterar as permissões do seu próprio papel"}))).into_response();
+    }
+
+    // Só se acrescenta a outro papel uma permissão que o próprio utilizador já tem
+    let current = match RolePermissions::for_role(&state, &role).await {
+        Ok(current) => current,
+        Err(_) => {
+            // Logar o erro 'e'
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar o papel"}))).into_response();
+        }
+    };
+    if let Some(permission) = permissions.iter().find(|p| !current.contains(**p) && !user.can(**p)) {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"erro": format!("Não pode conceder a permissão {}, que não possui", permission)}))).into_response();
+    }
+
+    match RolePermissions::replace(&state, &role, &permissions).await {
+        Ok(()) => (StatusCode::OK, Json(serde_json::json!({
+            "mensagem": "Permissões do papel atualizadas com sucesso",
+            "role": RolePermissions { role, permissions },
+        }))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"erro": "Erro interno ao atualizar o papel"}))).into_response()
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::sync::Arc;
+use axum::{
+    extract::{Path, State, Json},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use std::str::FromStr;
+
+use crate::{
+    models::{
+        appstate::AppState,
+        api_key::{ApiKey, API_SCOPES},
+        permission::RolePermissions,
+        user::{User, UserRole},
+    },
+    auth::{AuthUser, Authorized},
+    controllers::admin_controller::can_grant_role,
+    services::auth_service::{generate_opaque_token, password_hash},
+};
+
+// Validade máxima de uma chave com expiração
+const MAX_API_KEY_DAYS: i64 = 3650;
+
+// Struct para o payload de criação de uma chave de API
+#[derive(Deserialize, Debug)]
+pub struct NewApiKeyPayload {
+    name: String,
+    scopes: Vec<String>, // Ex.: ["tickets:write"]
+    expires_in_days: Option<i64>, // Sem valor: a chave não expira
+}
+
+// Struct para o payload de criação de uma conta de serviço
+#[derive(Deserialize, Debug)]
+pub struct ServiceAccountPayload {
+    name: String,
+    email: String, // Contacto do responsável pela integração
+    role: String,
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({"erro": message}))).into_response()
+}
+
+fn invalid_actor() -> Response {
+    error_response(StatusCode::BAD_REQUEST, "ID de utilizador inválido no token.")
+}
+
+// Cria a chave depois de validar o nome, os âmbitos (os de admin só para papéis com
+// permissões da área /admin) e a validade
+async fn create_key_for(state: Arc<AppState>, user_id: i32, role: &UserRole, payload: NewApiKeyPayload, created_by: i32) -> Response {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "O nome da chave é obrigatório");
+    }
+
+    let admin_area = match RolePermissions::for_role(&state, role).await {
+        Ok(permissions) => permissions.has_admin_area(),
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Erro interno ao criar a chave"),
+    };
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in payload.scopes.iter().map(|scope| scope.trim()) {
+        if !API_SCOPES.contains(&scope) {
+            return error_response(StatusCode::BAD_REQUEST, &format!("Âmbito inválido: {}", scope));
+        }
+        if scope.starts_with("admin:") && !admin_area {
+            return error_response(StatusCode::FORBIDDEN, "Só papéis com permissões de administração podem ter chaves com âmbitos de admin");
+        }
+        if !scopes.iter().any(|s| s == scope) {
+            scopes.push(scope.to_string());
+        }
+    }
+    if scopes.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "Indique pelo menos um âmbito");
+    }
+
+    let expires_at = match payload.expires_in_days {
+        None => None,
+        Some(days) if (1..=MAX_API_KEY_DAYS).contains(&days) => Some((Utc::now() + Duration::days(days)).naive_utc()),
+        Some(_) => {
+            return error_response(StatusCode::BAD_REQUEST, &format!("A validade deve estar entre 1 e {} dias", MAX_API_KEY_DAYS));
+        }
+    };
+
+    match ApiKey::create(state, user_id, name, &scopes, expires_at, created_by).await {
+        // O segredo não é guardado (só o hash): esta é a única vez que é mostrado
+        Ok((api_key, secret)) => (StatusCode::CREATED, Json(serde_json::json!({
+            "mensagem": "Chave criada. Guarde-a agora: não voltará a ser mostrada.",
+            "api_key": api_key,
+            "secret": secret,
+        }))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Erro interno ao criar a chave")
+        }
+    }
+}
+
+async fn list_keys_for(state: Arc<AppState>, user_id: i32) -> Response {
+    match ApiKey::list_by_user(state, user_id).await {
+        Ok(api_keys) => (StatusCode::OK, Json(serde_json::json!({ "api_keys": api_keys }))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Erro ao carregar as chaves")
+        }
+    }
+}
+
+async fn revoke_key(state: Arc<AppState>, api_key_id: i32, owner_id: Option<i32>) -> Response {
+    match ApiKey::revoke(state, api_key_id, owner_id).await {
+        Ok(result) if result.rows_affected() > 0 => {
+            (StatusCode::OK, Json(serde_json::json!({"mensagem": "Chave revogada com sucesso"}))).into_response()
+        }
+        Ok(_) => error_response(StatusCode::NOT_FOUND, "Chave não encontrada ou já revogada"),
+        Err(_) => {
+            // Logar o erro 'e'
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Erro interno ao revogar a chave")
+        }
+    }
+}
+
+// Handler de API para listar as chaves do próprio utilizador (GET /api-keys)
+pub async fn list_my_api_keys_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser, // Protegido
+) -> impl IntoResponse {
+    match claims.sub.parse::<i32>() {
+        Ok(user_id) => list_keys_for(state, user_id).await,
+        Err(_) => invalid_actor(),
+    }
+}
+
+// Handler para criar uma chave pessoal (POST /api-keys), com os âmbitos permitidos ao papel do utilizador
+pub async fn create_my_api_key_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser, // Protegido
+    Json(payload): Json<NewApiKeyPayload>,
+) -> impl IntoResponse {
+    let Ok(user_id) = claims.sub.parse::<i32>() else {
+        return invalid_actor();
+    };
+    let role = match UserRole::from_str(&claims.role) {
+        Ok(role) => role,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+    };
+    create_key_for(state, user_id, &role, payload, user_id).await
+}
+
+// Handler para revogar uma chave pessoal (POST /api-keys/:id/revoke)
+pub async fn revoke_my_api_key_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser, // Protegido
+    Path(api_key_id): Path<i32>,
+) -> impl IntoResponse {
+    match claims.sub.parse::<i32>() {
+        Ok(user_id) => revoke_key(state, api_key_id, Some(user_id)).await,
+        Err(_) => invalid_actor(),
+    }
+}
+
+// Handler de API para listar as contas de serviço (GET /admin/service-accounts)
+pub async fn list_service_accounts_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+) -> impl IntoResponse {
+    match User::get_service_accounts(state).await {
+        Ok(accounts) => (StatusCode::OK, Json(serde_json::json!({ "service_accounts": accounts }))).into_response(),
+        Err(_) => {
+            // Logar o erro 'e'
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Erro ao carregar as contas de serviço")
+        }
+    }
+}
+
+// Handler para criar uma conta de serviço (POST /admin/service-accounts).
+// A conta não tem senha utilizável: só acede com as chaves criadas pelos administradores.
+pub async fn create_service_account_handler(
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Protegido
+    Json(payload): Json<ServiceAccountPayload>,
+) -> impl IntoResponse {
+    let role = match UserRole::from_str(&payload.role) {
+        Ok(role) => role,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+    };
+    match can_grant_role(&state, &user, &role).await {
+        Ok(true) => {}
+        Ok(false) => return error_response(StatusCode::FORBIDDEN, "O papel tem permissões que não possui"),
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Erro interno ao criar a conta de serviço"),
+    }
+    let name = payload.name.trim();
+    let email = payload.email.trim().to_lowercase();
+    if name.is_empty() || !email.contains('@') {
+        return error_response(StatusCode::BAD_REQUEST, "Nome e e-mail de contacto são obrigatórios");
+    }
+
+    let mut account = User::build_user(name.to_string(), email, password_hash(&generate_opaque_token()), role);
+    account.user_service_account = true;
+    match User::save_user_in_db(&account, state).await {
+        Ok(result) => (StatusCode::CREATED, Json(serde_json::json!({
+            "mensagem": "Conta de serviço criada com sucesso",
+            "user_id": result.last_insert_id(),
+        }))).into_response(),
+        Err(e) => {
+            if e.as_database_error().is_some_and(|db_err| db_err.is_unique_violation()) {
+                return error_response(StatusCode::CONFLICT, "Este e-mail já está registado");
+            }
+            // Logar o erro 'e'
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Erro interno ao criar a conta de serviço")
+        }
+    }
+}
+
+// Carrega a conta de serviço indicada no caminho, ou a resposta de erro
+async fn service_account(state: &Arc<AppState>, user_id: i32) -> Result<User, Response> {
+    match User::get_user_by_id(user_id, state.clone()).await {
+        Ok(Some(user)) if user.user_service_account => Ok(user),
+        Ok(_) => Err(error_response(StatusCode::NOT_FOUND, "Conta de serviço não encontrada")),
+        Err(_) => Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Erro ao carregar a conta de serviço")),
+    }
+}
+
+// Handler de API para listar as chaves de uma conta de serviço (GET /admin/service-accounts/:id/api-keys)
+pub async fn list_service_account_keys_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+    Path(user_id): Path<i32>,
+) -> impl IntoResponse {
+    match service_account(&state, user_id).await {
+        Ok(_) => list_keys_for(state, user_id).await,
+        Err(response) => response,
+    }
+}
+
+// Handler para criar uma chave para uma conta de serviço (POST /admin/service-accounts/:id/api-keys)
+pub async fn create_service_account_key_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser, // Protegido
+    Path(user_id): Path<i32>,
+    Json(payload): Json<NewApiKeyPayload>,
+) -> impl IntoResponse {
+    let account = match service_account(&state, user_id).await {
+        Ok(account) => account,
+        Err(response) => return response,
+    };
+    let Ok(created_by) = claims.sub.parse::<i32>() else {
+        return invalid_actor();
+    };
+    create_key_for(state, user_id, &account.user_role, payload, created_by).await
+}
+
+// Handler para revogar qualquer chave, de uma pessoa ou de uma conta de serviço (POST /admin/api-keys/:id/revoke)
+pub async fn revoke_api_key_handler(
+    State(state): State<Arc<AppState>>,
+    AuthUser(_claims): AuthUser, // Protegido
+    Path(api_key_id): Path<i32>,
+) -> impl IntoResponse {
+    revoke_key(state, api_key_id, None).await
+}
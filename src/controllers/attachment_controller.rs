@@ -0,0 +1,82 @@
+use std::sync::Arc;
+use axum::{
+    extract::{Path, State, Json},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::json; // Para a macro json!
+
+use crate::models::{
+    appstate::AppState,
+    attachment::Attachment,
+    permission::Permission,
+};
+use crate::auth::Authorized; // Extractor para utilizador autenticado, com as permissões
+use crate::controllers::ticket_controller::load_visible_ticket;
+
+// Handler para listar os anexos de um ticket (GET /tickets/:id/attachments)
+pub async fn list_attachments(
+    Path(ticket_id): Path<i32>,
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Requer autenticação
+) -> impl IntoResponse {
+    if let Err(response) = load_visible_ticket(state.clone(), ticket_id, &user).await {
+        return response;
+    }
+
+    // Anexos de notas internas só são enviados a quem tem a permissão ticket.internal
+    match Attachment::get_attachments_by_ticket(state, ticket_id, user.can(Permission::TicketInternal)).await {
+        Ok(attachments) => (StatusCode::OK, Json(json!({ "attachments": attachments }))).into_response(),
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao carregar anexos"}))).into_response()
+        }
+    }
+}
+
+// Handler para descarregar um anexo (GET /tickets/:id/attachments/:attachment_id)
+pub async fn download_attachment(
+    Path((ticket_id, attachment_id)): Path<(i32, i32)>,
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Requer autenticação
+) -> Response {
+    if let Err(response) = load_visible_ticket(state.clone(), ticket_id, &user).await {
+        return response;
+    }
+
+    let attachment = match Attachment::get_attachment(state.clone(), ticket_id, attachment_id, user.can(Permission::TicketInternal)).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"erro": "Anexo não encontrado"}))).into_response();
+        }
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao buscar anexo"}))).into_response();
+        }
+    };
+
+    let bytes = match state.attachment_storage.get(&attachment.attachment_storage_key).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao ler anexo"}))).into_response();
+        }
+    };
+
+    // Aspas e caracteres fora de ASCII são trocados para não quebrar o cabeçalho
+    let safe_name: String = attachment
+        .attachment_file_name
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' { c } else { '_' })
+        .collect();
+
+    (
+        [
+            (header::CONTENT_TYPE, attachment.attachment_mime_type),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", safe_name)),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        bytes,
+    )
+        .into_response()
+}
@@ -1,11 +1,24 @@
+use std::future::Future;
+use std::net::IpAddr;
 use std::sync::Arc;
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
+use axum::response::{AppendHeaders, IntoResponse, Response};
+use chrono::Utc;
 use axum::{extract::State, Json};
 use serde::{Deserialize, Serialize};
 
+use crate::auth::AuthUser;
 use crate::models::appstate::AppState;
-use crate::models::user::User;
-use crate::services::auth_service::{jwt_gen, password_verify};
+use crate::models::login_throttle::{LoginThrottle, ThrottlePolicy, ThrottledAttempt, ACCOUNT_POLICY, IP_POLICY};
+use crate::models::session::{RefreshOutcome, UserSession};
+use crate::models::two_factor::TwoFactor;
+use crate::models::user::{User, UserRole};
+use crate::services::auth_service::{
+    jwt_gen, mfa_token_decode, mfa_token_gen, ACCESS_TOKEN_MINUTES,
+};
+use crate::services::auth_provider::authenticate;
+use crate::services::totp;
+use crate::rate_limit::{too_many_requests, ClientIp};
 
 #[derive(Debug, Deserialize)]
 pub struct LoginData {
@@ -15,68 +28,538 @@ pub struct LoginData {
 
 #[derive(Debug, Serialize)]
 pub struct TokenResponse {
-    pub token: String,
+    pub token: String,         // Access token (JWT de curta duração)
+    pub refresh_token: String, // Troca-se em POST /login/refresh por um novo par de tokens
+    pub expires_in: i64,       // Validade do access token, em segundos
     pub role: String,
 }
 
+// Resposta do login: os tokens, ou o pedido da segunda etapa quando a conta usa
+// (ou é obrigada a configurar) a verificação em duas etapas.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Tokens(TokenResponse),
+    SecondFactor(MfaChallenge),
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaChallenge {
+    pub mfa_token: String,         // Token intermédio, válido por 5 minutos
+    pub mfa_setup_required: bool,  // true: a conta ainda tem de configurar a verificação
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecondFactorData {
+    pub mfa_token: String,
+    pub code: String, // Código TOTP de 6 dígitos ou código de recuperação
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorSetupData {
+    pub mfa_token: Option<String>, // Usado na configuração obrigatória, durante o login
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorEnableData {
+    pub code: String,
+    pub mfa_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodeData {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub required: bool, // Obrigatória para o papel do utilizador (não pode ser desativada)
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetup {
+    pub secret: String,
+    pub provisioning_uri: String,
+    pub qr_svg: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorEnabled {
+    pub recovery_codes: Vec<String>, // Mostrados apenas desta vez
+    #[serde(flatten)]
+    pub tokens: Option<TokenResponse>, // Sessão iniciada, quando a ativação concluiu o login
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshData {
+    pub refresh_token: String,
+}
+
 #[derive(Serialize)]
 pub struct ErrorMessage {
     error: String,
 }
 
+// Login (POST /login). As falhas contam para a conta tentada e para o IP do cliente;
+// depois de algumas, cada nova tentativa tem de esperar cada vez mais (429 com Retry-After).
 pub async fn login(
     State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
     Json(data): Json<LoginData>,
-) -> Result<Json<TokenResponse>, (StatusCode, Json<ErrorMessage>)> {
-    let user_result = User::get_user_by_email(data.email.clone(), state.clone()).await;
+) -> Response {
+    let account_key = format!("conta:{}", data.email.trim().to_lowercase());
+    throttled(&state, account_key, ip, check_credentials(&state, &data)).await
+}
+
+async fn check_credentials(
+    state: &Arc<AppState>,
+    data: &LoginData,
+) -> Result<Json<LoginResponse>, (StatusCode, Json<ErrorMessage>)> {
+    // A senha é verificada pelos fornecedores configurados (AUTH_PROVIDERS: local, LDAP)
+    let user_result = authenticate(state, data.email.trim(), &data.password).await;
 
     match user_result {
-        Ok(user) => {
-            if password_verify(&data.password, &user.user_password) {
-                let user_id = match user.user_id {
-                    Some(id) => id,
-                    None => {
-                        // Em produção, logar este erro criticamente.
-                        return Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(ErrorMessage {
-                                error: "Erro interno ao processar informações do utilizador.".to_string(),
-                            }),
-                        ));
-                    }
-                };
-
-                let token = jwt_gen(&state.jwt_secret, user_id, &user.user_role);
-                Ok(Json(TokenResponse {
-                    token,
-                    role: user.user_role.to_string(),
-                }))
-            } else {
-                Err((
-                    StatusCode::UNAUTHORIZED,
-                    Json(ErrorMessage {
-                        error: "Credenciais inválidas".to_string(),
-                    }),
-                ))
+        Ok(Some(user)) => {
+            let user_id = match user.user_id {
+                Some(id) => id,
+                None => {
+                    // Em produção, logar este erro criticamente.
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ErrorMessage {
+                            error: "Erro interno ao processar informações do utilizador.".to_string(),
+                        }),
+                    ));
+                }
+            };
+
+            // Contas de serviço (ex.: associadas pelo LDAP) não entram com senha
+            if !user.user_active || user.user_service_account {
+                return Err(account_disabled());
             }
-        }
-        Err(err) => {
-            // Em produção, logar o 'err' detalhado.
-            if matches!(err, sqlx::Error::RowNotFound) {
-                 Err((
-                    StatusCode::UNAUTHORIZED, // Ou NOT_FOUND, mas UNAUTHORIZED é comum para falha de login
-                    Json(ErrorMessage {
-                        error: "Utilizador não encontrado ou credenciais inválidas.".to_string(),
-                    }),
-                ))
-            } else {
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorMessage {
-                        error: "Erro interno no servidor ao tentar login.".to_string(),
-                    }),
-                ))
+            if !user.user_email_verified {
+                return Err(email_not_verified());
+            }
+
+            // Com a verificação em duas etapas, a senha só dá acesso à segunda etapa.
+            let two_factor = TwoFactor::get_by_user(state.clone(), user_id)
+                .await
+                .map_err(|_| internal_error("Erro interno no servidor ao tentar login."))?;
+            let setup_required = !two_factor.enabled && is_two_factor_required(state, &user.user_role);
+            if two_factor.enabled || setup_required {
+                return Ok(Json(LoginResponse::SecondFactor(MfaChallenge {
+                    mfa_token: mfa_token_gen(&state.jwt_secret, user_id),
+                    mfa_setup_required: setup_required,
+                })));
             }
+
+            let tokens = start_session(state, user_id, &user.user_role).await?;
+            Ok(Json(LoginResponse::Tokens(tokens)))
+        }
+        Ok(None) => Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorMessage {
+                error: "Credenciais inválidas".to_string(),
+            }),
+        )),
+        Err(_) => {
+            // Em produção, logar o erro 'e' (indica o fornecedor que falhou).
+            // A senha pode estar certa num fornecedor indisponível.
+            Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorMessage {
+                    error: "Não foi possível verificar as credenciais. Tente novamente mais tarde.".to_string(),
+                }),
+            ))
+        }
+    }
+}
+
+// Troca um refresh token por um novo access token e um novo refresh token (POST /login/refresh).
+// O papel é lido de novo do banco, para que mudanças de papel valham na renovação seguinte.
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(data): Json<RefreshData>,
+) -> Result<Json<TokenResponse>, (StatusCode, Json<ErrorMessage>)> {
+    let outcome = UserSession::rotate_refresh_token(state.clone(), &data.refresh_token)
+        .await
+        .map_err(|_| internal_error("Erro interno ao renovar a sessão."))?;
+
+    match outcome {
+        RefreshOutcome::Rotated { user_id, session_id, refresh_token } => {
+            let user = match User::get_user_by_id(user_id, state.clone()).await {
+                Ok(Some(user)) if user.user_active => user,
+                Ok(_) => return Err(session_expired()),
+                Err(_) => return Err(internal_error("Erro interno ao renovar a sessão.")),
+            };
+            Ok(Json(token_response(&state, user_id, &user.user_role, &session_id, refresh_token)))
+        }
+        RefreshOutcome::Invalid | RefreshOutcome::Reused => Err(session_expired()),
+    }
+}
+
+// Abre a sessão do navegador (POST /login/session): com o access token acabado de obter,
+// o login.js recebe os cookies da mesma sessão, que dão acesso às páginas protegidas.
+pub async fn open_browser_session(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+) -> impl IntoResponse {
+    (AppendHeaders(state.session_cookies.issue(&state.jwt_secret, &claims).map(|cookie| (header::SET_COOKIE, cookie))), StatusCode::NO_CONTENT)
+}
+
+// Encerra a sessão no servidor (POST /logout). Aceita o access token, o cookie da sessão,
+// o refresh token (no corpo) ou vários, para que o logout funcione mesmo com o access token
+// já expirado. Os cookies da sessão do navegador são apagados, exceto se o pedido vier
+// pelo cookie sem o token CSRF (403).
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    auth_user: Result<AuthUser, Response>,
+    data: Option<Json<RefreshData>>,
+) -> Result<impl IntoResponse, Response> {
+    match auth_user {
+        Ok(AuthUser(claims)) => {
+            UserSession::revoke_session(state.clone(), &claims.sid)
+                .await
+                .map_err(|_| internal_error("Erro interno ao encerrar a sessão.").into_response())?;
         }
+        // Sem o token CSRF o pedido pode vir de outro site: a sessão e os cookies ficam como estão
+        Err(rejection) if rejection.status() == StatusCode::FORBIDDEN => return Err(rejection),
+        Err(_) => {} // Sessão já expirada ou encerrada: basta apagar os cookies
+    }
+    if let Some(Json(data)) = data {
+        UserSession::revoke_by_refresh_token(state.clone(), &data.refresh_token)
+            .await
+            .map_err(|_| internal_error("Erro interno ao encerrar a sessão.").into_response())?;
+    }
+    let cookies = state.session_cookies.clear().map(|cookie| (header::SET_COOKIE, cookie));
+    Ok((AppendHeaders(cookies), StatusCode::NO_CONTENT))
+}
+
+// Segunda etapa do login (POST /login/2fa): troca o token intermédio e um código válido
+// pelos tokens da sessão.
+pub async fn verify_second_factor(
+    State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    Json(data): Json<SecondFactorData>,
+) -> Response {
+    let Some(user_id) = mfa_token_decode(&state.jwt_secret, &data.mfa_token) else {
+        return mfa_token_expired().into_response();
+    };
+    let account_key = format!("2fa:{}", user_id);
+    throttled(&state, account_key, ip, check_second_factor(&state, user_id, &data.code)).await
+}
+
+async fn check_second_factor(
+    state: &Arc<AppState>,
+    user_id: i32,
+    code: &str,
+) -> Result<Json<TokenResponse>, (StatusCode, Json<ErrorMessage>)> {
+    let user = load_user(state, user_id).await?;
+    let two_factor = TwoFactor::get_by_user(state.clone(), user_id)
+        .await
+        .map_err(|_| internal_error("Erro interno ao validar o código."))?;
+    if !two_factor.enabled {
+        return Err(bad_request("A verificação em duas etapas não está ativa nesta conta."));
     }
+    if !check_code(state, user_id, &two_factor, code).await? {
+        return Err(invalid_code());
+    }
+
+    Ok(Json(start_session(state, user_id, &user.user_role).await?))
+}
+
+// Estado da verificação em duas etapas do utilizador autenticado (GET /login/2fa)
+pub async fn two_factor_status(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Json<TwoFactorStatus>, (StatusCode, Json<ErrorMessage>)> {
+    let user_id = parse_user_id(&claims.sub)?;
+    let user = load_user(&state, user_id).await?;
+    let two_factor = TwoFactor::get_by_user(state.clone(), user_id)
+        .await
+        .map_err(|_| internal_error("Erro interno ao consultar a verificação em duas etapas."))?;
+    Ok(Json(TwoFactorStatus {
+        enabled: two_factor.enabled,
+        required: is_two_factor_required(&state, &user.user_role),
+    }))
+}
+
+// Inicia a configuração (POST /login/2fa/setup): gera um segredo novo e devolve a URI
+// otpauth:// e o QR code para a aplicação autenticadora. Só fica ativa depois de confirmada.
+pub async fn setup_two_factor(
+    State(state): State<Arc<AppState>>,
+    auth_user: Option<AuthUser>,
+    data: Option<Json<TwoFactorSetupData>>,
+) -> Result<Json<TwoFactorSetup>, (StatusCode, Json<ErrorMessage>)> {
+    let mfa_token = data.and_then(|Json(data)| data.mfa_token);
+    let user_id = identify(&state, auth_user, mfa_token.as_deref())?;
+    let user = load_user(&state, user_id).await?;
+    let two_factor = TwoFactor::get_by_user(state.clone(), user_id)
+        .await
+        .map_err(|_| internal_error("Erro interno ao configurar a verificação em duas etapas."))?;
+    if two_factor.enabled {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorMessage {
+                error: "A verificação em duas etapas já está ativa.".to_string(),
+            }),
+        ));
+    }
+
+    let secret = totp::generate_secret();
+    let provisioning_uri = totp::provisioning_uri(&state.totp.issuer, &user.user_email, &secret);
+    let qr_svg = totp::qr_svg(&provisioning_uri)
+        .map_err(|_| internal_error("Erro interno ao gerar o QR code."))?;
+    TwoFactor::save_pending_secret(state.clone(), user_id, &secret)
+        .await
+        .map_err(|_| internal_error("Erro interno ao configurar a verificação em duas etapas."))?;
+
+    Ok(Json(TwoFactorSetup { secret, provisioning_uri, qr_svg }))
+}
+
+// Confirma a configuração com um código da aplicação (POST /login/2fa/enable) e devolve os
+// códigos de recuperação. Na configuração obrigatória durante o login, inicia também a sessão.
+pub async fn enable_two_factor(
+    State(state): State<Arc<AppState>>,
+    auth_user: Option<AuthUser>,
+    Json(data): Json<TwoFactorEnableData>,
+) -> Result<Json<TwoFactorEnabled>, (StatusCode, Json<ErrorMessage>)> {
+    let user_id = identify(&state, auth_user, data.mfa_token.as_deref())?;
+    let user = load_user(&state, user_id).await?;
+    let two_factor = TwoFactor::get_by_user(state.clone(), user_id)
+        .await
+        .map_err(|_| internal_error("Erro interno ao ativar a verificação em duas etapas."))?;
+    let secret = match (&two_factor.secret, two_factor.enabled) {
+        (Some(secret), false) => secret,
+        (_, true) => return Err(bad_request("A verificação em duas etapas já está ativa.")),
+        (None, false) => return Err(bad_request("Inicie a configuração antes de a confirmar.")),
+    };
+    let step = totp::verify_code(secret, &data.code, Utc::now().timestamp()).ok_or_else(invalid_code)?;
+
+    let recovery_codes = totp::generate_recovery_codes();
+    TwoFactor::enable(state.clone(), user_id, step, &recovery_codes)
+        .await
+        .map_err(|_| internal_error("Erro interno ao ativar a verificação em duas etapas."))?;
+
+    let tokens = if data.mfa_token.is_some() {
+        Some(start_session(&state, user_id, &user.user_role).await?)
+    } else {
+        None
+    };
+    Ok(Json(TwoFactorEnabled { recovery_codes, tokens }))
+}
+
+// Desativa a verificação em duas etapas (POST /login/2fa/disable); exige um código válido
+// e não é permitido quando ela é obrigatória para o papel do utilizador.
+pub async fn disable_two_factor(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Json(data): Json<TwoFactorCodeData>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorMessage>)> {
+    let user_id = parse_user_id(&claims.sub)?;
+    let user = load_user(&state, user_id).await?;
+    if is_two_factor_required(&state, &user.user_role) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorMessage {
+                error: "A verificação em duas etapas é obrigatória para este papel.".to_string(),
+            }),
+        ));
+    }
+    let two_factor = TwoFactor::get_by_user(state.clone(), user_id)
+        .await
+        .map_err(|_| internal_error("Erro interno ao desativar a verificação em duas etapas."))?;
+    if !two_factor.enabled {
+        return Err(bad_request("A verificação em duas etapas não está ativa nesta conta."));
+    }
+    if !check_code(&state, user_id, &two_factor, &data.code).await? {
+        return Err(invalid_code());
+    }
+
+    TwoFactor::disable(state, user_id)
+        .await
+        .map_err(|_| internal_error("Erro interno ao desativar a verificação em duas etapas."))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Executa uma tentativa de autenticação com a proteção contra força bruta: recusa-a
+// enquanto a conta ou o IP estiverem bloqueados, conta as falhas (401) e limpa as da
+// conta quando ela é bem-sucedida.
+async fn throttled<T: IntoResponse>(
+    state: &Arc<AppState>,
+    account_key: String,
+    ip: IpAddr,
+    attempt: impl Future<Output = Result<T, (StatusCode, Json<ErrorMessage>)>>,
+) -> Response {
+    let ip_key = format!("ip:{}", ip);
+    let mut throttle = match LoginThrottle::begin(state.clone(), &[account_key.clone(), ip_key.clone()]).await {
+        Ok(throttle) => throttle,
+        Err(_) => return internal_error("Erro interno no servidor ao tentar login.").into_response(),
+    };
+    if let Some(secs) = throttle.retry_after() {
+        return too_many_requests(secs as u64, ErrorMessage {
+            error: format!("Demasiadas tentativas falhadas. Tente novamente dentro de {} segundos.", secs),
+        });
+    }
+
+    let result = attempt.await;
+    let bookkeeping = match &result {
+        Ok(_) => throttle.clear(&account_key).await,
+        Err((StatusCode::UNAUTHORIZED, _)) => {
+            record_failures(&mut throttle, &[(&account_key, &ACCOUNT_POLICY), (&ip_key, &IP_POLICY)]).await
+        }
+        Err(_) => Ok(()),
+    };
+    // Uma falha no registo não muda a resposta da tentativa (em produção, logar o erro).
+    let _ = bookkeeping.and(throttle.commit().await);
+    result.into_response()
+}
+
+async fn record_failures(throttle: &mut ThrottledAttempt, keys: &[(&str, &ThrottlePolicy)]) -> Result<(), sqlx::Error> {
+    for (key, policy) in keys {
+        throttle.record_failure(key, policy).await?;
+    }
+    Ok(())
+}
+
+fn is_two_factor_required(state: &AppState, role: &UserRole) -> bool {
+    state.totp.require_for_admins && *role == UserRole::Administrador
+}
+
+// Valida um código TOTP (que não pode ser reutilizado) ou, em alternativa, um código de recuperação
+async fn check_code(
+    state: &Arc<AppState>,
+    user_id: i32,
+    two_factor: &TwoFactor,
+    code: &str,
+) -> Result<bool, (StatusCode, Json<ErrorMessage>)> {
+    let secret = two_factor.secret.as_deref().unwrap_or_default();
+    let result = match totp::verify_code(secret, code, Utc::now().timestamp()) {
+        Some(step) => TwoFactor::consume_step(state.clone(), user_id, step).await,
+        None => TwoFactor::consume_recovery_code(state.clone(), user_id, code).await,
+    };
+    result.map_err(|_| internal_error("Erro interno ao validar o código."))
+}
+
+// Utilizador da configuração: o autenticado pelo access token ou, durante o login,
+// o do token intermédio.
+fn identify(
+    state: &AppState,
+    auth_user: Option<AuthUser>,
+    mfa_token: Option<&str>,
+) -> Result<i32, (StatusCode, Json<ErrorMessage>)> {
+    if let Some(token) = mfa_token {
+        return mfa_token_decode(&state.jwt_secret, token).ok_or_else(mfa_token_expired);
+    }
+    match auth_user {
+        Some(AuthUser(claims)) => parse_user_id(&claims.sub),
+        None => Err(session_expired()),
+    }
+}
+
+fn parse_user_id(sub: &str) -> Result<i32, (StatusCode, Json<ErrorMessage>)> {
+    sub.parse::<i32>()
+        .map_err(|_| bad_request("ID de utilizador inválido no token."))
+}
+
+// Carrega o utilizador de um token; contas desativadas são recusadas
+async fn load_user(state: &Arc<AppState>, user_id: i32) -> Result<User, (StatusCode, Json<ErrorMessage>)> {
+    match User::get_user_by_id(user_id, state.clone()).await {
+        Ok(Some(user)) if !user.user_active => Err(account_disabled()),
+        Ok(Some(user)) => Ok(user),
+        Ok(None) => Err(session_expired()),
+        Err(_) => Err(internal_error("Erro interno ao carregar o utilizador.")),
+    }
+}
+
+// Abre uma sessão e emite o par de tokens
+pub(crate) async fn start_session(
+    state: &Arc<AppState>,
+    user_id: i32,
+    role: &UserRole,
+) -> Result<TokenResponse, (StatusCode, Json<ErrorMessage>)> {
+    let (session_id, refresh_token) = UserSession::create_session(state.clone(), user_id)
+        .await
+        .map_err(|_| internal_error("Erro interno ao iniciar a sessão."))?;
+    Ok(token_response(state, user_id, role, &session_id, refresh_token))
+}
+
+fn token_response(state: &AppState, user_id: i32, role: &UserRole, session_id: &str, refresh_token: String) -> TokenResponse {
+    TokenResponse {
+        token: jwt_gen(&state.jwt_keys, user_id, role, session_id),
+        refresh_token,
+        expires_in: ACCESS_TOKEN_MINUTES * 60,
+        role: role.to_string(),
+    }
+}
+
+fn session_expired() -> (StatusCode, Json<ErrorMessage>) {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorMessage {
+            error: "Sessão expirada ou encerrada. Faça login novamente.".to_string(),
+        }),
+    )
+}
+
+fn mfa_token_expired() -> (StatusCode, Json<ErrorMessage>) {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorMessage {
+            error: "O tempo para concluir o login expirou. Faça login novamente.".to_string(),
+        }),
+    )
+}
+
+fn account_disabled() -> (StatusCode, Json<ErrorMessage>) {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorMessage {
+            error: "Esta conta está desativada. Contacte o administrador.".to_string(),
+        }),
+    )
+}
+
+fn email_not_verified() -> (StatusCode, Json<ErrorMessage>) {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorMessage {
+            error: "Confirme o seu e-mail antes de entrar: use o link que lhe enviámos ou peça um novo em /register/verify/resend.".to_string(),
+        }),
+    )
+}
+
+fn invalid_code() -> (StatusCode, Json<ErrorMessage>) {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorMessage {
+            error: "Código inválido.".to_string(),
+        }),
+    )
+}
+
+fn bad_request(message: &str) -> (StatusCode, Json<ErrorMessage>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorMessage {
+            error: message.to_string(),
+        }),
+    )
+}
+
+fn internal_error(message: &str) -> (StatusCode, Json<ErrorMessage>) {
+    // Em produção, logar o erro detalhado.
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorMessage {
+            error: message.to_string(),
+        }),
+    )
+}
+
+// Handler que publica as chaves públicas dos access tokens (GET /.well-known/jwks.json),
+// para outros serviços internos validarem os tokens. Vazio enquanto os tokens forem HS256.
+pub async fn jwks_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    ([(header::CACHE_CONTROL, "public, max-age=300")], Json(state.jwt_keys.jwks()))
 }
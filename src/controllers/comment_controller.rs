@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use axum::{
+    extract::{Path, State, Json},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde_json::json; // Para a macro json!
+
+use crate::models::{
+    appstate::AppState,
+    attachment::Attachment,
+    comment::{NewTicketComment, TicketComment},
+    permission::Permission,
+    ticket::Ticket,
+};
+use crate::auth::Authorized; // Extractor para utilizador autenticado, com as permissões
+use crate::controllers::ticket_controller::load_visible_ticket;
+use crate::services::notification_service::{notify_ticket_event, TicketEvent};
+use crate::upload::WithAttachments; // JSON ou multipart com anexos
+
+// Handler para listar a conversa de um ticket (GET /tickets/:id/comments)
+pub async fn list_comments(
+    Path(ticket_id): Path<i32>,
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Requer autenticação
+) -> impl IntoResponse {
+    if let Err(response) = load_visible_ticket(state.clone(), ticket_id, &user).await {
+        return response;
+    }
+
+    // Notas internas (e os seus anexos) só são enviadas a quem tem a permissão ticket.internal
+    let internal = user.can(Permission::TicketInternal);
+    let comments = TicketComment::get_comments_by_ticket(state.clone(), ticket_id, internal).await;
+    let attachments = Attachment::get_attachments_by_ticket(state, ticket_id, internal).await;
+    match (comments, attachments) {
+        (Ok(mut comments), Ok(attachments)) => {
+            // Anexos sem comentário pertencem ao próprio ticket (ver GET /tickets/:id/attachments)
+            for attachment in attachments {
+                if let Some(comment) = comments
+                    .iter_mut()
+                    .find(|c| c.comment_id.is_some() && c.comment_id == attachment.attachment_comment_id)
+                {
+                    comment.comment_attachments.push(attachment);
+                }
+            }
+            (StatusCode::OK, Json(json!({ "comments": comments }))).into_response()
+        }
+        _ => {
+            // Em produção, logar o erro 'e' detalhado.
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao carregar comentários"}))).into_response()
+        }
+    }
+}
+
+// Handler para comentar ou responder num ticket (POST /tickets/:id/comments)
+pub async fn create_comment(
+    Path(ticket_id): Path<i32>,
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Requer autenticação
+    WithAttachments(mut payload, files): WithAttachments<NewTicketComment>,
+) -> impl IntoResponse {
+    let current_user_id = match user.claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"erro": "ID de utilizador inválido no token."}))).into_response();
+        }
+    };
+
+    if let Err(response) = load_visible_ticket(state.clone(), ticket_id, &user).await {
+        return response;
+    }
+
+    // Um comentário só com anexos é aceite; sem texto nem anexos, não
+    if payload.comment_body.trim().is_empty() && files.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({"erro": "O comentário não pode estar vazio."}))).into_response();
+    }
+
+    if payload.comment_is_internal && !user.can(Permission::TicketInternal) {
+        return (StatusCode::FORBIDDEN, Json(json!({"erro": "Apenas a equipa pode criar notas internas."}))).into_response();
+    }
+
+    // Uma resposta só pode apontar para um comentário do mesmo ticket visível ao autor
+    if let Some(parent_id) = payload.comment_parent_id {
+        match TicketComment::get_comment_visibility(state.clone(), parent_id, ticket_id).await {
+            Ok(Some(false)) => {}
+            // Respostas a notas internas continuam internas
+            Ok(Some(true)) if user.can(Permission::TicketInternal) => payload.comment_is_internal = true,
+            Ok(_) => {
+                return (StatusCode::BAD_REQUEST, Json(json!({"erro": "Comentário de origem inválido."}))).into_response();
+            }
+            Err(_) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao salvar comentário"}))).into_response();
+            }
+        }
+    }
+
+    match TicketComment::save_new_comment_in_db(&payload, ticket_id, current_user_id, state.clone(), &files).await {
+        Ok(comment) => {
+            // Uma mensagem pública da equipa conta como primeira resposta para o SLA
+            if user.can(Permission::TicketInternal) && !comment.comment_is_internal {
+                // Uma falha aqui não invalida o comentário já salvo (em produção, logar).
+                let _ = Ticket::mark_first_response(state.clone(), ticket_id).await;
+            }
+            notify_ticket_event(state, ticket_id, current_user_id, TicketEvent::Commented {
+                author_name: comment.comment_author_name.clone(),
+                body: comment.comment_body.clone(),
+                internal: comment.comment_is_internal,
+            });
+            (StatusCode::CREATED, Json(comment)).into_response()
+        }
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao salvar comentário"}))).into_response()
+        }
+    }
+}
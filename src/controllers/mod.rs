@@ -3,4 +3,10 @@
 pub mod auth_controller;
 pub mod ticket_controller;
 pub mod admin_controller;
-pub mod user_controller; 
\ No newline at end of file
+pub mod user_controller;
+pub mod comment_controller;
+pub mod attachment_controller;
+pub mod password_controller;
+pub mod verification_controller;
+pub mod oidc_controller;
+pub mod api_key_controller;
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use serde::Deserialize;
+use tera::Context;
+
+use crate::controllers::auth_controller::{start_session, TokenResponse};
+use crate::models::appstate::AppState;
+use crate::services::auth_provider::provision_external_user;
+use crate::services::oidc::{pending_login_decode, pending_login_encode};
+use crate::services::session_cookie::cookie_value;
+
+// Cookie com o login em curso (state, nonce e code verifier), restrito às rotas do SSO
+const PENDING_LOGIN_COOKIE: &str = "oidc_login";
+const PENDING_LOGIN_COOKIE_PATH: &str = "/login/oidc";
+
+// Parâmetros com que o fornecedor de identidade regressa (GET /login/oidc/callback)
+#[derive(Deserialize, Debug)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+// Renderiza a página de login com uma mensagem de erro, ou com os tokens do login
+// por SSO, que o login.js guarda como num login com senha.
+fn render_login(state: &AppState, error_message: &str, tokens: Option<&TokenResponse>, status: StatusCode) -> Response {
+    let mut context = Context::new();
+    context.insert("static_path", "/static");
+    context.insert("success", "");
+    context.insert("oidc_enabled", &state.oidc.is_some());
+    context.insert("error_message", error_message);
+    if let Some(tokens) = tokens {
+        context.insert("oidc_login", tokens);
+    }
+
+    let mut response = match state.tera.render("login.html", &context) {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            (StatusCode::INTERNAL_SERVER_ERROR, Html("<h1>Erro ao carregar a página de login.</h1>".to_string())).into_response()
+        }
+    };
+    // O login em curso terminou (com ou sem sucesso): o cookie deixa de ser necessário
+    response.headers_mut().insert(
+        header::SET_COOKIE,
+        HeaderValue::from_str(&format!("{}=; Path={}; Max-Age=0", PENDING_LOGIN_COOKIE, PENDING_LOGIN_COOKIE_PATH))
+            .expect("Cookie inválido"),
+    );
+    response
+}
+
+fn sso_failed(state: &AppState, status: StatusCode) -> Response {
+    render_login(state, "Não foi possível entrar com o SSO. Tente novamente ou contacte o administrador.", None, status)
+}
+
+// Handler que inicia o login por SSO (GET /login/oidc): redireciona para o fornecedor
+pub async fn start_oidc_login(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let Some(oidc) = state.oidc.as_ref() else {
+        return (StatusCode::NOT_FOUND, Html("<h1>O login por SSO não está configurado.</h1>".to_string())).into_response();
+    };
+
+    match oidc.authorization_request().await {
+        Ok((url, pending)) => {
+            let cookie = format!(
+                "{}={}; Path={}; Max-Age=600; HttpOnly; SameSite=Lax{}",
+                PENDING_LOGIN_COOKIE,
+                pending_login_encode(&state.jwt_secret, &pending),
+                PENDING_LOGIN_COOKIE_PATH,
+                if oidc.uses_https() { "; Secure" } else { "" },
+            );
+            let mut response = Redirect::to(&url).into_response();
+            response.headers_mut().insert(header::SET_COOKIE, HeaderValue::from_str(&cookie).expect("Cookie inválido"));
+            response
+        }
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            sso_failed(&state, StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+// Handler do regresso do fornecedor (GET /login/oidc/callback): troca o código pelo ID token,
+// associa (ou cria) a conta pelo email e abre a sessão.
+pub async fn oidc_callback(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CallbackQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let Some(oidc) = state.oidc.as_ref() else {
+        return (StatusCode::NOT_FOUND, Html("<h1>O login por SSO não está configurado.</h1>".to_string())).into_response();
+    };
+    if query.error.is_some() {
+        // Em produção, logar o erro devolvido pelo fornecedor.
+        return sso_failed(&state, StatusCode::UNAUTHORIZED);
+    }
+
+    // O state tem de corresponder ao do cookie deste navegador (proteção contra CSRF no login)
+    let pending = cookie_value(&headers, PENDING_LOGIN_COOKIE).and_then(|value| pending_login_decode(&state.jwt_secret, value));
+    let (Some(pending), Some(code)) = (pending, query.code) else {
+        return render_login(&state, "O login por SSO expirou. Tente novamente.", None, StatusCode::BAD_REQUEST);
+    };
+    if !pending.matches_state(query.state.as_deref()) {
+        return render_login(&state, "O login por SSO expirou. Tente novamente.", None, StatusCode::BAD_REQUEST);
+    }
+
+    let identity = match oidc.exchange_code(&code, &pending).await {
+        Ok(identity) => identity,
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            return sso_failed(&state, StatusCode::UNAUTHORIZED);
+        }
+    };
+
+    // A conta é associada pelo email (ou criada no primeiro login) e o papel acompanha os grupos mapeados
+    let user = match provision_external_user(&state, &identity).await {
+        Ok(user) if !user.user_active || user.user_service_account => {
+            return render_login(&state, "Esta conta está desativada. Contacte o administrador.", None, StatusCode::FORBIDDEN);
+        }
+        Ok(user) => user,
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            return sso_failed(&state, StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // O fornecedor de identidade é responsável pelos fatores de autenticação: não há segunda etapa local
+    match start_session(&state, user.user_id.unwrap_or_default(), &user.user_role).await {
+        Ok(tokens) => render_login(&state, "", Some(&tokens), StatusCode::OK),
+        Err(_) => sso_failed(&state, StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use axum::{
+    extract::{Form, Query, State},
+    response::{Html, IntoResponse, Redirect, Response},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use tera::Context;
+
+use crate::models::{
+    appstate::AppState,
+    password_reset::PasswordReset,
+    user::User,
+};
+use crate::services::{auth_service::password_hash, notification_service::send_password_reset};
+
+// Mensagem exibida após um pedido de redefinição, exista ou não a conta,
+// para não revelar quais emails estão registados.
+const RESET_REQUESTED_MESSAGE: &str =
+    "Se o e-mail estiver registado, enviámos um link para redefinir a senha. Verifique a sua caixa de entrada.";
+const INVALID_LINK_MESSAGE: &str = "Este link é inválido ou já expirou. Peça um novo.";
+
+// Estrutura para os dados do formulário "esqueci a senha"
+#[derive(Deserialize, Debug)]
+pub struct ForgotPasswordPayload {
+    user_email: String,
+}
+
+// Token recebido no link do email (GET /password/reset?token=...)
+#[derive(Deserialize, Debug)]
+pub struct ResetTokenQuery {
+    #[serde(default)]
+    token: String,
+}
+
+// Estrutura para os dados do formulário de nova senha
+#[derive(Deserialize, Debug)]
+pub struct ResetPasswordPayload {
+    token: String,
+    new_password: String,
+    confirm_password: String,
+}
+
+// Renderiza uma das páginas de senha com o status indicado
+fn render_page(state: &AppState, template: &str, context: &Context, status: StatusCode) -> Response {
+    match state.tera.render(template, context) {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(_) => {
+            // Em produção, o erro 'e' deve ser logado.
+            (StatusCode::INTERNAL_SERVER_ERROR, Html("<h1>Erro ao carregar a página</h1>".to_string())).into_response()
+        }
+    }
+}
+
+fn forgot_context(error_message: &str, info_message: &str) -> Context {
+    let mut context = Context::new();
+    context.insert("static_path", "/static");
+    context.insert("error_message", error_message);
+    context.insert("info_message", info_message);
+    context
+}
+
+fn reset_context(token: &str, error_message: &str) -> Context {
+    let mut context = Context::new();
+    context.insert("static_path", "/static");
+    context.insert("token", token);
+    context.insert("error_message", error_message);
+    context
+}
+
+// Handler para renderizar a página "esqueci a senha" (GET /password/forgot)
+pub async fn render_forgot_password_page(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    render_page(&state, "forgot_password.html", &forgot_context("", ""), StatusCode::OK)
+}
+
+// Handler para pedir o link de redefinição por email (POST /password/forgot)
+pub async fn request_password_reset(
+    State(state): State<Arc<AppState>>,
+    Form(payload): Form<ForgotPasswordPayload>,
+) -> impl IntoResponse {
+    if state.mailer.is_none() {
+        let context = forgot_context("A recuperação de senha está indisponível: o envio de emails não está configurado.", "");
+        return render_page(&state, "forgot_password.html", &context, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    match User::get_user_by_email(payload.user_email.trim().to_string(), state.clone()).await {
+        // Contas desativadas e contas de serviço não recuperam o acesso por aqui; a resposta é a mesma
+        Ok(user) if !user.user_active || user.user_service_account => {}
+        Ok(user) => {
+            let user_id = user.user_id.unwrap_or_default();
+            match PasswordReset::create_token(state.clone(), user_id).await {
+                Ok(token) => send_password_reset(state.clone(), user, token),
+                Err(_) => {
+                    // Em produção, o erro 'e' deve ser logado.
+                    let context = forgot_context("Ocorreu um erro ao processar o pedido. Tente novamente mais tarde.", "");
+                    return render_page(&state, "forgot_password.html", &context, StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+        }
+        Err(sqlx::Error::RowNotFound) => {}
+        Err(_) => {
+            // Em produção, o erro 'e' deve ser logado.
+            let context = forgot_context("Ocorreu um erro ao processar o pedido. Tente novamente mais tarde.", "");
+            return render_page(&state, "forgot_password.html", &context, StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    render_page(&state, "forgot_password.html", &forgot_context("", RESET_REQUESTED_MESSAGE), StatusCode::OK)
+}
+
+// Handler para renderizar o formulário de nova senha (GET /password/reset?token=...)
+pub async fn render_reset_password_page(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ResetTokenQuery>,
+) -> impl IntoResponse {
+    match PasswordReset::is_valid(state.clone(), &query.token).await {
+        Ok(true) => render_page(&state, "reset_password.html", &reset_context(&query.token, ""), StatusCode::OK),
+        Ok(false) => render_page(&state, "reset_password.html", &reset_context("", INVALID_LINK_MESSAGE), StatusCode::BAD_REQUEST),
+        Err(_) => {
+            // Em produção, o erro 'e' deve ser logado.
+            let context = reset_context("", "Ocorreu um erro ao validar o link. Tente novamente mais tarde.");
+            render_page(&state, "reset_password.html", &context, StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// Handler para gravar a nova senha (POST /password/reset). O token deixa de valer
+// e todas as sessões abertas do utilizador são encerradas.
+pub async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    Form(payload): Form<ResetPasswordPayload>,
+) -> impl IntoResponse {
+    if payload.new_password.is_empty() {
+        let context = reset_context(&payload.token, "Indique a nova senha.");
+        return render_page(&state, "reset_password.html", &context, StatusCode::BAD_REQUEST);
+    }
+    if payload.new_password != payload.confirm_password {
+        let context = reset_context(&payload.token, "As senhas não coincidem.");
+        return render_page(&state, "reset_password.html", &context, StatusCode::BAD_REQUEST);
+    }
+
+    let hashed_password = password_hash(&payload.new_password);
+    match PasswordReset::reset_password(state.clone(), &payload.token, &hashed_password).await {
+        Ok(true) => Redirect::to("/login?success=reset").into_response(),
+        Ok(false) => render_page(&state, "reset_password.html", &reset_context("", INVALID_LINK_MESSAGE), StatusCode::BAD_REQUEST),
+        Err(_) => {
+            // Em produção, o erro 'e' deve ser logado.
+            let context = reset_context(&payload.token, "Ocorreu um erro ao redefinir a senha. Tente novamente mais tarde.");
+            render_page(&state, "reset_password.html", &context, StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -1,26 +1,44 @@
 use std::sync::Arc;
 use axum::{
-    extract::{Path, State, Json},
+    extract::{Path, Query, State, Json},
     http::StatusCode,
-    response::{Html, IntoResponse},
+    response::{Html, IntoResponse, Response},
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json; // Para a macro json!
 use tera::Context;
 
 // Importações dos modelos e autenticação
 use crate::models::{
     appstate::AppState,
-    ticket::{NewTicket, Ticket},
+    attachment::Attachment,
+    permission::Permission,
+    ticket_history::TicketHistoryEntry,
+    ticket::{NewTicket, StatusTicket, Ticket, TicketListQuery, TicketScope},
 };
-use crate::auth::AuthUser; // Extractor para utilizador autenticado
+use crate::auth::{AuthUser, Authorized, PageUser}; // Extractors para utilizador autenticado (API e páginas)
+use crate::upload::WithAttachments; // JSON ou multipart com anexos
+use crate::services::notification_service::{notify_ticket_event, TicketEvent};
+
+// Ticket recém-criado acompanhado dos anexos enviados com ele
+#[derive(Serialize)]
+pub struct CreatedTicketView {
+    #[serde(flatten)]
+    ticket: Ticket,
+    ticket_attachments: Vec<Attachment>,
+}
 
 // Handler para criar um novo ticket (POST /new_ticket)
 pub async fn create_ticket(
     State(state): State<Arc<AppState>>,
-    AuthUser(claims): AuthUser, // Requer autenticação
-    Json(mut payload): Json<NewTicket>, // Payload do novo ticket
+    user: Authorized, // Requer a permissão ticket.create
+    WithAttachments(mut payload, files): WithAttachments<NewTicket>, // Payload do novo ticket + anexos
 ) -> impl IntoResponse {
-    let user_id_str = claims.sub;
+    if !user.can(Permission::TicketCreate) {
+        return (StatusCode::FORBIDDEN, Json(json!({"erro": "Acesso Negado."}))).into_response();
+    }
+
+    let user_id_str = user.claims.sub;
     let current_user_id = match user_id_str.parse::<i32>() {
         Ok(id) => id,
         Err(_) => {
@@ -35,23 +53,31 @@ pub async fn create_ticket(
     // Define o ticket_client_id com o ID do utilizador autenticado
     payload.ticket_client_id = current_user_id;
 
-    match Ticket::save_new_ticket_in_db(&payload, state).await {
-        Ok(ticket_criado_no_db) => {
-            (StatusCode::CREATED, Json(ticket_criado_no_db)).into_response()
+    match Ticket::save_new_ticket_in_db(&payload, state.clone(), &files).await {
+        Ok((ticket_criado_no_db, ticket_attachments)) => {
+            let ticket_id = ticket_criado_no_db.ticket_id.unwrap_or_default();
+            notify_ticket_event(state, ticket_id, current_user_id, TicketEvent::Created);
+            (
+                StatusCode::CREATED,
+                Json(CreatedTicketView { ticket: ticket_criado_no_db, ticket_attachments }),
+            ).into_response()
         }
         Err(_) => {
             // Em produção, logar o erro 'e' detalhado.
+            // Nada foi gravado: nem o ticket, nem os anexos.
             (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao salvar ticket"}))).into_response()
         }
     }
 }
 
-// Handler para listar os tickets abertos do utilizador autenticado (GET /tickets)
-// Esta rota pode precisar de um nome mais específico se houver uma rota de admin para todos os tickets.
+// Handler para listar os tickets abertos pelo utilizador autenticado (GET /tickets).
+// Aceita os filtros, a ordenação e o cursor de TicketListQuery; sem filtro de status
+// devolve apenas os tickets não fechados.
 pub async fn list_tickets(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser, // Requer autenticação
-) -> Result<Json<Vec<Ticket>>, (StatusCode, Json<serde_json::Value>)> { // Tipo de retorno mais explícito
+    Query(query): Query<TicketListQuery>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> { // Lista simples ou TicketPage (com cursor/limit)
     let user_id_str = claims.sub;
     let current_user_id = match user_id_str.parse::<i32>() {
         Ok(id) => id,
@@ -64,8 +90,21 @@ pub async fn list_tickets(
         }
     };
 
-    match Ticket::get_open_tickets(state, current_user_id).await {
-        Ok(tickets) => Ok(Json(tickets)),
+    let mut filter = match query.parse() {
+        Ok(filter) => filter,
+        Err(message) => return Err((StatusCode::BAD_REQUEST, Json(json!({"erro": message})))),
+    };
+    filter.requester_id = None; // O escopo já restringe aos tickets do próprio utilizador
+    let scope = TicketScope::Requester(current_user_id);
+
+    // Sem cursor nem limit, a resposta mantém o formato original: um array de tickets
+    let result = if query.is_paged() {
+        Ticket::search_tickets(state, scope, &filter).await.map(|page| Json(page).into_response())
+    } else {
+        Ticket::search_all_tickets(state, scope, &filter).await.map(|tickets| Json(tickets).into_response())
+    };
+    match result {
+        Ok(response) => Ok(response),
         Err(_) => {
             // Em produção, logar o erro 'e' detalhado.
             Err((
@@ -105,10 +144,39 @@ pub async fn get_my_open_tickets_api(
     }
 }
 
+// Handler de API para os tickets atribuídos ao técnico autenticado (GET /tickets/assigned).
+// A rota exige a permissão ticket.list.assigned.
+pub async fn get_my_assigned_tickets_api(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser, // Requer autenticação
+) -> Result<Json<Vec<Ticket>>, (StatusCode, Json<serde_json::Value>)> {
+    let current_user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"erro": "ID de utilizador inválido no token."}))
+            ));
+        }
+    };
+
+    match Ticket::get_assigned_tickets(state, current_user_id).await {
+        Ok(tickets) => Ok(Json(tickets)),
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"erro": "Erro ao buscar os chamados atribuídos."}))
+            ))
+        }
+    }
+}
+
 // Handler para servir a página HTML base de "novo ticket" (GET /new_ticket)
 // Esta página carregará os tickets dinamicamente via JavaScript.
 pub async fn new_ticket(
     State(state): State<Arc<AppState>>,
+    PageUser(_claims): PageUser, // Protegido pelo cookie da sessão
 ) -> impl IntoResponse {
     let mut context = Context::new();
     context.insert("static_path", "/static"); 
@@ -122,30 +190,112 @@ pub async fn new_ticket(
     }
 }
 
-// Handler para fechar um ticket (POST /tickets/:id/close)
-pub async fn close_ticket(
-    Path(id): Path<i32>, // ID do ticket a ser fechado
+// Carrega um ticket verificando se o utilizador autenticado o pode ver:
+// com a permissão ticket.read.all vê todos, sem ela apenas os próprios chamados.
+pub async fn load_visible_ticket(
+    state: Arc<AppState>,
+    ticket_id: i32,
+    user: &Authorized,
+) -> Result<Ticket, Response> {
+    let ticket = match Ticket::get_ticket_by_id(state, ticket_id).await {
+        Ok(Some(ticket)) => ticket,
+        Ok(None) => {
+            return Err((StatusCode::NOT_FOUND, Json(json!({"erro": "Chamado não encontrado"}))).into_response());
+        }
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao buscar chamado"}))).into_response());
+        }
+    };
+
+    let is_owner = user.claims.sub.parse::<i32>().ok() == Some(ticket.ticket_client_id);
+    if !user.can(Permission::TicketReadAll) && !is_owner {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"erro": "Acesso Negado."}))).into_response());
+    }
+
+    Ok(ticket)
+}
+
+// Struct para o payload de transição de status
+#[derive(Deserialize, Debug)]
+pub struct TransitionPayload {
+    status: StatusTicket, // Ex.: "Em Atendimento", "Resolvido", "Fechado"
+}
+
+// Handler para mover um ticket no ciclo de vida (POST /tickets/:id/transition)
+pub async fn transition_ticket(
+    Path(id): Path<i32>, // ID do ticket
     State(state): State<Arc<AppState>>,
-    AuthUser(_claims): AuthUser, // Requer autenticação, mas não usamos as claims diretamente aqui (a menos que queira validar se o utilizador pode fechar este ticket)
+    user: Authorized, // Requer autenticação
+    Json(payload): Json<TransitionPayload>,
 ) -> impl IntoResponse {
-    // Para MySQL ou SQLite
-    let query_sql = "UPDATE Tickets SET Ticket_Status = 'Fechado' WHERE ID_Ticket = ?";
-
-    match sqlx::query(query_sql)
-        .bind(id)
-        .execute(&state.pool)
-        .await 
-    {
+    let actor_id = match user.claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"erro": "ID de utilizador inválido no token."}))).into_response();
+        }
+    };
+
+    let ticket = match load_visible_ticket(state.clone(), id, &user).await {
+        Ok(ticket) => ticket,
+        Err(response) => return response,
+    };
+
+    // Sem a permissão ticket.transition.any, só é possível fechar ou reabrir
+    if !user.can(Permission::TicketTransitionAny) && !payload.status.is_client_transition() {
+        return (StatusCode::FORBIDDEN, Json(json!({"erro": "Acesso Negado."}))).into_response();
+    }
+
+    let current_status = ticket.ticket_status;
+    if !current_status.can_transition_to(payload.status) {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "erro": format!("Transição inválida: {} -> {}", current_status, payload.status),
+                "transicoes_permitidas": current_status.allowed_transitions(),
+            }))
+        ).into_response();
+    }
+
+    match Ticket::update_ticket_status(state.clone(), id, current_status, payload.status, actor_id).await {
         Ok(query_result) => {
             if query_result.rows_affected() > 0 {
-                (StatusCode::OK, Json(json!({"mensagem": "Chamado fechado com sucesso"}))).into_response()
+                if payload.status == StatusTicket::Fechado {
+                    notify_ticket_event(state, id, actor_id, TicketEvent::Closed);
+                }
+                (StatusCode::OK, Json(json!({
+                    "mensagem": "Status do chamado atualizado com sucesso",
+                    "ticket_status": payload.status,
+                    "transicoes_permitidas": payload.status.allowed_transitions(),
+                }))).into_response()
             } else {
-                (StatusCode::NOT_FOUND, Json(json!({"erro": "Chamado não encontrado"}))).into_response()
+                // O status mudou entre a leitura e a atualização
+                (StatusCode::CONFLICT, Json(json!({"erro": "O chamado foi alterado por outro utilizador. Recarregue e tente novamente."}))).into_response()
             }
         }
         Err(_) => {
             // Em produção, logar o erro 'e' detalhado.
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao fechar chamado"}))).into_response()
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao atualizar status do chamado"}))).into_response()
+        }
+    }
+}
+
+// Handler para o histórico de alterações de um ticket (GET /tickets/:id/history)
+pub async fn get_ticket_history(
+    Path(id): Path<i32>,
+    State(state): State<Arc<AppState>>,
+    user: Authorized, // Requer autenticação
+) -> impl IntoResponse {
+    if let Err(response) = load_visible_ticket(state.clone(), id, &user).await {
+        return response;
+    }
+
+    // Entradas de notas internas só são mostradas a quem tem a permissão ticket.internal
+    match TicketHistoryEntry::get_history_by_ticket(state, id, user.can(Permission::TicketInternal)).await {
+        Ok(history) => (StatusCode::OK, Json(json!({ "history": history }))).into_response(),
+        Err(_) => {
+            // Em produção, logar o erro 'e' detalhado.
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"erro": "Erro ao carregar histórico do chamado"}))).into_response()
         }
     }
 }
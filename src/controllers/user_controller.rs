@@ -1,8 +1,7 @@
 use std::sync::Arc;
-use std::str::FromStr;
 use axum::{
-    extract::{Form, State},
-    response::{Html, IntoResponse, Redirect},
+    extract::{Form, Query, State},
+    response::{Html, IntoResponse, Redirect, Response},
     http::StatusCode,
 };
 use serde::Deserialize;
@@ -11,9 +10,12 @@ use tera::Context;
 // Importações dos modelos e serviços
 use crate::models::{
     appstate::AppState,
+    invitation::Invitation,
     user::{User, UserRole},
 };
-use crate::services::auth_service::password_hash;
+use crate::services::auth_service::{invite_token_decode, password_hash};
+use crate::services::registration_policy::skip_email_verification;
+use crate::controllers::verification_controller::start_email_verification;
 
 // Estrutura para os dados do formulário de registro
 #[derive(Deserialize, Debug)]
@@ -21,7 +23,8 @@ pub struct RegisterPayload {
     user_name: String,
     user_email: String,
     user_password: String,
-    user_role: String, // Recebe "Cliente" ou "Administrador"
+    // Um eventual campo "user_role" enviado pelo formulário é ignorado:
+    // o registo público cria sempre clientes (a equipa é criada em /admin/users).
 }
 
 // Handler para criar um novo utilizador (POST /register)
@@ -29,32 +32,53 @@ pub async fn create_user(
     State(state): State<Arc<AppState>>,
     Form(payload): Form<RegisterPayload>,
 ) -> impl IntoResponse {
-    let role_from_form = match UserRole::from_str(&payload.user_role) {
-        Ok(r) => r,
-        Err(_) => {
-            // Em produção, este erro deve ser logado.
-            // Para o utilizador, poderia retornar um erro mais específico ou
-            // renderizar o formulário novamente com uma mensagem.
-            // Mantendo o fallback para Cliente, mas idealmente validaria no frontend/retornaria erro.
-            UserRole::Cliente 
-        }
-    };
-    
-    // Lógica de segurança para registo de Admin pode ser adicionada aqui em produção.
+    // Só os domínios de email configurados podem criar conta (REGISTRATION_ALLOWED_DOMAINS / _DENIED_DOMAINS)
+    if let Err(message) = state.registration_domains.check(&payload.user_email) {
+        let mut error_context = Context::new();
+        error_context.insert("static_path", "/static");
+        error_context.insert("user_name_val", &payload.user_name);
+        error_context.insert("user_email_val", &payload.user_email);
+        error_context.insert("error_message", &message);
+        let rendered = state.tera.render("register.html", &error_context)
+            .unwrap_or_else(|_| message.clone()); // Fallback se o template de erro falhar
+        return (StatusCode::FORBIDDEN, Html(rendered)).into_response();
+    }
+
+    // Sem envio de emails não há como confirmar a conta: o registo só segue se isso for
+    // explicitamente dispensado (REGISTRATION_SKIP_EMAIL_VERIFICATION)
+    if state.mailer.is_none() && !skip_email_verification() {
+        let mut error_context = Context::new();
+        error_context.insert("static_path", "/static");
+        error_context.insert("user_name_val", &payload.user_name);
+        error_context.insert("user_email_val", &payload.user_email);
+        error_context.insert("error_message", "O registo está indisponível de momento. Contacte o suporte.");
+        let rendered = state.tera.render("register.html", &error_context)
+            .unwrap_or_else(|_| "Registo indisponível.".to_string()); // Fallback se o template de erro falhar
+        return (StatusCode::SERVICE_UNAVAILABLE, Html(rendered)).into_response();
+    }
 
     let hashed_password = password_hash(&payload.user_password);
 
-    let new_user = User::build_user(
+    let mut new_user = User::build_user(
         payload.user_name.clone(),
         payload.user_email.clone(),
         hashed_password,
-        role_from_form,
+        UserRole::Cliente,
     );
+    // A conta só entra depois de confirmar o email, exceto sem SMTP com a confirmação dispensada
+    new_user.user_email_verified = state.mailer.is_none();
 
     match User::save_user_in_db(&new_user, Arc::clone(&state)).await {
-        Ok(_) => {
+        Ok(_) if new_user.user_email_verified => {
             Redirect::to("/login?success=1").into_response()
         }
+        Ok(result) => {
+            new_user.user_id = Some(result.last_insert_id() as i32);
+            // Se o link não puder ser emitido agora, o utilizador pode pedir outro em /register/verify/resend
+            // Em produção, logar o erro 'e' detalhado.
+            let _ = start_email_verification(Arc::clone(&state), new_user).await;
+            Redirect::to("/login?success=verify").into_response()
+        }
         Err(e) => {
             // Em produção, o erro 'e' deve ser logado detalhadamente.
             let mut error_context = Context::new();
@@ -100,3 +124,105 @@ pub async fn render_register_page(
         }
     }
 }
+
+// Parâmetros do link de um convite (GET /register/invite?token=...)
+#[derive(Deserialize, Debug)]
+pub struct InviteQuery {
+    token: Option<String>,
+}
+
+// Estrutura para os dados do formulário de aceitação de um convite
+#[derive(Deserialize, Debug)]
+pub struct AcceptInvitePayload {
+    token: String,
+    user_name: String,
+    user_password: String,
+}
+
+// Renderiza o formulário de registo para um convite da equipa: o email e o papel vêm do convite.
+// Um convite inválido, expirado, revogado ou já usado mostra apenas a mensagem de erro.
+async fn render_invite_page_with(
+    state: &Arc<AppState>,
+    token: &str,
+    user_name: &str,
+    error_message: &str,
+    status: StatusCode,
+) -> Response {
+    let invitation = match invite_token_decode(&state.jwt_secret, token) {
+        Some(invitation_id) => match Invitation::get_pending_by_id(Arc::clone(state), invitation_id).await {
+            Ok(invitation) => invitation,
+            Err(_) => {
+                // Em produção, o erro 'e' deve ser logado.
+                return (StatusCode::INTERNAL_SERVER_ERROR, Html("<h1>Erro ao carregar o convite</h1>".to_string())).into_response();
+            }
+        },
+        None => None,
+    };
+
+    let mut context = Context::new();
+    context.insert("static_path", "/static");
+    context.insert("form_action", "/register/invite");
+    context.insert("page_title", "Aceite o convite");
+    context.insert("user_name_val", user_name);
+
+    let status = match invitation {
+        Some(invitation) => {
+            context.insert("invite_token", token);
+            context.insert("invite_role", &invitation.invitation_role.to_string());
+            context.insert("user_email_val", &invitation.invitation_email);
+            context.insert("error_message", error_message);
+            status
+        }
+        None => {
+            context.insert("invite_invalid", &true);
+            context.insert("user_email_val", "");
+            context.insert("error_message", "Este convite é inválido, expirou ou já foi utilizado. Peça um novo convite ao administrador.");
+            StatusCode::GONE
+        }
+    };
+
+    match state.tera.render("register.html", &context) {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(_) => {
+            // Em produção, o erro 'e' deve ser logado.
+            (StatusCode::INTERNAL_SERVER_ERROR, Html("<h1>Erro ao carregar página de registo</h1>".to_string())).into_response()
+        }
+    }
+}
+
+// Handler para renderizar a página de um convite (GET /register/invite)
+pub async fn render_invite_page(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<InviteQuery>,
+) -> impl IntoResponse {
+    let token = query.token.unwrap_or_default();
+    render_invite_page_with(&state, &token, "", "", StatusCode::OK).await
+}
+
+// Handler para aceitar um convite e criar a conta da equipa (POST /register/invite)
+pub async fn accept_invitation(
+    State(state): State<Arc<AppState>>,
+    Form(payload): Form<AcceptInvitePayload>,
+) -> impl IntoResponse {
+    let Some(invitation_id) = invite_token_decode(&state.jwt_secret, &payload.token) else {
+        return render_invite_page_with(&state, &payload.token, "", "", StatusCode::GONE).await;
+    };
+
+    if payload.user_name.trim().is_empty() || payload.user_password.is_empty() {
+        return render_invite_page_with(&state, &payload.token, &payload.user_name, "Indique o seu nome e uma senha.", StatusCode::BAD_REQUEST).await;
+    }
+
+    let hashed_password = password_hash(&payload.user_password);
+    match Invitation::accept(Arc::clone(&state), invitation_id, payload.user_name.trim(), &hashed_password).await {
+        Ok(true) => Redirect::to("/login?success=1").into_response(),
+        // O convite deixou de estar pendente: a página mostra a mensagem de convite inválido
+        Ok(false) => render_invite_page_with(&state, &payload.token, "", "", StatusCode::GONE).await,
+        Err(e) => {
+            // Em produção, o erro 'e' deve ser logado detalhadamente.
+            if e.as_database_error().is_some_and(|db_err| db_err.is_unique_violation()) {
+                return render_invite_page_with(&state, &payload.token, &payload.user_name, "Este e-mail já está registado. Faça login ou peça ajuda ao administrador.", StatusCode::CONFLICT).await;
+            }
+            render_invite_page_with(&state, &payload.token, &payload.user_name, "Ocorreu um erro ao criar a conta. Tente novamente mais tarde.", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
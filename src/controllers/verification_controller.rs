@@ -0,0 +1,107 @@
+use std::sync::Arc;
+use axum::{
+    extract::{Form, Query, State},
+    response::{Html, IntoResponse, Redirect, Response},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use tera::Context;
+
+use crate::models::{
+    appstate::AppState,
+    email_verification::EmailVerification,
+    user::User,
+};
+use crate::services::notification_service::send_email_verification;
+
+// Mensagem exibida após um pedido de reenvio, exista ou não a conta,
+// para não revelar quais emails estão registados.
+const RESEND_REQUESTED_MESSAGE: &str =
+    "Se o e-mail estiver registado e ainda por confirmar, enviámos um novo link. Verifique a sua caixa de entrada.";
+const INVALID_LINK_MESSAGE: &str = "Este link de confirmação é inválido ou já expirou. Peça um novo.";
+
+// Token recebido no link do email (GET /register/verify?token=...)
+#[derive(Deserialize, Debug)]
+pub struct VerifyTokenQuery {
+    #[serde(default)]
+    token: String,
+}
+
+// Estrutura para os dados do formulário de reenvio
+#[derive(Deserialize, Debug)]
+pub struct ResendVerificationPayload {
+    user_email: String,
+}
+
+// Renderiza a página de confirmação de email com as mensagens e o status indicados
+fn render_page(state: &AppState, error_message: &str, info_message: &str, status: StatusCode) -> Response {
+    let mut context = Context::new();
+    context.insert("static_path", "/static");
+    context.insert("error_message", error_message);
+    context.insert("info_message", info_message);
+
+    match state.tera.render("verify_email.html", &context) {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(_) => {
+            // Em produção, o erro 'e' deve ser logado.
+            (StatusCode::INTERNAL_SERVER_ERROR, Html("<h1>Erro ao carregar a página</h1>".to_string())).into_response()
+        }
+    }
+}
+
+// Emite um token de confirmação para o utilizador e envia-o por email em segundo plano
+pub async fn start_email_verification(state: Arc<AppState>, user: User) -> Result<(), sqlx::Error> {
+    let token = EmailVerification::create_token(state.clone(), user.user_id.unwrap_or_default()).await?;
+    send_email_verification(state, user, token);
+    Ok(())
+}
+
+// Handler do link de confirmação (GET /register/verify?token=...)
+pub async fn verify_email(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<VerifyTokenQuery>,
+) -> impl IntoResponse {
+    match EmailVerification::verify(state.clone(), &query.token).await {
+        Ok(true) => Redirect::to("/login?success=verified").into_response(),
+        Ok(false) => render_page(&state, INVALID_LINK_MESSAGE, "", StatusCode::BAD_REQUEST),
+        Err(_) => {
+            // Em produção, o erro 'e' deve ser logado.
+            render_page(&state, "Ocorreu um erro ao confirmar o e-mail. Tente novamente mais tarde.", "", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// Handler para renderizar a página de reenvio do link (GET /register/verify/resend)
+pub async fn render_resend_verification_page(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    render_page(&state, "", "", StatusCode::OK)
+}
+
+// Handler para reenviar o link de confirmação (POST /register/verify/resend)
+pub async fn resend_verification(
+    State(state): State<Arc<AppState>>,
+    Form(payload): Form<ResendVerificationPayload>,
+) -> impl IntoResponse {
+    if state.mailer.is_none() {
+        return render_page(&state, "O envio de emails não está configurado.", "", StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    match User::get_user_by_email(payload.user_email.trim().to_string(), state.clone()).await {
+        // Contas desativadas ou já confirmadas não recebem link; a resposta é a mesma
+        Ok(user) if !user.user_active || user.user_email_verified => {}
+        Ok(user) => {
+            if start_email_verification(state.clone(), user).await.is_err() {
+                // Em produção, o erro 'e' deve ser logado.
+                return render_page(&state, "Ocorreu um erro ao processar o pedido. Tente novamente mais tarde.", "", StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+        Err(sqlx::Error::RowNotFound) => {}
+        Err(_) => {
+            // Em produção, o erro 'e' deve ser logado.
+            return render_page(&state, "Ocorreu um erro ao processar o pedido. Tente novamente mais tarde.", "", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    render_page(&state, "", RESEND_REQUESTED_MESSAGE, StatusCode::OK)
+}
@@ -1,3 +1,4 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 use axum::{
     response::{Html, IntoResponse}, 
@@ -18,6 +19,8 @@ mod db;
 mod models;      
 mod routes;      
 mod services;    
+mod upload;
+mod rate_limit;
 
 #[tokio::main]
 async fn main() {
@@ -26,32 +29,76 @@ async fn main() {
     // Configuração do estado da aplicação
     let jwt_secret = std::env::var("JWT_SECRET")
         .expect("Falha ao carregar JWT_SECRET do ambiente");
+    let jwt_keys = services::jwt_keys::JwtKeys::from_env(&jwt_secret)
+        .expect("Falha ao carregar as chaves de assinatura dos tokens");
+    let session_cookies = services::session_cookie::SessionCookies::from_env()
+        .expect("Falha ao configurar os cookies da sessão");
     let tera = Tera::new("./src/templates/**/*.html")
         .expect("Falha ao carregar templates Tera");
     let pool: MySqlPool = db::connection::establish_connection()
         .await
         .expect("Falha ao estabelecer conexão com o banco de dados");
+    let calendar = services::business_calendar::BusinessCalendar::from_env()
+        .expect("Falha ao carregar o calendário de expediente");
+    let attachment_storage = services::attachment_storage::storage_from_env()
+        .expect("Falha ao configurar o armazenamento de anexos");
+    let attachment_limits = services::attachment_storage::AttachmentLimits::from_env()
+        .expect("Falha ao carregar os limites de anexos");
+    let mailer = services::notification_service::Mailer::from_env()
+        .expect("Falha ao configurar o envio de emails");
+    let totp = services::totp::TotpConfig::from_env()
+        .expect("Falha ao carregar a configuração da verificação em duas etapas");
+    let registration_domains = services::registration_policy::EmailDomainPolicy::from_env()
+        .expect("Falha ao carregar os domínios aceites no registo");
+    let oidc = services::oidc::OidcClient::from_env()
+        .expect("Falha ao configurar o login por SSO (OIDC)");
+    let auth_providers = services::auth_provider::providers_from_env()
+        .expect("Falha ao configurar os fornecedores de autenticação");
 
     let state = Arc::new(AppState {
         tera,
         pool,
         jwt_secret,
+        jwt_keys,
+        session_cookies,
+        calendar,
+        attachment_storage,
+        attachment_limits,
+        mailer,
+        totp,
+        registration_domains,
+        oidc,
+        auth_providers,
     });
 
+    // Canal de entrada por email (opcional)
+    if let Some(inbound_config) = services::inbound_email::InboundEmailConfig::from_env()
+        .expect("Falha ao configurar o canal de entrada por email")
+    {
+        services::inbound_email::spawn_maildir_poller(state.clone(), inbound_config);
+    }
+
     // Definição das rotas da aplicação
     let app = Router::new()
         .nest_service("/static", ServeDir::new("src/static")) // Serve ficheiros estáticos
         .route("/", get(render_index_page)) // Rota para a página inicial
+        .route("/.well-known/jwks.json", get(controllers::auth_controller::jwks_handler)) // Chaves públicas dos access tokens
         // Rotas específicas da aplicação aninhadas
         .nest("/register", crate::routes::register::RegisterRoute::create_register_route(state.clone()))
         .nest("/login", crate::routes::auth::AuthRoute::get_authenticated(state.clone()))
+        .nest("/logout", crate::routes::auth::AuthRoute::logout_route(state.clone()))
+        .nest("/password", crate::routes::password::PasswordRoute::create_password_routes(state.clone()))
         .nest("/new_ticket", crate::routes::ticket::TicketRoute::create_new_ticket_route(state.clone()))
         .nest(
             "/tickets", // Agrupa rotas relacionadas a tickets existentes
             crate::routes::ticket::TicketRoute::list_tickets_route(state.clone())
-                .merge(crate::routes::ticket::TicketRoute::close_ticket_route(state.clone()))
+                .merge(crate::routes::ticket::TicketRoute::transition_ticket_route(state.clone()))
+                .merge(crate::routes::ticket::TicketRoute::comments_route(state.clone()))
+                .merge(crate::routes::ticket::TicketRoute::attachments_route(state.clone()))
+                .merge(crate::routes::ticket::TicketRoute::history_route(state.clone()))
         )
         .nest("/admin", crate::routes::admin::AdminRoute::create_admin_routes(state.clone()))
+        .nest("/api-keys", crate::routes::api_keys::ApiKeyRoute::create_api_key_routes(state.clone()))
         .with_state(state); // Aplica o estado compartilhado a todas as rotas
     
     // Inicia o servidor
@@ -64,7 +111,8 @@ async fn main() {
     // Por enquanto, vou mantê-lo como um log útil de que o servidor iniciou.
     println!("Servidor a rodar em {}", listener.local_addr().unwrap());
 
-    axum::serve(listener, app)
+    // O endereço da ligação identifica o cliente no limite de pedidos e no bloqueio de login
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .expect("Falha ao iniciar o servidor Axum");
 }
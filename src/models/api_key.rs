@@ -0,0 +1,242 @@
+use std::sync::Arc;
+use axum::http::Method;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{mysql::MySqlRow, Row};
+use std::str::FromStr;
+use crate::models::{appstate::AppState, user::UserRole};
+use crate::services::auth_service::{generate_opaque_token, hash_token};
+
+// Prefixo das chaves, para as distinguir dos JWTs no cabeçalho Authorization
+pub const API_KEY_PREFIX: &str = "hdk_";
+
+// Âmbitos que uma chave pode ter. "tickets" cobre /tickets e /new_ticket; "admin" cobre /admin,
+// exceto a gestão de contas de serviço e chaves. ":write" inclui ":read".
+pub const API_SCOPES: [&str; 4] = ["tickets:read", "tickets:write", "admin:read", "admin:write"];
+
+// Chave de API (sem o segredo, que só é mostrado na criação)
+#[derive(Debug, Serialize)]
+pub struct ApiKey {
+    pub api_key_id: i32,
+    pub api_key_user_id: i32,
+    pub api_key_name: String,
+    pub api_key_prefix: String, // Início da chave, para a reconhecer
+    pub api_key_scopes: Vec<String>,
+    pub api_key_expires_at: Option<NaiveDateTime>, // UTC
+    pub api_key_last_used_at: Option<NaiveDateTime>, // UTC
+    pub api_key_created_at: NaiveDateTime, // UTC
+}
+
+// Dono e âmbitos de uma chave válida apresentada num pedido
+pub struct ApiKeyAuth {
+    pub api_key_id: i32,
+    pub user_id: i32,
+    pub user_role: UserRole,
+    pub scopes: Vec<String>,
+}
+
+const SELECT_API_KEY: &str = "SELECT
+        ID_Api_Key, ID_User, Api_Key_Name, Api_Key_Prefix, Api_Key_Scopes,
+        Api_Key_Expires_At, Api_Key_Last_Used_At, Api_Key_Created_At
+    FROM Api_Keys";
+
+// Âmbito exigido para um pedido (None: rota não acessível com chaves de API)
+pub fn required_scope(method: &Method, path: &str) -> Option<String> {
+    // Compara segmentos inteiros: "/ticketsx" não está dentro de "/tickets"
+    let under = |prefix: &str| path == prefix || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'));
+    let area = if under("/tickets") || under("/new_ticket") {
+        "tickets"
+    } else if under("/admin") && !under("/admin/service-accounts") && !under("/admin/api-keys") {
+        "admin"
+    } else {
+        return None;
+    };
+    let access = if method == Method::GET || method == Method::HEAD { "read" } else { "write" };
+    Some(format!("{}:{}", area, access))
+}
+
+// Indica se os âmbitos de uma chave cobrem o âmbito exigido
+pub fn scopes_allow(scopes: &[String], required: &str) -> bool {
+    let write = required.replace(":read", ":write");
+    scopes.iter().any(|scope| scope == required || *scope == write)
+}
+
+impl ApiKey {
+    fn from_row(row: &MySqlRow) -> Result<ApiKey, sqlx::Error> {
+        let scopes: String = row.try_get("Api_Key_Scopes")?;
+        Ok(ApiKey {
+            api_key_id: row.try_get("ID_Api_Key")?,
+            api_key_user_id: row.try_get("ID_User")?,
+            api_key_name: row.try_get("Api_Key_Name")?,
+            api_key_prefix: row.try_get("Api_Key_Prefix")?,
+            api_key_scopes: scopes.split_whitespace().map(str::to_string).collect(),
+            api_key_expires_at: row.try_get("Api_Key_Expires_At")?,
+            api_key_last_used_at: row.try_get("Api_Key_Last_Used_At")?,
+            api_key_created_at: row.try_get("Api_Key_Created_At")?,
+        })
+    }
+
+    // Cria uma chave para o utilizador e devolve-a com o segredo em claro (mostrado uma única vez)
+    pub async fn create(
+        state: Arc<AppState>,
+        user_id: i32,
+        name: &str,
+        scopes: &[String],
+        expires_at: Option<NaiveDateTime>,
+        created_by: i32,
+    ) -> Result<(ApiKey, String), sqlx::Error> {
+        let secret = format!("{}{}", API_KEY_PREFIX, generate_opaque_token());
+        let prefix: String = secret.chars().take(12).collect();
+
+        let result = sqlx::query(
+            "INSERT INTO Api_Keys (ID_User, Api_Key_Name, Api_Key_Prefix, Api_Key_Hash, Api_Key_Scopes, Api_Key_Expires_At, ID_User_Created_By, Api_Key_Created_At)
+             VALUES (?, ?, ?, ?, ?, ?, ?, UTC_TIMESTAMP())"
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(&prefix)
+        .bind(hash_token(&secret))
+        .bind(scopes.join(" "))
+        .bind(expires_at)
+        .bind(created_by)
+        .execute(&state.pool)
+        .await?;
+
+        let row = sqlx::query(&format!("{} WHERE ID_Api_Key = ?", SELECT_API_KEY))
+            .bind(result.last_insert_id() as i32)
+            .fetch_one(&state.pool)
+            .await?;
+        Ok((ApiKey::from_row(&row)?, secret))
+    }
+
+    // Chaves ainda não revogadas de um utilizador (as expiradas continuam listadas)
+    pub async fn list_by_user(state: Arc<AppState>, user_id: i32) -> Result<Vec<ApiKey>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "{} WHERE ID_User = ? AND Api_Key_Revoked_At IS NULL ORDER BY Api_Key_Created_At DESC",
+            SELECT_API_KEY
+        ))
+        .bind(user_id)
+        .fetch_all(&state.pool)
+        .await?;
+
+        rows.iter().map(ApiKey::from_row).collect()
+    }
+
+    // Revoga uma chave; com `owner_id`, só se pertencer a esse utilizador
+    pub async fn revoke(state: Arc<AppState>, api_key_id: i32, owner_id: Option<i32>) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
+        sqlx::query(
+            "UPDATE Api_Keys SET Api_Key_Revoked_At = UTC_TIMESTAMP()
+             WHERE ID_Api_Key = ? AND (? IS NULL OR ID_User = ?) AND Api_Key_Revoked_At IS NULL"
+        )
+        .bind(api_key_id)
+        .bind(owner_id)
+        .bind(owner_id)
+        .execute(&state.pool)
+        .await
+    }
+
+    // Valida uma chave apresentada num pedido: não revogada, não expirada e de uma conta ativa.
+    // Regista também a data de utilização (no máximo uma escrita por minuto e por chave).
+    pub async fn authenticate(state: &AppState, secret: &str) -> Result<Option<ApiKeyAuth>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT k.ID_Api_Key, k.ID_User, k.Api_Key_Scopes, u.User_Role
+             FROM Api_Keys k
+             JOIN Users u ON u.ID_User = k.ID_User
+             WHERE k.Api_Key_Hash = ? AND k.Api_Key_Revoked_At IS NULL
+               AND (k.Api_Key_Expires_At IS NULL OR k.Api_Key_Expires_At > UTC_TIMESTAMP())
+               AND u.User_Active = TRUE"
+        )
+        .bind(hash_token(secret))
+        .fetch_optional(&state.pool)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let role_str: String = row.try_get("User_Role")?;
+        let scopes: String = row.try_get("Api_Key_Scopes")?;
+        let auth = ApiKeyAuth {
+            api_key_id: row.try_get("ID_Api_Key")?,
+            user_id: row.try_get("ID_User")?,
+            user_role: UserRole::from_str(&role_str).map_err(|e| sqlx::Error::Decode(e.into()))?,
+            scopes: scopes.split_whitespace().map(str::to_string).collect(),
+        };
+
+        sqlx::query(
+            "UPDATE Api_Keys SET Api_Key_Last_Used_At = UTC_TIMESTAMP()
+             WHERE ID_Api_Key = ? AND (Api_Key_Last_Used_At IS NULL OR Api_Key_Last_Used_At < UTC_TIMESTAMP() - INTERVAL 1 MINUTE)"
+        )
+        .bind(auth.api_key_id)
+        .execute(&state.pool)
+        .await?;
+
+        Ok(Some(auth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_scope_by_method_and_path() {
+        let cases = [
+            (Method::GET, "/tickets", Some("tickets:read")),
+            (Method::HEAD, "/tickets/assigned", Some("tickets:read")),
+            (Method::GET, "/tickets/5/history", Some("tickets:read")),
+            (Method::POST, "/tickets/5/comments", Some("tickets:write")),
+            (Method::POST, "/tickets/5/transition", Some("tickets:write")),
+            (Method::DELETE, "/tickets/5/attachments/3", Some("tickets:write")),
+            (Method::GET, "/new_ticket", Some("tickets:read")),
+            (Method::POST, "/new_ticket", Some("tickets:write")),
+            (Method::GET, "/admin/users", Some("admin:read")),
+            (Method::PUT, "/admin/sla", Some("admin:write")),
+            (Method::POST, "/admin/users/7/deactivate", Some("admin:write")),
+            (Method::GET, "/admin", Some("admin:read")),
+            // Gestão de chaves, contas de serviço, login e o resto do site não aceitam chaves
+            (Method::GET, "/admin/service-accounts", None),
+            (Method::POST, "/admin/service-accounts/3/api-keys", None),
+            (Method::DELETE, "/admin/api-keys/9", None),
+            (Method::GET, "/api-keys", None),
+            (Method::POST, "/login", None),
+            (Method::POST, "/login/refresh", None),
+            (Method::POST, "/logout", None),
+            (Method::POST, "/password/change", None),
+            (Method::GET, "/", None),
+            (Method::GET, "/static/js/login.js", None),
+            // Só segmentos inteiros contam
+            (Method::GET, "/ticketsx", None),
+            (Method::GET, "/administrator", None),
+            (Method::GET, "/admin/service-accountsx", Some("admin:read")),
+            // Dentro de um router aninhado o URI perde o prefixo: quem chama tem de passar o original
+            (Method::GET, "/5/history", None),
+            (Method::GET, "/users", None),
+        ];
+        for (method, path, expected) in cases {
+            assert_eq!(required_scope(&method, path).as_deref(), expected, "{} {}", method, path);
+        }
+    }
+
+    #[test]
+    fn scopes_allow_write_to_include_read() {
+        let scopes = |list: &[&str]| list.iter().map(|scope| scope.to_string()).collect::<Vec<_>>();
+        let cases = [
+            (scopes(&["tickets:read"]), "tickets:read", true),
+            (scopes(&["tickets:read"]), "tickets:write", false),
+            (scopes(&["tickets:write"]), "tickets:read", true),
+            (scopes(&["tickets:write"]), "tickets:write", true),
+            (scopes(&["admin:write"]), "tickets:read", false),
+            (scopes(&["tickets:read", "admin:read"]), "admin:read", true),
+            (scopes(&[]), "tickets:read", false),
+            // Não há âmbitos curinga nem outros além de API_SCOPES
+            (scopes(&["*"]), "tickets:read", false),
+            (scopes(&["tickets:*"]), "tickets:read", false),
+            (scopes(&["admin"]), "admin:read", false),
+            (scopes(&["tickets:delete"]), "tickets:write", false),
+            (scopes(&["Tickets:Read"]), "tickets:read", false),
+        ];
+        for (scopes, required, expected) in cases {
+            assert_eq!(scopes_allow(&scopes, required), expected, "{:?} {}", scopes, required);
+        }
+    }
+}
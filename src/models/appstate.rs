@@ -1,8 +1,27 @@
 use tera::Tera;
 use sqlx::mysql::MySqlPool;
+use crate::services::auth_provider::AuthProvider;
+use crate::services::attachment_storage::{AttachmentLimits, AttachmentStorage};
+use crate::services::business_calendar::BusinessCalendar;
+use crate::services::jwt_keys::JwtKeys;
+use crate::services::notification_service::Mailer;
+use crate::services::oidc::OidcClient;
+use crate::services::registration_policy::EmailDomainPolicy;
+use crate::services::session_cookie::SessionCookies;
+use crate::services::totp::TotpConfig;
 
 pub struct AppState {
     pub tera: Tera,
     pub pool: MySqlPool,
-    pub jwt_secret: String,
+    pub jwt_secret: String, // Tokens internos em HS256 (2FA, convites, login por SSO, cookie de sessão); fora do JWKS
+    pub jwt_keys: JwtKeys, // Chaves dos access tokens (HS256 ou RS256/EdDSA com rotação por "kid")
+    pub session_cookies: SessionCookies, // Cookies da sessão do navegador (páginas)
+    pub calendar: BusinessCalendar, // Expediente e feriados usados nos prazos de SLA
+    pub attachment_storage: Box<dyn AttachmentStorage>, // Backend dos anexos (local ou S3)
+    pub attachment_limits: AttachmentLimits,
+    pub mailer: Option<Mailer>, // None quando SMTP_HOST não está configurado
+    pub totp: TotpConfig, // Verificação em duas etapas
+    pub registration_domains: EmailDomainPolicy, // Domínios aceites no registo público
+    pub oidc: Option<OidcClient>, // None quando OIDC_ISSUER não está configurado
+    pub auth_providers: Vec<Box<dyn AuthProvider>>, // Verificação da senha no login (local, LDAP)
 }
@@ -0,0 +1,236 @@
+use std::sync::Arc;
+use chrono::{NaiveDateTime, Utc};
+use rand::RngCore;
+use serde::Serialize;
+use sqlx::{mysql::MySqlRow, MySqlConnection, Row};
+use std::fmt;
+use crate::models::{
+    appstate::AppState,
+    ticket_history::{HistoryAction, HistoryRecord, TicketHistoryEntry},
+};
+
+// Ficheiro recebido num pedido multipart, já validado e ainda não guardado
+#[derive(Debug)]
+pub struct UploadedFile {
+    pub file_name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+// Ticket (e comentário) a que pertencem os anexos guardados numa transação
+pub struct AttachmentOwner {
+    pub ticket_id: i32,
+    pub comment_id: Option<i32>,
+    pub uploader_id: i32,
+    pub is_internal: bool, // Anexos de notas internas
+}
+
+// Falha ao gravar um registo com anexos: no banco ou no backend de armazenamento
+#[derive(Debug)]
+pub enum StoreError {
+    Database(sqlx::Error),
+    Storage(String),
+}
+
+impl From<sqlx::Error> for StoreError {
+    fn from(e: sqlx::Error) -> Self {
+        StoreError::Database(e)
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Database(e) => write!(f, "{}", e),
+            StoreError::Storage(e) => write!(f, "armazenamento dos anexos: {}", e),
+        }
+    }
+}
+
+// Estrutura para representar um anexo de ticket (ou de um comentário do ticket)
+#[derive(Debug, Serialize)]
+pub struct Attachment {
+    pub attachment_id: i32,
+    pub attachment_ticket_id: i32,
+    pub attachment_comment_id: Option<i32>, // None = anexado ao próprio ticket
+    pub attachment_file_name: String,
+    pub attachment_mime_type: String,
+    pub attachment_size: i64,
+    pub attachment_uploader_id: i32,
+    pub attachment_created_at: NaiveDateTime, // UTC
+    #[serde(skip)]
+    pub attachment_storage_key: String,
+}
+
+// SELECT base usado por todas as consultas que devolvem Attachment.
+// O JOIN com os comentários permite esconder anexos de notas internas.
+const SELECT_ATTACHMENTS: &str = "SELECT
+        a.ID_Attachment, a.ID_Ticket, a.ID_Comment, a.Attachment_File_Name,
+        a.Attachment_Mime_Type, a.Attachment_Size, a.ID_User_Uploader,
+        a.Attachment_Created_At, a.Attachment_Storage_Key
+    FROM Ticket_Attachments a
+    LEFT JOIN Ticket_Comments c ON a.ID_Comment = c.ID_Comment";
+
+impl Attachment {
+    // Converte uma linha do SELECT_ATTACHMENTS num Attachment
+    fn from_row(row: &MySqlRow) -> Result<Attachment, sqlx::Error> {
+        Ok(Attachment {
+            attachment_id: row.try_get("ID_Attachment")?,
+            attachment_ticket_id: row.try_get("ID_Ticket")?,
+            attachment_comment_id: row.try_get("ID_Comment")?,
+            attachment_file_name: row.try_get("Attachment_File_Name")?,
+            attachment_mime_type: row.try_get("Attachment_Mime_Type")?,
+            attachment_size: row.try_get("Attachment_Size")?,
+            attachment_uploader_id: row.try_get("ID_User_Uploader")?,
+            attachment_created_at: row.try_get("Attachment_Created_At")?,
+            attachment_storage_key: row.try_get("Attachment_Storage_Key")?,
+        })
+    }
+
+    // Envia os ficheiros para o backend de armazenamento e regista-os na transação `conn`,
+    // a mesma do ticket ou comentário a que pertencem. Se algum falhar, os ficheiros já
+    // enviados são apagados e o erro é devolvido para que a transação seja desfeita.
+    pub async fn store_all(
+        conn: &mut MySqlConnection,
+        state: &AppState,
+        owner: AttachmentOwner,
+        files: &[UploadedFile],
+    ) -> Result<Vec<Attachment>, StoreError> {
+        let mut attachments = Vec::with_capacity(files.len());
+        for file in files {
+            match Self::store_one(conn, state, &owner, file).await {
+                Ok(attachment) => attachments.push(attachment),
+                Err(e) => {
+                    Self::discard_stored(state, &attachments).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(attachments)
+    }
+
+    async fn store_one(
+        conn: &mut MySqlConnection,
+        state: &AppState,
+        owner: &AttachmentOwner,
+        file: &UploadedFile,
+    ) -> Result<Attachment, StoreError> {
+        // Chave aleatória: o nome original nunca é usado no caminho de armazenamento
+        let mut random = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut random);
+        let storage_key = format!("tickets/{}/{}", owner.ticket_id, hex::encode(random));
+
+        state
+            .attachment_storage
+            .put(&storage_key, &file.content_type, &file.bytes)
+            .await
+            .map_err(StoreError::Storage)?;
+        match Self::insert_row(conn, owner, file, storage_key.clone()).await {
+            Ok(attachment) => Ok(attachment),
+            Err(e) => {
+                // Em produção, logar se o ficheiro não puder ser apagado.
+                let _ = state.attachment_storage.delete(&storage_key).await;
+                Err(e.into())
+            }
+        }
+    }
+
+    // Regista os metadados do ficheiro já guardado e a entrada correspondente no histórico
+    async fn insert_row(
+        conn: &mut MySqlConnection,
+        owner: &AttachmentOwner,
+        file: &UploadedFile,
+        storage_key: String,
+    ) -> Result<Attachment, sqlx::Error> {
+        let created_at = Utc::now().naive_utc();
+        let result = sqlx::query(
+            "INSERT INTO Ticket_Attachments (
+                ID_Ticket, ID_Comment, Attachment_File_Name, Attachment_Mime_Type,
+                Attachment_Size, ID_User_Uploader, Attachment_Created_At, Attachment_Storage_Key
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(owner.ticket_id)
+        .bind(owner.comment_id)
+        .bind(&file.file_name)
+        .bind(&file.content_type)
+        .bind(file.bytes.len() as i64)
+        .bind(owner.uploader_id)
+        .bind(created_at)
+        .bind(&storage_key)
+        .execute(&mut *conn)
+        .await?;
+
+        // Anexos de notas internas também ficam ocultos no histórico dos clientes
+        TicketHistoryEntry::record(conn, HistoryRecord {
+            ticket_id: owner.ticket_id,
+            actor_id: Some(owner.uploader_id),
+            action: HistoryAction::Anexo,
+            old_value: None,
+            new_value: Some(&file.file_name),
+            is_internal: owner.is_internal,
+        })
+        .await?;
+
+        Ok(Attachment {
+            attachment_id: result.last_insert_id() as i32,
+            attachment_ticket_id: owner.ticket_id,
+            attachment_comment_id: owner.comment_id,
+            attachment_file_name: file.file_name.clone(),
+            attachment_mime_type: file.content_type.clone(),
+            attachment_size: file.bytes.len() as i64,
+            attachment_uploader_id: owner.uploader_id,
+            attachment_created_at: created_at,
+            attachment_storage_key: storage_key,
+        })
+    }
+
+    // Apaga do armazenamento os ficheiros de anexos cuja transação não foi confirmada
+    pub async fn discard_stored(state: &AppState, attachments: &[Attachment]) {
+        for attachment in attachments {
+            // Um ficheiro que não possa ser apagado fica apenas sem referência (em produção, logar).
+            let _ = state.attachment_storage.delete(&attachment.attachment_storage_key).await;
+        }
+    }
+
+    // Lista os anexos de um ticket; sem include_internal omite os de notas internas
+    pub async fn get_attachments_by_ticket(
+        state: Arc<AppState>,
+        ticket_id: i32,
+        include_internal: bool,
+    ) -> Result<Vec<Attachment>, sqlx::Error> {
+        let visibility_filter = if include_internal { "" } else { " AND COALESCE(c.Comment_Is_Internal, FALSE) = FALSE" };
+        let query_sql = format!(
+            "{} WHERE a.ID_Ticket = ?{} ORDER BY a.Attachment_Created_At, a.ID_Attachment",
+            SELECT_ATTACHMENTS, visibility_filter
+        );
+
+        let rows = sqlx::query(&query_sql)
+            .bind(ticket_id)
+            .fetch_all(&state.pool)
+            .await?;
+
+        rows.iter().map(Attachment::from_row).collect()
+    }
+
+    // Busca um anexo de um ticket respeitando a mesma regra de visibilidade
+    pub async fn get_attachment(
+        state: Arc<AppState>,
+        ticket_id: i32,
+        attachment_id: i32,
+        include_internal: bool,
+    ) -> Result<Option<Attachment>, sqlx::Error> {
+        let visibility_filter = if include_internal { "" } else { " AND COALESCE(c.Comment_Is_Internal, FALSE) = FALSE" };
+        let query_sql = format!(
+            "{} WHERE a.ID_Ticket = ? AND a.ID_Attachment = ?{}",
+            SELECT_ATTACHMENTS, visibility_filter
+        );
+
+        let row = sqlx::query(&query_sql)
+            .bind(ticket_id)
+            .bind(attachment_id)
+            .fetch_optional(&state.pool)
+            .await?;
+
+        row.as_ref().map(Attachment::from_row).transpose()
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{mysql::MySqlRow, Row};
+use crate::models::{
+    appstate::AppState,
+    attachment::{Attachment, AttachmentOwner, StoreError, UploadedFile},
+    ticket_history::{HistoryAction, HistoryRecord, TicketHistoryEntry},
+};
+
+// Estrutura para representar um comentário (ou resposta) num ticket
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TicketComment {
+    pub comment_id: Option<i32>,
+    pub comment_ticket_id: i32,
+    pub comment_parent_id: Option<i32>, // Preenchido quando é resposta a outro comentário
+    pub comment_author_id: i32,
+    pub comment_author_name: String,
+    pub comment_body: String,
+    pub comment_is_internal: bool, // Nota interna: visível apenas para a equipa
+    pub comment_created_at: NaiveDateTime, // UTC
+    #[serde(default, skip_deserializing)]
+    pub comment_attachments: Vec<Attachment>, // Preenchido ao criar o comentário e ao listar a conversa
+}
+
+// Estrutura para criar um novo comentário
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewTicketComment {
+    pub comment_body: String,
+    pub comment_parent_id: Option<i32>,
+    #[serde(default)]
+    pub comment_is_internal: bool,
+}
+
+// SELECT base usado por todas as consultas que devolvem TicketComment
+const SELECT_COMMENTS: &str = "SELECT
+        c.ID_Comment, c.ID_Ticket, c.ID_Parent_Comment, c.ID_User_Author,
+        c.Comment_Body, c.Comment_Is_Internal, c.Comment_Created_At,
+        u.User_Name AS author_name_from_db
+    FROM Ticket_Comments c
+    JOIN Users u ON c.ID_User_Author = u.ID_User";
+
+impl TicketComment {
+    // Converte uma linha do SELECT_COMMENTS num TicketComment
+    fn from_row(row: &MySqlRow) -> Result<TicketComment, sqlx::Error> {
+        Ok(TicketComment {
+            comment_id: row.try_get("ID_Comment")?,
+            comment_ticket_id: row.try_get("ID_Ticket")?,
+            comment_parent_id: row.try_get("ID_Parent_Comment")?,
+            comment_author_id: row.try_get("ID_User_Author")?,
+            comment_author_name: row.try_get("author_name_from_db")?,
+            comment_body: row.try_get("Comment_Body")?,
+            comment_is_internal: row.try_get("Comment_Is_Internal")?,
+            comment_created_at: row.try_get("Comment_Created_At")?,
+            comment_attachments: Vec::new(),
+        })
+    }
+
+    // Busca a conversa de um ticket em ordem cronológica.
+    // O frontend monta a árvore de respostas a partir de comment_parent_id.
+    // Com include_internal = false as notas internas nem saem do banco.
+    pub async fn get_comments_by_ticket(
+        state: Arc<AppState>,
+        ticket_id: i32,
+        include_internal: bool,
+    ) -> Result<Vec<TicketComment>, sqlx::Error> {
+        let visibility_filter = if include_internal { "" } else { " AND c.Comment_Is_Internal = FALSE" };
+        let query_sql = format!(
+            "{} WHERE c.ID_Ticket = ?{} ORDER BY c.Comment_Created_At, c.ID_Comment",
+            SELECT_COMMENTS, visibility_filter
+        );
+
+        let rows = sqlx::query(&query_sql)
+            .bind(ticket_id)
+            .fetch_all(&state.pool)
+            .await?;
+
+        rows.iter().map(TicketComment::from_row).collect()
+    }
+
+    // Procura um comentário do ticket (para validar respostas).
+    // Devolve Some(is_internal) se existir, None caso contrário.
+    pub async fn get_comment_visibility(
+        state: Arc<AppState>,
+        comment_id: i32,
+        ticket_id: i32,
+    ) -> Result<Option<bool>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT Comment_Is_Internal FROM Ticket_Comments WHERE ID_Comment = ? AND ID_Ticket = ?"
+        )
+        .bind(comment_id)
+        .bind(ticket_id)
+        .fetch_optional(&state.pool)
+        .await
+    }
+
+    // Salva um novo comentário e devolve-o já com o nome do autor
+    // O comentário e os seus anexos são gravados juntos: se um anexo falhar, nada fica.
+    pub async fn save_new_comment_in_db(
+        new_comment: &NewTicketComment,
+        ticket_id: i32,
+        author_id: i32,
+        state: Arc<AppState>,
+        files: &[UploadedFile],
+    ) -> Result<TicketComment, StoreError> {
+        let query_sql = "INSERT INTO Ticket_Comments (
+                ID_Ticket, ID_Parent_Comment, ID_User_Author, Comment_Body,
+                Comment_Is_Internal, Comment_Created_At
+            ) VALUES (?, ?, ?, ?, ?, ?)";
+
+        let mut tx = state.pool.begin().await?;
+        let result = sqlx::query(query_sql)
+            .bind(ticket_id)
+            .bind(new_comment.comment_parent_id)
+            .bind(author_id)
+            .bind(&new_comment.comment_body)
+            .bind(new_comment.comment_is_internal)
+            .bind(Utc::now().naive_utc())
+            .execute(&mut *tx)
+            .await?;
+
+        TicketHistoryEntry::record(&mut tx, HistoryRecord {
+            ticket_id,
+            actor_id: Some(author_id),
+            action: if new_comment.comment_is_internal { HistoryAction::NotaInterna } else { HistoryAction::Comentario },
+            old_value: None,
+            new_value: Some(&new_comment.comment_body),
+            is_internal: new_comment.comment_is_internal,
+        })
+        .await?;
+        let owner = AttachmentOwner {
+            ticket_id,
+            comment_id: Some(result.last_insert_id() as i32),
+            uploader_id: author_id,
+            is_internal: new_comment.comment_is_internal,
+        };
+        let attachments = Attachment::store_all(&mut tx, &state, owner, files).await?;
+        if let Err(e) = tx.commit().await {
+            Attachment::discard_stored(&state, &attachments).await;
+            return Err(e.into());
+        }
+
+        let query_sql = format!("{} WHERE c.ID_Comment = ?", SELECT_COMMENTS);
+        let row = sqlx::query(&query_sql)
+            .bind(result.last_insert_id())
+            .fetch_one(&state.pool)
+            .await?;
+
+        let mut comment = TicketComment::from_row(&row)?;
+        comment.comment_attachments = attachments;
+        Ok(comment)
+    }
+}
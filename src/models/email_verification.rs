@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use sqlx::Row;
+use crate::models::appstate::AppState;
+use crate::services::auth_service::{generate_opaque_token, hash_token};
+
+// Validade de um link de confirmação de email
+pub const VERIFICATION_TOKEN_HOURS: i64 = 48;
+
+// Tokens de confirmação do email de uma conta criada no registo público: de uso único
+// e guardados apenas como hash SHA-256, como os de redefinição de senha.
+pub struct EmailVerification;
+
+impl EmailVerification {
+    // Emite um token para o utilizador. Os links anteriores ainda pendentes deixam de valer.
+    pub async fn create_token(state: Arc<AppState>, user_id: i32) -> Result<String, sqlx::Error> {
+        let token = generate_opaque_token();
+        let expires_at = Utc::now().naive_utc() + Duration::hours(VERIFICATION_TOKEN_HOURS);
+
+        let mut tx = state.pool.begin().await?;
+        sqlx::query(
+            "UPDATE Email_Verifications SET Verification_Used_At = UTC_TIMESTAMP()
+             WHERE ID_User = ? AND Verification_Used_At IS NULL"
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO Email_Verifications (ID_User, Verification_Token_Hash, Verification_Expires_At, Verification_Created_At)
+             VALUES (?, ?, ?, UTC_TIMESTAMP())"
+        )
+        .bind(user_id)
+        .bind(hash_token(&token))
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(token)
+    }
+
+    // Usa o token para marcar o email da conta como verificado.
+    // Devolve false se o token for inválido, já usado ou expirado.
+    pub async fn verify(state: Arc<AppState>, token: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        let row = sqlx::query(
+            "SELECT ID_Email_Verification, ID_User FROM Email_Verifications
+             WHERE Verification_Token_Hash = ? AND Verification_Used_At IS NULL AND Verification_Expires_At > UTC_TIMESTAMP()
+             FOR UPDATE"
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(false);
+        };
+        let verification_id: i32 = row.try_get("ID_Email_Verification")?;
+        let user_id: i32 = row.try_get("ID_User")?;
+
+        sqlx::query("UPDATE Email_Verifications SET Verification_Used_At = UTC_TIMESTAMP() WHERE ID_Email_Verification = ?")
+            .bind(verification_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE Users SET User_Email_Verified = TRUE WHERE ID_User = ?")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
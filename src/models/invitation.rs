@@ -0,0 +1,142 @@
+use std::sync::Arc;
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::{mysql::MySqlRow, Row};
+use std::str::FromStr;
+use crate::models::{appstate::AppState, user::UserRole};
+
+// Validade de um convite
+pub const INVITATION_DAYS: i64 = 7;
+
+// Convite para criar uma conta da equipa. O link enviado por email leva um token assinado
+// com o ID do convite; a linha no banco permite listar, revogar e aceitar uma única vez.
+#[derive(Debug, Serialize)]
+pub struct Invitation {
+    pub invitation_id: i32,
+    pub invitation_email: String,
+    pub invitation_role: UserRole,
+    pub invitation_invited_by_name: Option<String>,
+    pub invitation_expires_at: NaiveDateTime, // UTC
+    pub invitation_created_at: NaiveDateTime, // UTC
+}
+
+// Condição dos convites que ainda podem ser aceites
+const PENDING_FILTER: &str =
+    "i.Invitation_Accepted_At IS NULL AND i.Invitation_Revoked_At IS NULL AND i.Invitation_Expires_At > UTC_TIMESTAMP()";
+
+const SELECT_INVITATION: &str = "SELECT
+        i.ID_Invitation, i.Invitation_Email, i.Invitation_Role, u.User_Name AS invited_by_name_from_db,
+        i.Invitation_Expires_At, i.Invitation_Created_At
+    FROM Staff_Invitations i
+    LEFT JOIN Users u ON i.ID_User_Invited_By = u.ID_User";
+
+impl Invitation {
+    fn from_row(row: &MySqlRow) -> Result<Invitation, sqlx::Error> {
+        let role_str: String = row.try_get("Invitation_Role")?;
+        let role = UserRole::from_str(&role_str).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        Ok(Invitation {
+            invitation_id: row.try_get("ID_Invitation")?,
+            invitation_email: row.try_get("Invitation_Email")?,
+            invitation_role: role,
+            invitation_invited_by_name: row.try_get("invited_by_name_from_db")?,
+            invitation_expires_at: row.try_get("Invitation_Expires_At")?,
+            invitation_created_at: row.try_get("Invitation_Created_At")?,
+        })
+    }
+
+    // Cria um convite; um convite ainda pendente para o mesmo email é revogado
+    pub async fn create(state: Arc<AppState>, email: &str, role: UserRole, invited_by: i32) -> Result<Invitation, sqlx::Error> {
+        let expires_at = Utc::now().naive_utc() + Duration::days(INVITATION_DAYS);
+        let mut tx = state.pool.begin().await?;
+        sqlx::query(
+            "UPDATE Staff_Invitations SET Invitation_Revoked_At = UTC_TIMESTAMP()
+             WHERE Invitation_Email = ? AND Invitation_Accepted_At IS NULL AND Invitation_Revoked_At IS NULL"
+        )
+        .bind(email)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query(
+            "INSERT INTO Staff_Invitations (Invitation_Email, Invitation_Role, ID_User_Invited_By, Invitation_Expires_At, Invitation_Created_At)
+             VALUES (?, ?, ?, ?, UTC_TIMESTAMP())"
+        )
+        .bind(email)
+        .bind(role.to_string())
+        .bind(invited_by)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+        let row = sqlx::query(&format!("{} WHERE i.ID_Invitation = ?", SELECT_INVITATION))
+            .bind(result.last_insert_id() as i32)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Invitation::from_row(&row)
+    }
+
+    // Convites ainda por aceitar, não revogados e dentro da validade
+    pub async fn get_pending(state: Arc<AppState>) -> Result<Vec<Invitation>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "{} WHERE {} ORDER BY i.Invitation_Created_At DESC",
+            SELECT_INVITATION, PENDING_FILTER
+        ))
+        .fetch_all(&state.pool)
+        .await?;
+
+        rows.iter().map(Invitation::from_row).collect()
+    }
+
+    // Convite pendente pelo ID (None se não existir, já tiver sido usado, revogado ou expirado)
+    pub async fn get_pending_by_id(state: Arc<AppState>, invitation_id: i32) -> Result<Option<Invitation>, sqlx::Error> {
+        let row = sqlx::query(&format!("{} WHERE i.ID_Invitation = ? AND {}", SELECT_INVITATION, PENDING_FILTER))
+            .bind(invitation_id)
+            .fetch_optional(&state.pool)
+            .await?;
+
+        row.as_ref().map(Invitation::from_row).transpose()
+    }
+
+    pub async fn revoke(state: Arc<AppState>, invitation_id: i32) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
+        sqlx::query(
+            "UPDATE Staff_Invitations SET Invitation_Revoked_At = UTC_TIMESTAMP()
+             WHERE ID_Invitation = ? AND Invitation_Accepted_At IS NULL AND Invitation_Revoked_At IS NULL"
+        )
+        .bind(invitation_id)
+        .execute(&state.pool)
+        .await
+    }
+
+    // Aceita o convite criando a conta (com o email e o papel do convite) na mesma transação.
+    // Devolve false se o convite já não estiver pendente.
+    pub async fn accept(
+        state: Arc<AppState>,
+        invitation_id: i32,
+        user_name: &str,
+        hashed_password: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        let row = sqlx::query(&format!("{} WHERE i.ID_Invitation = ? AND {} FOR UPDATE", SELECT_INVITATION, PENDING_FILTER))
+            .bind(invitation_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(row) = row else {
+            return Ok(false);
+        };
+        let invitation = Invitation::from_row(&row)?;
+
+        sqlx::query("INSERT INTO Users (User_Name, User_Email, User_Password, User_Role) VALUES (?, ?, ?, ?)")
+            .bind(user_name)
+            .bind(&invitation.invitation_email)
+            .bind(hashed_password)
+            .bind(invitation.invitation_role.to_string())
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE Staff_Invitations SET Invitation_Accepted_At = UTC_TIMESTAMP() WHERE ID_Invitation = ?")
+            .bind(invitation_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,209 @@
+use std::sync::Arc;
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::{MySql, Row, Transaction};
+use crate::models::appstate::AppState;
+
+// Falhas mais antigas do que isto (sem novas tentativas) deixam de contar
+const FAILURE_MEMORY_HOURS: i64 = 24;
+
+// Política de atraso progressivo: as primeiras falhas são livres; a partir daí cada falha
+// dobra a espera até à tentativa seguinte e, a partir de lockout_after, a chave fica
+// bloqueada durante lockout_minutes.
+pub struct ThrottlePolicy {
+    pub free_failures: u32,
+    pub lockout_after: u32,
+    pub lockout_minutes: i64,
+}
+
+// Por conta (email tentado, ou utilizador na segunda etapa)
+pub const ACCOUNT_POLICY: ThrottlePolicy = ThrottlePolicy { free_failures: 3, lockout_after: 10, lockout_minutes: 15 };
+// Por IP: mais tolerante, pois vários utilizadores podem partilhar o mesmo endereço
+pub const IP_POLICY: ThrottlePolicy = ThrottlePolicy { free_failures: 10, lockout_after: 50, lockout_minutes: 15 };
+
+impl ThrottlePolicy {
+    fn delay_after(&self, failures: u32) -> Duration {
+        let lockout = Duration::minutes(self.lockout_minutes);
+        if failures <= self.free_failures {
+            Duration::zero()
+        } else if failures >= self.lockout_after {
+            lockout
+        } else {
+            let exponent = (failures - self.free_failures).min(20);
+            Duration::seconds(1i64 << exponent).min(lockout)
+        }
+    }
+
+    // Falhas de uma chave depois de mais uma e até quando ela fica bloqueada
+    fn after_failure(&self, previous_failures: u32, now: NaiveDateTime) -> (u32, Option<NaiveDateTime>) {
+        let failures = previous_failures + 1;
+        let delay = self.delay_after(failures);
+        (failures, (delay > Duration::zero()).then(|| now + delay))
+    }
+}
+
+// Falhas que ainda contam: são esquecidas após FAILURE_MEMORY_HOURS sem novas falhas
+fn recent_failures(failures: u32, last_failure: NaiveDateTime, now: NaiveDateTime) -> u32 {
+    if now - last_failure > Duration::hours(FAILURE_MEMORY_HOURS) { 0 } else { failures }
+}
+
+// Segundos (arredondados para cima) até ao fim de um bloqueio; None se já terminou
+fn remaining_lock(locked_until: Option<NaiveDateTime>, now: NaiveDateTime) -> Option<i64> {
+    locked_until.filter(|until| *until > now).map(|until| (until - now).num_seconds() + 1)
+}
+
+// Registo das tentativas de login falhadas, por chave ("conta:<email>", "ip:<endereço>", ...).
+// Guardado no banco para valer entre reinícios e entre instâncias da aplicação.
+pub struct LoginThrottle;
+
+// Tentativa de login em curso. As linhas das chaves ficam bloqueadas (SELECT ... FOR UPDATE)
+// desde a verificação do bloqueio até ao registo do resultado, na mesma transação, para que
+// pedidos simultâneos com a mesma conta ou IP sejam contados um a um.
+pub struct ThrottledAttempt {
+    tx: Transaction<'static, MySql>,
+    now: NaiveDateTime,
+    rows: Vec<(String, u32, Option<NaiveDateTime>)>, // Chave, falhas recentes, bloqueio
+}
+
+impl LoginThrottle {
+    // Abre a transação da tentativa e bloqueia as linhas das chaves (criadas vazias se preciso)
+    pub async fn begin(state: Arc<AppState>, keys: &[String]) -> Result<ThrottledAttempt, sqlx::Error> {
+        let now = Utc::now().naive_utc();
+        let mut tx = state.pool.begin().await?;
+
+        // Ordem fixa das chaves: duas tentativas nunca esperam uma pela outra em sentidos opostos
+        let mut keys = keys.to_vec();
+        keys.sort();
+        keys.dedup();
+        let mut rows = Vec::with_capacity(keys.len());
+        for key in keys {
+            // ON DUPLICATE KEY UPDATE (e não INSERT IGNORE) bloqueia logo a linha em modo exclusivo
+            sqlx::query(
+                "INSERT INTO Login_Throttle (Throttle_Key, Throttle_Failures, Throttle_Last_Failure_At)
+                 VALUES (?, 0, ?)
+                 ON DUPLICATE KEY UPDATE Throttle_Key = Throttle_Key"
+            )
+            .bind(&key)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+            let row = sqlx::query(
+                "SELECT Throttle_Failures, Throttle_Last_Failure_At, Throttle_Locked_Until
+                 FROM Login_Throttle WHERE Throttle_Key = ? FOR UPDATE"
+            )
+            .bind(&key)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            let failures: u32 = row.try_get("Throttle_Failures")?;
+            let last_failure: NaiveDateTime = row.try_get("Throttle_Last_Failure_At")?;
+            rows.push((key, recent_failures(failures, last_failure, now), row.try_get("Throttle_Locked_Until")?));
+        }
+        Ok(ThrottledAttempt { tx, now, rows })
+    }
+}
+
+impl ThrottledAttempt {
+    // Segundos até poder tentar de novo, considerando a chave mais restrita (None = pode tentar)
+    pub fn retry_after(&self) -> Option<i64> {
+        self.rows
+            .iter()
+            .filter_map(|(_, _, locked_until)| remaining_lock(*locked_until, self.now))
+            .max()
+    }
+
+    // Conta mais uma falha para a chave e calcula até quando ela fica bloqueada
+    pub async fn record_failure(&mut self, key: &str, policy: &ThrottlePolicy) -> Result<(), sqlx::Error> {
+        let previous_failures = self
+            .rows
+            .iter()
+            .find(|(row_key, _, _)| row_key == key)
+            .map_or(0, |(_, failures, _)| *failures);
+        let (failures, locked_until) = policy.after_failure(previous_failures, self.now);
+
+        sqlx::query(
+            "INSERT INTO Login_Throttle (Throttle_Key, Throttle_Failures, Throttle_Last_Failure_At, Throttle_Locked_Until)
+             VALUES (?, ?, ?, ?)
+             ON DUPLICATE KEY UPDATE Throttle_Failures = VALUES(Throttle_Failures),
+                Throttle_Last_Failure_At = VALUES(Throttle_Last_Failure_At),
+                Throttle_Locked_Until = VALUES(Throttle_Locked_Until)"
+        )
+        .bind(key)
+        .bind(failures)
+        .bind(self.now)
+        .bind(locked_until)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    // Esquece as falhas de uma chave (login bem-sucedido)
+    pub async fn clear(&mut self, key: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM Login_Throttle WHERE Throttle_Key = ?")
+            .bind(key)
+            .execute(&mut *self.tx)
+            .await?;
+        Ok(())
+    }
+
+    // Grava o resultado da tentativa e liberta as linhas
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        self.tx.commit().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn free_failures_have_no_delay() {
+        for failures in 0..=ACCOUNT_POLICY.free_failures {
+            assert_eq!(ACCOUNT_POLICY.delay_after(failures), Duration::zero());
+        }
+        assert_eq!(ACCOUNT_POLICY.after_failure(2, at(10, 0, 0)), (3, None));
+        assert_eq!(IP_POLICY.delay_after(10), Duration::zero());
+        assert_eq!(IP_POLICY.delay_after(11), Duration::seconds(2));
+    }
+
+    #[test]
+    fn delay_doubles_until_the_lockout() {
+        let delays: Vec<i64> = (4..=10).map(|failures| ACCOUNT_POLICY.delay_after(failures).num_seconds()).collect();
+        assert_eq!(delays, [2, 4, 8, 16, 32, 64, 15 * 60]);
+
+        // Antes de lockout_after, a espera nunca passa do bloqueio
+        let policy = ThrottlePolicy { free_failures: 0, lockout_after: 40, lockout_minutes: 1 };
+        assert_eq!(policy.delay_after(6), Duration::minutes(1));
+        assert_eq!(policy.delay_after(39), Duration::minutes(1));
+        // Falhas acima de lockout_after mantêm o bloqueio
+        assert_eq!(IP_POLICY.delay_after(500), Duration::minutes(15));
+    }
+
+    #[test]
+    fn a_failure_locks_the_key_from_now() {
+        let now = at(10, 0, 0);
+        assert_eq!(ACCOUNT_POLICY.after_failure(3, now), (4, Some(at(10, 0, 2))));
+        assert_eq!(ACCOUNT_POLICY.after_failure(9, now), (10, Some(at(10, 15, 0))));
+    }
+
+    #[test]
+    fn lockout_expires() {
+        let until = Some(at(10, 15, 0));
+        assert_eq!(remaining_lock(until, at(10, 0, 0)), Some(15 * 60 + 1));
+        assert_eq!(remaining_lock(until, at(10, 14, 59)), Some(2));
+        assert_eq!(remaining_lock(until, at(10, 15, 0)), None);
+        assert_eq!(remaining_lock(until, at(11, 0, 0)), None);
+        assert_eq!(remaining_lock(None, at(10, 0, 0)), None);
+    }
+
+    #[test]
+    fn old_failures_are_forgotten() {
+        let last_failure = at(10, 0, 0);
+        assert_eq!(recent_failures(7, last_failure, last_failure + Duration::hours(24)), 7);
+        assert_eq!(recent_failures(7, last_failure, last_failure + Duration::hours(24) + Duration::seconds(1)), 0);
+    }
+}
@@ -1,4 +1,16 @@
 
 pub mod appstate; 
 pub mod user;     
-pub mod ticket;   
+pub mod ticket;
+pub mod comment;
+pub mod sla;
+pub mod attachment;
+pub mod ticket_history;
+pub mod session;
+pub mod password_reset;
+pub mod two_factor;
+pub mod login_throttle;
+pub mod invitation;
+pub mod email_verification;
+pub mod api_key;
+pub mod permission;
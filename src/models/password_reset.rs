@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use sqlx::Row;
+use crate::models::{appstate::AppState, session::UserSession};
+use crate::services::auth_service::{generate_opaque_token, hash_token};
+
+// Validade de um link de redefinição de senha
+pub const RESET_TOKEN_MINUTES: i64 = 60;
+
+// Tokens de redefinição de senha: de uso único, com validade curta e guardados
+// apenas como hash SHA-256 (o token em claro só existe no email enviado).
+pub struct PasswordReset;
+
+impl PasswordReset {
+    // Emite um token para o utilizador. Os pedidos anteriores ainda pendentes deixam de valer.
+    pub async fn create_token(state: Arc<AppState>, user_id: i32) -> Result<String, sqlx::Error> {
+        let token = generate_opaque_token();
+        let expires_at = Utc::now().naive_utc() + Duration::minutes(RESET_TOKEN_MINUTES);
+
+        let mut tx = state.pool.begin().await?;
+        sqlx::query(
+            "UPDATE Password_Resets SET Reset_Used_At = UTC_TIMESTAMP()
+             WHERE ID_User = ? AND Reset_Used_At IS NULL"
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO Password_Resets (ID_User, Reset_Token_Hash, Reset_Expires_At, Reset_Created_At)
+             VALUES (?, ?, ?, UTC_TIMESTAMP())"
+        )
+        .bind(user_id)
+        .bind(hash_token(&token))
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(token)
+    }
+
+    // Indica se o token existe, não foi usado e não expirou (para exibir o formulário)
+    pub async fn is_valid(state: Arc<AppState>, token: &str) -> Result<bool, sqlx::Error> {
+        let found: Option<i32> = sqlx::query_scalar(
+            "SELECT ID_User FROM Password_Resets
+             WHERE Reset_Token_Hash = ? AND Reset_Used_At IS NULL AND Reset_Expires_At > UTC_TIMESTAMP()"
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&state.pool)
+        .await?;
+        Ok(found.is_some())
+    }
+
+    // Usa o token para gravar a nova senha (já com hash) e encerra todas as sessões
+    // do utilizador. Devolve false se o token for inválido, já usado ou expirado.
+    pub async fn reset_password(state: Arc<AppState>, token: &str, hashed_password: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        let row = sqlx::query(
+            "SELECT ID_Password_Reset, ID_User FROM Password_Resets
+             WHERE Reset_Token_Hash = ? AND Reset_Used_At IS NULL AND Reset_Expires_At > UTC_TIMESTAMP()
+             FOR UPDATE"
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(false);
+        };
+        let reset_id: i32 = row.try_get("ID_Password_Reset")?;
+        let user_id: i32 = row.try_get("ID_User")?;
+
+        sqlx::query("UPDATE Password_Resets SET Reset_Used_At = UTC_TIMESTAMP() WHERE ID_Password_Reset = ?")
+            .bind(reset_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE Users SET User_Password = ? WHERE ID_User = ?")
+            .bind(hashed_password)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        UserSession::revoke_all_sessions(&mut tx, user_id).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::fmt;
+use std::str::FromStr;
+use crate::models::{appstate::AppState, user::UserRole};
+
+// Permissões que um papel pode ter. Os papéis (Cliente, Tecnico, Administrador) são
+// conjuntos de permissões guardados em Role_Permissions e editáveis em /admin/roles.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Permission {
+    #[serde(rename = "ticket.create")]
+    TicketCreate,
+    #[serde(rename = "ticket.read.all")]
+    TicketReadAll,
+    #[serde(rename = "ticket.list.assigned")]
+    TicketListAssigned,
+    #[serde(rename = "ticket.list.all")]
+    TicketListAll,
+    #[serde(rename = "ticket.internal")]
+    TicketInternal,
+    #[serde(rename = "ticket.transition.any")]
+    TicketTransitionAny,
+    #[serde(rename = "ticket.set_priority")]
+    TicketSetPriority,
+    #[serde(rename = "ticket.assign")]
+    TicketAssign,
+    #[serde(rename = "sla.manage")]
+    SlaManage,
+    #[serde(rename = "user.manage")]
+    UserManage,
+    #[serde(rename = "invitation.manage")]
+    InvitationManage,
+    #[serde(rename = "service_account.manage")]
+    ServiceAccountManage,
+    #[serde(rename = "role.manage")]
+    RoleManage,
+}
+
+pub const PERMISSIONS: [Permission; 13] = [
+    Permission::TicketCreate,
+    Permission::TicketReadAll,
+    Permission::TicketListAssigned,
+    Permission::TicketListAll,
+    Permission::TicketInternal,
+    Permission::TicketTransitionAny,
+    Permission::TicketSetPriority,
+    Permission::TicketAssign,
+    Permission::SlaManage,
+    Permission::UserManage,
+    Permission::InvitationManage,
+    Permission::ServiceAccountManage,
+    Permission::RoleManage,
+];
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::TicketCreate => "ticket.create",
+            Permission::TicketReadAll => "ticket.read.all",
+            Permission::TicketListAssigned => "ticket.list.assigned",
+            Permission::TicketListAll => "ticket.list.all",
+            Permission::TicketInternal => "ticket.internal",
+            Permission::TicketTransitionAny => "ticket.transition.any",
+            Permission::TicketSetPriority => "ticket.set_priority",
+            Permission::TicketAssign => "ticket.assign",
+            Permission::SlaManage => "sla.manage",
+            Permission::UserManage => "user.manage",
+            Permission::InvitationManage => "invitation.manage",
+            Permission::ServiceAccountManage => "service_account.manage",
+            Permission::RoleManage => "role.manage",
+        }
+    }
+
+    // Descrição mostrada na lista de permissões de GET /admin/roles
+    pub fn description(&self) -> &'static str {
+        match self {
+            Permission::TicketCreate => "Abrir chamados",
+            Permission::TicketReadAll => "Ver e comentar qualquer chamado (sem ela, apenas os próprios)",
+            Permission::TicketListAssigned => "Painel da equipa com os chamados atribuídos",
+            Permission::TicketListAll => "Pesquisar todos os chamados no painel",
+            Permission::TicketInternal => "Notas internas e respostas da equipa (contam para o SLA)",
+            Permission::TicketTransitionAny => "Qualquer transição de estado (sem ela, apenas fechar e reabrir)",
+            Permission::TicketSetPriority => "Definir a prioridade dos chamados",
+            Permission::TicketAssign => "Atribuir chamados a técnicos",
+            Permission::SlaManage => "Configurar as políticas de SLA",
+            Permission::UserManage => "Criar utilizadores, alterar papéis e desativar contas",
+            Permission::InvitationManage => "Convidar membros da equipa",
+            Permission::ServiceAccountManage => "Gerir contas de serviço e revogar qualquer chave de API",
+            Permission::RoleManage => "Editar as permissões dos papéis",
+        }
+    }
+
+    // Permissões exercidas nas rotas /admin (as chaves com âmbitos admin:* exigem uma delas)
+    pub fn is_admin_area(&self) -> bool {
+        !matches!(
+            self,
+            Permission::TicketCreate | Permission::TicketReadAll | Permission::TicketInternal | Permission::TicketTransitionAny
+        )
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PERMISSIONS
+            .iter()
+            .find(|permission| permission.as_str() == s)
+            .copied()
+            .ok_or_else(|| format!("Permissão inválida: {}", s))
+    }
+}
+
+// Permissões do papel de um utilizador
+#[derive(Debug, Clone, Default)]
+pub struct PermissionSet(HashSet<Permission>);
+
+impl PermissionSet {
+    pub fn contains(&self, permission: Permission) -> bool {
+        self.0.contains(&permission)
+    }
+
+    // Indica se este conjunto tem todas as permissões de outro (ex.: para atribuir um papel)
+    pub fn covers(&self, other: &PermissionSet) -> bool {
+        other.0.is_subset(&self.0)
+    }
+
+    pub fn has_admin_area(&self) -> bool {
+        self.0.iter().any(Permission::is_admin_area)
+    }
+}
+
+// Definição de um papel, como listada em GET /admin/roles
+#[derive(Debug, Serialize)]
+pub struct RolePermissions {
+    pub role: UserRole,
+    pub permissions: Vec<Permission>,
+}
+
+impl RolePermissions {
+    // Permissões de um papel. Valores que já não correspondem a uma permissão são ignorados.
+    pub async fn for_role(state: &AppState, role: &UserRole) -> Result<PermissionSet, sqlx::Error> {
+        let rows = sqlx::query("SELECT Permission FROM Role_Permissions WHERE User_Role = ?")
+            .bind(role.to_string())
+            .fetch_all(&state.pool)
+            .await?;
+
+        let mut permissions = HashSet::new();
+        for row in rows {
+            let value: String = row.try_get("Permission")?;
+            if let Ok(permission) = Permission::from_str(&value) {
+                permissions.insert(permission);
+            }
+        }
+        Ok(PermissionSet(permissions))
+    }
+
+    pub async fn get_all(state: &AppState) -> Result<Vec<RolePermissions>, sqlx::Error> {
+        let mut roles = Vec::new();
+        for role in [UserRole::Cliente, UserRole::Tecnico, UserRole::Administrador] {
+            let set = Self::for_role(state, &role).await?;
+            let permissions = PERMISSIONS.iter().copied().filter(|permission| set.contains(*permission)).collect();
+            roles.push(RolePermissions { role, permissions });
+        }
+        Ok(roles)
+    }
+
+    // Substitui as permissões de um papel; vale no pedido seguinte dos utilizadores com esse papel
+    pub async fn replace(state: &AppState, role: &UserRole, permissions: &[Permission]) -> Result<(), sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        sqlx::query("DELETE FROM Role_Permissions WHERE User_Role = ?")
+            .bind(role.to_string())
+            .execute(&mut *tx)
+            .await?;
+        for permission in permissions {
+            sqlx::query("INSERT INTO Role_Permissions (User_Role, Permission) VALUES (?, ?)")
+                .bind(role.to_string())
+                .bind(permission.as_str())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(permissions: &[Permission]) -> PermissionSet {
+        PermissionSet(permissions.iter().copied().collect())
+    }
+
+    #[test]
+    fn parses_every_permission_by_its_name() {
+        for permission in PERMISSIONS {
+            assert_eq!(Permission::from_str(permission.as_str()), Ok(permission));
+            let json = serde_json::to_string(&permission).unwrap();
+            assert_eq!(serde_json::from_str::<Permission>(&json).unwrap(), permission);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_permission_names() {
+        assert_eq!(Permission::from_str("ticket.delete"), Err("Permissão inválida: ticket.delete".to_string()));
+        assert!(Permission::from_str("").is_err());
+        assert!(Permission::from_str("Ticket.Create").is_err());
+        assert!(Permission::from_str(" ticket.create").is_err());
+    }
+
+    #[test]
+    fn covers_requires_every_permission_of_the_other_set() {
+        let admin = set(&PERMISSIONS);
+        let tecnico = set(&[Permission::TicketCreate, Permission::TicketReadAll, Permission::TicketInternal]);
+        let cliente = set(&[Permission::TicketCreate]);
+
+        assert!(admin.covers(&tecnico));
+        assert!(tecnico.covers(&cliente));
+        assert!(tecnico.covers(&tecnico));
+        assert!(cliente.covers(&PermissionSet::default()));
+
+        assert!(!cliente.covers(&tecnico));
+        assert!(!tecnico.covers(&admin));
+        assert!(!PermissionSet::default().covers(&cliente));
+        // Conjuntos disjuntos não se cobrem em nenhum sentido
+        let gestor = set(&[Permission::SlaManage]);
+        assert!(!gestor.covers(&cliente) && !cliente.covers(&gestor));
+    }
+}
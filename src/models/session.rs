@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::{MySqlConnection, Row};
+use crate::models::appstate::AppState;
+use crate::services::auth_service::{generate_opaque_token, hash_token};
+
+// Validade de um refresh token; cada uso gera um novo (rotação)
+const REFRESH_TOKEN_DAYS: i64 = 7;
+
+// Sessão de login de um utilizador. Os access tokens (JWT) carregam o ID da sessão
+// e deixam de ser aceites assim que a sessão é revogada (logout, reutilização de
+// refresh token, etc.). Os refresh tokens ficam guardados apenas como hash SHA-256.
+pub struct UserSession;
+
+// Resultado da troca de um refresh token
+pub enum RefreshOutcome {
+    // Novo refresh token emitido para a mesma sessão
+    Rotated { user_id: i32, session_id: String, refresh_token: String },
+    // Token desconhecido, expirado ou de sessão revogada
+    Invalid,
+    // Token já usado: provável roubo, a sessão inteira é revogada
+    Reused,
+}
+
+impl UserSession {
+    // Abre uma sessão e emite o primeiro refresh token. Devolve (id da sessão, refresh token).
+    pub async fn create_session(state: Arc<AppState>, user_id: i32) -> Result<(String, String), sqlx::Error> {
+        let session_id = generate_opaque_token();
+        let mut tx = state.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO User_Sessions (ID_Session, ID_User, Session_Created_At) VALUES (?, ?, UTC_TIMESTAMP())"
+        )
+        .bind(&session_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        let refresh_token = Self::issue_refresh_token(&mut tx, &session_id).await?;
+        tx.commit().await?;
+        Ok((session_id, refresh_token))
+    }
+
+    async fn issue_refresh_token(conn: &mut MySqlConnection, session_id: &str) -> Result<String, sqlx::Error> {
+        let refresh_token = generate_opaque_token();
+        let expires_at = Utc::now().naive_utc() + Duration::days(REFRESH_TOKEN_DAYS);
+        sqlx::query(
+            "INSERT INTO Refresh_Tokens (ID_Session, Token_Hash, Token_Expires_At, Token_Created_At)
+             VALUES (?, ?, ?, UTC_TIMESTAMP())"
+        )
+        .bind(session_id)
+        .bind(hash_token(&refresh_token))
+        .bind(expires_at)
+        .execute(conn)
+        .await?;
+        Ok(refresh_token)
+    }
+
+    // Troca um refresh token por um novo. O token usado fica marcado e não serve mais;
+    // apresentar de novo um token já trocado revoga a sessão.
+    pub async fn rotate_refresh_token(state: Arc<AppState>, refresh_token: &str) -> Result<RefreshOutcome, sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        let row = sqlx::query(
+            "SELECT r.ID_Refresh_Token, r.ID_Session, s.ID_User, r.Token_Expires_At, r.Token_Used_At, s.Session_Revoked_At
+             FROM Refresh_Tokens r
+             JOIN User_Sessions s ON r.ID_Session = s.ID_Session
+             WHERE r.Token_Hash = ?
+             FOR UPDATE"
+        )
+        .bind(hash_token(refresh_token))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(RefreshOutcome::Invalid);
+        };
+        let token_id: i32 = row.try_get("ID_Refresh_Token")?;
+        let session_id: String = row.try_get("ID_Session")?;
+        let user_id: i32 = row.try_get("ID_User")?;
+        let expires_at: NaiveDateTime = row.try_get("Token_Expires_At")?;
+        let used_at: Option<NaiveDateTime> = row.try_get("Token_Used_At")?;
+        let revoked_at: Option<NaiveDateTime> = row.try_get("Session_Revoked_At")?;
+        if revoked_at.is_some() || expires_at <= Utc::now().naive_utc() {
+            return Ok(RefreshOutcome::Invalid);
+        }
+        if used_at.is_some() {
+            Self::revoke_session_in(&mut tx, &session_id).await?;
+            tx.commit().await?;
+            return Ok(RefreshOutcome::Reused);
+        }
+
+        sqlx::query("UPDATE Refresh_Tokens SET Token_Used_At = UTC_TIMESTAMP() WHERE ID_Refresh_Token = ?")
+            .bind(token_id)
+            .execute(&mut *tx)
+            .await?;
+        let new_token = Self::issue_refresh_token(&mut tx, &session_id).await?;
+        tx.commit().await?;
+
+        Ok(RefreshOutcome::Rotated { user_id, session_id, refresh_token: new_token })
+    }
+
+    // Indica se a sessão existe, pertence ao utilizador e não foi revogada
+    pub async fn is_active(state: &AppState, session_id: &str, user_id: i32) -> Result<bool, sqlx::Error> {
+        let found: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM User_Sessions WHERE ID_Session = ? AND ID_User = ? AND Session_Revoked_At IS NULL"
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(&state.pool)
+        .await?;
+        Ok(found.is_some())
+    }
+
+    // Revoga uma sessão (logout)
+    pub async fn revoke_session(state: Arc<AppState>, session_id: &str) -> Result<(), sqlx::Error> {
+        let mut conn = state.pool.acquire().await?;
+        Self::revoke_session_in(&mut conn, session_id).await
+    }
+
+    // Revoga a sessão à qual pertence um refresh token (logout sem access token válido)
+    pub async fn revoke_by_refresh_token(state: Arc<AppState>, refresh_token: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE User_Sessions s JOIN Refresh_Tokens r ON r.ID_Session = s.ID_Session
+             SET s.Session_Revoked_At = COALESCE(s.Session_Revoked_At, UTC_TIMESTAMP())
+             WHERE r.Token_Hash = ?"
+        )
+        .bind(hash_token(refresh_token))
+        .execute(&state.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Revoga todas as sessões de um utilizador (troca de senha, conta desativada, etc.).
+    // Recebe a conexão da transação da alteração que a motivou.
+    pub async fn revoke_all_sessions(conn: &mut MySqlConnection, user_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE User_Sessions SET Session_Revoked_At = UTC_TIMESTAMP()
+             WHERE ID_User = ? AND Session_Revoked_At IS NULL"
+        )
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn revoke_session_in(conn: &mut MySqlConnection, session_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE User_Sessions SET Session_Revoked_At = COALESCE(Session_Revoked_At, UTC_TIMESTAMP())
+             WHERE ID_Session = ?"
+        )
+        .bind(session_id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
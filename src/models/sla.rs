@@ -0,0 +1,187 @@
+use std::sync::Arc;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{mysql::MySqlRow, Row};
+use std::str::FromStr;
+use crate::models::{
+    appstate::AppState,
+    ticket::{Category, Priority, Ticket},
+};
+use crate::services::business_calendar::BusinessCalendar;
+
+// Fração do prazo restante abaixo da qual o ticket é sinalizado como "Em Risco"
+const SLA_AT_RISK_FRACTION: f64 = 0.25;
+
+// Política de SLA: metas de primeira resposta e de resolução por prioridade.
+// Uma política com categoria tem precedência sobre a política geral (categoria NULL).
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SlaPolicy {
+    pub policy_id: Option<i32>,
+    pub policy_priority: Priority,
+    pub policy_category: Option<Category>,
+    pub policy_response_minutes: i64,
+    pub policy_resolution_minutes: i64,
+}
+
+// Situação de um prazo de SLA num dado momento
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum SlaState {
+    #[serde(rename = "Sem Prazo")]
+    SemPrazo,
+    #[serde(rename = "No Prazo")]
+    NoPrazo,
+    #[serde(rename = "Em Risco")]
+    EmRisco,
+    Violado,
+    Cumprido,
+}
+
+// Situação dos dois prazos de SLA de um ticket
+#[derive(Debug, Serialize)]
+pub struct TicketSla {
+    pub response: SlaState,
+    pub resolution: SlaState,
+}
+
+impl SlaPolicy {
+    // Converte uma linha da tabela SLA_Policies numa SlaPolicy
+    fn from_row(row: &MySqlRow) -> Result<SlaPolicy, sqlx::Error> {
+        let priority_str: String = row.try_get("Policy_Priority")?;
+        let category_str: Option<String> = row.try_get("Policy_Category")?;
+
+        Ok(SlaPolicy {
+            policy_id: row.try_get("ID_Policy")?,
+            policy_priority: Priority::from_str(&priority_str).unwrap_or(Priority::Média),
+            policy_category: category_str.and_then(|c| Category::from_str(&c).ok()),
+            policy_response_minutes: row.try_get("Policy_Response_Minutes")?,
+            policy_resolution_minutes: row.try_get("Policy_Resolution_Minutes")?,
+        })
+    }
+
+    // Lista todas as políticas configuradas
+    pub async fn get_all_policies(state: Arc<AppState>) -> Result<Vec<SlaPolicy>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT ID_Policy, Policy_Priority, Policy_Category, Policy_Response_Minutes, Policy_Resolution_Minutes
+             FROM SLA_Policies ORDER BY Policy_Priority, Policy_Category"
+        )
+        .fetch_all(&state.pool)
+        .await?;
+
+        rows.iter().map(SlaPolicy::from_row).collect()
+    }
+
+    // Busca a política aplicável a uma prioridade/categoria (None se não houver)
+    pub async fn find_policy(
+        state: Arc<AppState>,
+        priority: Priority,
+        category: Category,
+    ) -> Result<Option<SlaPolicy>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT ID_Policy, Policy_Priority, Policy_Category, Policy_Response_Minutes, Policy_Resolution_Minutes
+             FROM SLA_Policies
+             WHERE Policy_Priority = ? AND (Policy_Category = ? OR Policy_Category IS NULL)
+             ORDER BY Policy_Category IS NULL
+             LIMIT 1"
+        )
+        .bind(priority.as_db_str())
+        .bind(category.to_string())
+        .fetch_optional(&state.pool)
+        .await?;
+
+        row.as_ref().map(SlaPolicy::from_row).transpose()
+    }
+
+    // Cria ou atualiza a política de uma prioridade/categoria.
+    // Os prazos dos tickets existentes só são recalculados quando a prioridade muda.
+    pub async fn save_policy_in_db(policy: &SlaPolicy, state: Arc<AppState>) -> Result<(), sqlx::Error> {
+        let category = policy.policy_category.map(|c| c.to_string());
+        let updated = sqlx::query(
+            "UPDATE SLA_Policies SET Policy_Response_Minutes = ?, Policy_Resolution_Minutes = ?
+             WHERE Policy_Priority = ? AND Policy_Category <=> ?"
+        )
+        .bind(policy.policy_response_minutes)
+        .bind(policy.policy_resolution_minutes)
+        .bind(policy.policy_priority.as_db_str())
+        .bind(&category)
+        .execute(&state.pool)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            sqlx::query(
+                "INSERT INTO SLA_Policies (Policy_Priority, Policy_Category, Policy_Response_Minutes, Policy_Resolution_Minutes)
+                 VALUES (?, ?, ?, ?)"
+            )
+            .bind(policy.policy_priority.as_db_str())
+            .bind(&category)
+            .bind(policy.policy_response_minutes)
+            .bind(policy.policy_resolution_minutes)
+            .execute(&state.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    // Calcula os prazos (primeira resposta, resolução) a partir da abertura do ticket.
+    // As metas contam apenas minutos de expediente do calendário.
+    pub fn deadlines_from(
+        &self,
+        opened_at: NaiveDateTime,
+        calendar: &BusinessCalendar,
+    ) -> (NaiveDateTime, NaiveDateTime) {
+        (
+            calendar.add_business_minutes(opened_at, self.policy_response_minutes),
+            calendar.add_business_minutes(opened_at, self.policy_resolution_minutes),
+        )
+    }
+}
+
+// Avalia um prazo: cumprido/violado se a etapa já terminou, senão compara com `now`.
+// O risco é medido em minutos de expediente restantes face ao total do prazo.
+pub fn evaluate_deadline(
+    start: NaiveDateTime,
+    due: Option<NaiveDateTime>,
+    done_at: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+    calendar: &BusinessCalendar,
+) -> SlaState {
+    let Some(due) = due else {
+        return SlaState::SemPrazo;
+    };
+
+    if let Some(done_at) = done_at {
+        return if done_at <= due { SlaState::Cumprido } else { SlaState::Violado };
+    }
+    if now > due {
+        return SlaState::Violado;
+    }
+
+    let window = calendar.business_minutes_between(start, due).max(1) as f64;
+    let remaining = calendar.business_minutes_between(now, due) as f64;
+    if remaining / window < SLA_AT_RISK_FRACTION {
+        SlaState::EmRisco
+    } else {
+        SlaState::NoPrazo
+    }
+}
+
+impl TicketSla {
+    // Situação dos prazos de SLA de um ticket no momento `now`
+    pub fn evaluate(ticket: &Ticket, now: NaiveDateTime, calendar: &BusinessCalendar) -> TicketSla {
+        TicketSla {
+            response: evaluate_deadline(
+                ticket.ticket_created_at,
+                ticket.ticket_response_due,
+                ticket.ticket_first_response_at,
+                now,
+                calendar,
+            ),
+            resolution: evaluate_deadline(
+                ticket.ticket_created_at,
+                ticket.ticket_resolution_due,
+                ticket.ticket_resolved_at,
+                now,
+                calendar,
+            ),
+        }
+    }
+}
@@ -1,26 +1,96 @@
 
 use std::sync::Arc;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
+use sqlx::{mysql::MySqlRow, MySql, QueryBuilder, Row};
 use std::fmt;
-use crate::models::appstate::AppState;
+use std::str::FromStr;
+use crate::models::{
+    appstate::AppState,
+    attachment::{Attachment, AttachmentOwner, StoreError, UploadedFile},
+    sla::SlaPolicy,
+    ticket_history::{HistoryAction, HistoryRecord, TicketHistoryEntry},
+};
 
-// Enum para o Status do Ticket
-#[derive(Debug, Deserialize, Serialize, sqlx::Type, Clone, Copy)]
-#[sqlx(type_name = "StatusTicket", rename_all = "PascalCase")]
-pub enum StatusTicket { Aberto, Fechado }
+// Enum para o Status do Ticket (ciclo de vida completo)
+#[derive(Debug, Deserialize, Serialize, sqlx::Type, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "StatusTicket")]
+pub enum StatusTicket {
+    Novo,
+    #[serde(rename = "Em Atendimento")]
+    #[sqlx(rename = "Em Atendimento")]
+    EmAtendimento,
+    #[serde(rename = "Aguardando Cliente")]
+    #[sqlx(rename = "Aguardando Cliente")]
+    AguardandoCliente,
+    Resolvido,
+    Fechado,
+    Reaberto,
+}
 
 impl fmt::Display for StatusTicket {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            StatusTicket::Aberto  => write!(f, "Aberto"),
-            StatusTicket::Fechado => write!(f, "Fechado"),
+            StatusTicket::Novo              => write!(f, "Novo"),
+            StatusTicket::EmAtendimento     => write!(f, "Em Atendimento"),
+            StatusTicket::AguardandoCliente => write!(f, "Aguardando Cliente"),
+            StatusTicket::Resolvido         => write!(f, "Resolvido"),
+            StatusTicket::Fechado           => write!(f, "Fechado"),
+            StatusTicket::Reaberto          => write!(f, "Reaberto"),
+        }
+    }
+}
+
+impl FromStr for StatusTicket {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            // "Aberto" é o valor legado, anterior ao ciclo de vida completo
+            "Novo" | "Aberto"    => Ok(StatusTicket::Novo),
+            "Em Atendimento"     => Ok(StatusTicket::EmAtendimento),
+            "Aguardando Cliente" => Ok(StatusTicket::AguardandoCliente),
+            "Resolvido"          => Ok(StatusTicket::Resolvido),
+            "Fechado"            => Ok(StatusTicket::Fechado),
+            "Reaberto"           => Ok(StatusTicket::Reaberto),
+            _ => Err(format!("Valor inválido para StatusTicket: {}", s)),
         }
     }
 }
 
+impl StatusTicket {
+    // Estados para os quais o ticket pode avançar a partir do estado atual
+    pub fn allowed_transitions(&self) -> &'static [StatusTicket] {
+        use StatusTicket::*;
+        match self {
+            Novo              => &[EmAtendimento, AguardandoCliente, Resolvido, Fechado],
+            EmAtendimento     => &[AguardandoCliente, Resolvido, Fechado],
+            AguardandoCliente => &[EmAtendimento, Resolvido, Fechado],
+            Resolvido         => &[Fechado, Reaberto],
+            Fechado           => &[Reaberto],
+            Reaberto          => &[EmAtendimento, AguardandoCliente, Resolvido, Fechado],
+        }
+    }
+
+    pub fn can_transition_to(&self, next: StatusTicket) -> bool {
+        self.allowed_transitions().contains(&next)
+    }
+
+    // Estados que contam como resposta da equipa para o SLA
+    pub fn counts_as_response(&self) -> bool {
+        matches!(
+            self,
+            StatusTicket::EmAtendimento | StatusTicket::AguardandoCliente | StatusTicket::Resolvido
+        )
+    }
+
+    // Transições que o próprio cliente pode fazer nos seus tickets
+    pub fn is_client_transition(&self) -> bool {
+        matches!(self, StatusTicket::Fechado | StatusTicket::Reaberto)
+    }
+}
+
 // Enum para a Prioridade do Ticket
-#[derive(Debug, Deserialize, Serialize, sqlx::Type, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, sqlx::Type, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "Priority", rename_all = "lowercase")] // O banco espera 'baixa', 'média', 'alta'
 pub enum Priority { Baixa, Média, Alta }
 
@@ -35,6 +105,29 @@ impl fmt::Display for Priority {
     }
 }
 
+impl FromStr for Priority {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "baixa" => Ok(Priority::Baixa),
+            "média" | "media" => Ok(Priority::Média), // Aceita "media" sem acento também
+            "alta" => Ok(Priority::Alta),
+            _ => Err(format!("Valor inválido para Priority: {}", s)),
+        }
+    }
+}
+
+impl Priority {
+    // Valor armazenado no banco ('baixa', 'média', 'alta')
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            Priority::Baixa => "baixa",
+            Priority::Média => "média",
+            Priority::Alta  => "alta",
+        }
+    }
+}
+
 // Enum para a Categoria do Ticket
 #[derive(Debug, Deserialize, Serialize, sqlx::Type, Clone, Copy)]
 #[sqlx(type_name = "Category", rename_all = "PascalCase")]
@@ -51,6 +144,31 @@ impl fmt::Display for Category {
     }
 }
 
+impl FromStr for Category {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Software" => Ok(Category::Software),
+            "Hardware" => Ok(Category::Hardware),
+            "Redes"    => Ok(Category::Redes),
+            "Acesso"   => Ok(Category::Acesso),
+            _ => Err(format!("Valor inválido para Category: {}", s)),
+        }
+    }
+}
+
+// SELECT base usado por todas as consultas que devolvem Ticket
+const SELECT_TICKETS: &str = "SELECT
+        t.ID_Ticket, t.Ticket_Title, t.Ticket_Status, t.Ticket_Priority,
+        t.Ticket_category, t.Ticket_Description, t.ID_User_Requesting,
+        u.User_Name AS client_name_from_db,
+        t.ID_User_Assigned, a.User_Name AS assignee_name_from_db,
+        t.Ticket_Created_At, t.Ticket_Response_Due, t.Ticket_Resolution_Due,
+        t.Ticket_First_Response_At, t.Ticket_Resolved_At
+    FROM Tickets t
+    JOIN Users u ON t.ID_User_Requesting = u.ID_User
+    LEFT JOIN Users a ON t.ID_User_Assigned = a.ID_User";
+
 // Estrutura para representar um Ticket
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Ticket {
@@ -62,6 +180,14 @@ pub struct Ticket {
     pub ticket_client_id: i32,
     pub ticket_category: Category,
     pub ticket_client_name: String,
+    pub ticket_assignee_id: Option<i32>, // Técnico responsável (None = não atribuído)
+    pub ticket_assignee_name: Option<String>,
+    pub ticket_created_at: NaiveDateTime, // UTC
+    // Prazos de SLA (None quando não há política para a prioridade/categoria)
+    pub ticket_response_due: Option<NaiveDateTime>,
+    pub ticket_resolution_due: Option<NaiveDateTime>,
+    pub ticket_first_response_at: Option<NaiveDateTime>,
+    pub ticket_resolved_at: Option<NaiveDateTime>,
 }
 
 // Estrutura para criar um novo Ticket
@@ -74,50 +200,68 @@ pub struct NewTicket {
 }
 
 impl Ticket {
-    // Construtor para uma nova instância de Ticket (principalmente para uso interno ou testes)
-    pub fn new_ticket(
-        title: String,
-        description: String,
-        client_id: i32,
-        category: Category,
-        client_name: String,
-    ) -> Self {
-        Self {
-            ticket_id: None,
-            ticket_status: StatusTicket::Aberto,
-            ticket_priority: Priority::Média, // Prioridade padrão ao criar localmente
-            ticket_description: description,
-            ticket_client_id: client_id,
-            ticket_title: title,
-            ticket_category: category,
-            ticket_client_name: client_name,
-        }
-    }
-
-    // Salva um novo ticket no banco de dados
+    // Salva um novo ticket no banco de dados (e a abertura no histórico)
+    // O ticket, o seu histórico e os anexos são gravados juntos: se um anexo falhar, nada fica.
     pub async fn save_new_ticket_in_db(
         new_ticket_payload: &NewTicket,
         state: Arc<AppState>,
-    ) -> Result<Ticket, sqlx::Error> {
+        files: &[UploadedFile],
+    ) -> Result<(Ticket, Vec<Attachment>), StoreError> {
         let query_sql = "INSERT INTO Tickets (
                 Ticket_Title, Ticket_Description, ID_User_Requesting,
-                Ticket_category, Ticket_Status, Ticket_Priority
-            ) VALUES (?, ?, ?, ?, ?, ?)";
+                Ticket_category, Ticket_Status, Ticket_Priority,
+                Ticket_Created_At, Ticket_Response_Due, Ticket_Resolution_Due
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)";
 
-        let default_status = StatusTicket::Aberto;
+        let default_status = StatusTicket::Novo;
         let default_priority = Priority::Média; // Prioridade padrão ao salvar no banco
+        let created_at = Utc::now().naive_utc();
+
+        // Prazos de SLA conforme a política da prioridade/categoria, se existir
+        let (response_due, resolution_due) =
+            match SlaPolicy::find_policy(state.clone(), default_priority, new_ticket_payload.ticket_category).await? {
+                Some(policy) => {
+                    let (response_due, resolution_due) = policy.deadlines_from(created_at, &state.calendar);
+                    (Some(response_due), Some(resolution_due))
+                }
+                None => (None, None),
+            };
 
+        let mut tx = state.pool.begin().await?;
         let result = sqlx::query(query_sql)
             .bind(&new_ticket_payload.ticket_title)
             .bind(&new_ticket_payload.ticket_description)
             .bind(new_ticket_payload.ticket_client_id)
             .bind(new_ticket_payload.ticket_category.to_string()) // "Software", "Hardware", etc.
-            .bind(default_status.to_string())                     // "Aberto"
-            .bind(default_priority.to_string().to_lowercase())    // Salva como "baixa", "média", "alta"
-            .execute(&state.pool)
+            .bind(default_status.to_string())                     // "Novo"
+            .bind(default_priority.as_db_str())                // Salva como "baixa", "média", "alta"
+            .bind(created_at)
+            .bind(response_due)
+            .bind(resolution_due)
+            .execute(&mut *tx)
             .await?;
 
         let last_inserted_id = result.last_insert_id();
+        TicketHistoryEntry::record(&mut tx, HistoryRecord {
+            ticket_id: last_inserted_id as i32,
+            actor_id: Some(new_ticket_payload.ticket_client_id),
+            action: HistoryAction::Criado,
+            old_value: None,
+            new_value: Some(&new_ticket_payload.ticket_title),
+            is_internal: false,
+        })
+        .await?;
+        let owner = AttachmentOwner {
+            ticket_id: last_inserted_id as i32,
+            comment_id: None,
+            uploader_id: new_ticket_payload.ticket_client_id,
+            is_internal: false,
+        };
+        let attachments = Attachment::store_all(&mut tx, &state, owner, files).await?;
+        if let Err(e) = tx.commit().await {
+            Attachment::discard_stored(&state, &attachments).await;
+            return Err(e.into());
+        }
 
         let client_name_from_db: String = sqlx::query_scalar(
             "SELECT User_Name FROM Users WHERE ID_User = ?"
@@ -130,7 +274,7 @@ impl Ticket {
             "Cliente Desconhecido".to_string() // Fallback
         });
 
-        Ok(Ticket {
+        let ticket = Ticket {
             ticket_id: Some(last_inserted_id as i32),
             ticket_title: new_ticket_payload.ticket_title.clone(),
             ticket_description: new_ticket_payload.ticket_description.clone(),
@@ -139,133 +283,750 @@ impl Ticket {
             ticket_status: default_status,
             ticket_priority: default_priority, // Retorna o enum; a exibição usará Display
             ticket_client_name: client_name_from_db,
+            ticket_assignee_id: None,
+            ticket_assignee_name: None,
+            ticket_created_at: created_at,
+            ticket_response_due: response_due,
+            ticket_resolution_due: resolution_due,
+            ticket_first_response_at: None,
+            ticket_resolved_at: None,
+        };
+        Ok((ticket, attachments))
+    }
+
+    // Converte uma linha do SELECT_TICKETS num Ticket
+    fn from_row(row: &MySqlRow) -> Result<Ticket, sqlx::Error> {
+        let category_str: String = row.try_get("Ticket_category")?;
+        let resolved_category = Category::from_str(&category_str)
+            .unwrap_or(Category::Software); // Fallback
+
+        let status_str: String = row.try_get("Ticket_Status")?;
+        let resolved_status = StatusTicket::from_str(&status_str)
+            .unwrap_or(StatusTicket::Novo); // Fallback
+
+        let priority_str: String = row.try_get("Ticket_Priority")?; // Vem como 'baixa', 'média', 'alta'
+        let resolved_priority = Priority::from_str(&priority_str)
+            .unwrap_or(Priority::Média); // Fallback
+
+        Ok(Ticket {
+            ticket_id: row.try_get("ID_Ticket")?,
+            ticket_title: row.try_get("Ticket_Title")?,
+            ticket_status: resolved_status,
+            ticket_priority: resolved_priority,
+            ticket_category: resolved_category,
+            ticket_description: row.try_get("Ticket_Description")?,
+            ticket_client_id: row.try_get("ID_User_Requesting")?,
+            ticket_client_name: row.try_get("client_name_from_db")?,
+            ticket_assignee_id: row.try_get("ID_User_Assigned")?,
+            ticket_assignee_name: row.try_get("assignee_name_from_db")?,
+            ticket_created_at: row.try_get("Ticket_Created_At")?,
+            ticket_response_due: row.try_get("Ticket_Response_Due")?,
+            ticket_resolution_due: row.try_get("Ticket_Resolution_Due")?,
+            ticket_first_response_at: row.try_get("Ticket_First_Response_At")?,
+            ticket_resolved_at: row.try_get("Ticket_Resolved_At")?,
         })
     }
 
-    // Busca os tickets abertos de um utilizador específico
+    // Busca os tickets ainda não fechados de um utilizador específico
     pub async fn get_open_tickets(
         state: Arc<AppState>,
         current_user_id: i32,
     ) -> Result<Vec<Ticket>, sqlx::Error> {
-        let query_sql = "SELECT
-                t.ID_Ticket, t.Ticket_Title, t.Ticket_Status, t.Ticket_Priority,
-                t.Ticket_category, t.Ticket_Description, t.ID_User_Requesting,
-                u.User_Name AS client_name_from_db
-            FROM Tickets t
-            JOIN Users u ON t.ID_User_Requesting = u.ID_User
-            WHERE t.Ticket_Status = 'Aberto' AND t.ID_User_Requesting = ?";
-
-        let rows = sqlx::query(query_sql)
+        let query_sql = format!(
+            "{} WHERE t.Ticket_Status <> 'Fechado' AND t.ID_User_Requesting = ?",
+            SELECT_TICKETS
+        );
+
+        let rows = sqlx::query(&query_sql)
             .bind(current_user_id)
             .fetch_all(&state.pool)
             .await?;
 
-        rows.into_iter()
-            .map(|row| {
-                let category_str: String = row.try_get("Ticket_category")?;
-                let resolved_category = match category_str.as_str() {
-                    "Software" => Category::Software, "Hardware" => Category::Hardware,
-                    "Redes" => Category::Redes, "Acesso" => Category::Acesso,
-                    _ => Category::Software // Fallback
-                };
-
-                let status_str: String = row.try_get("Ticket_Status")?;
-                let resolved_status = match status_str.as_str() {
-                    "Aberto" => StatusTicket::Aberto, "Fechado" => StatusTicket::Fechado,
-                    _ => StatusTicket::Aberto // Fallback
-                };
+        rows.iter().map(Ticket::from_row).collect() // Coleta em Result<Vec<Ticket>, sqlx::Error>
+    }
 
-                let priority_str: String = row.try_get("Ticket_Priority")?; // Vem como 'baixa', 'média', 'alta'
-                let resolved_priority = match priority_str.to_lowercase().as_str() {
-                    "baixa" => Priority::Baixa, 
-                    "média" | "media" => Priority::Média, // Aceita "media" sem acento também
-                    "alta" => Priority::Alta,
-                    _ => Priority::Média // Fallback
-                };
+    // Busca os tickets ainda não fechados atribuídos a um técnico
+    pub async fn get_assigned_tickets(
+        state: Arc<AppState>,
+        assignee_id: i32,
+    ) -> Result<Vec<Ticket>, sqlx::Error> {
+        let query_sql = format!(
+            "{} WHERE t.Ticket_Status <> 'Fechado' AND t.ID_User_Assigned = ?",
+            SELECT_TICKETS
+        );
 
-                Ok(Ticket {
-                    ticket_id: row.try_get("ID_Ticket")?, // ID é importante, não usar _for_log aqui
-                    ticket_title: row.try_get("Ticket_Title")?,
-                    ticket_status: resolved_status,
-                    ticket_priority: resolved_priority,
-                    ticket_category: resolved_category,
-                    ticket_description: row.try_get("Ticket_Description")?,
-                    ticket_client_id: row.try_get("ID_User_Requesting")?,
-                    ticket_client_name: row.try_get("client_name_from_db")?,
-                })
-            })
-            .collect() // Coleta em Result<Vec<Ticket>, sqlx::Error>
-    }
-
-    // Busca todos os tickets (para admin)
-    pub async fn get_all_tickets(state: Arc<AppState>) -> Result<Vec<Ticket>, sqlx::Error> {
-        let query_sql = "SELECT
-                t.ID_Ticket, t.Ticket_Title, t.Ticket_Status, t.Ticket_Priority,
-                t.Ticket_category, t.Ticket_Description, t.ID_User_Requesting,
-                u.User_Name AS client_name_from_db
-            FROM Tickets t
-            JOIN Users u ON t.ID_User_Requesting = u.ID_User";
-        
-        let rows = sqlx::query(query_sql)
+        let rows = sqlx::query(&query_sql)
+            .bind(assignee_id)
             .fetch_all(&state.pool)
             .await?;
 
-        rows.into_iter()
-            .map(|row| {
-                let category_str: String = row.try_get("Ticket_category")?;
-                let resolved_category = match category_str.as_str() {
-                    "Software" => Category::Software, "Hardware" => Category::Hardware,
-                    "Redes" => Category::Redes, "Acesso" => Category::Acesso,
-                    _ => Category::Software // Fallback
-                };
+        rows.iter().map(Ticket::from_row).collect()
+    }
 
-                let status_str: String = row.try_get("Ticket_Status")?;
-                let resolved_status = match status_str.as_str() {
-                    "Aberto" => StatusTicket::Aberto, "Fechado" => StatusTicket::Fechado,
-                    _ => StatusTicket::Aberto // Fallback
-                };
+    // Busca um ticket pelo ID (None se não existir)
+    pub async fn get_ticket_by_id(
+        state: Arc<AppState>,
+        ticket_id: i32,
+    ) -> Result<Option<Ticket>, sqlx::Error> {
+        let query_sql = format!("{} WHERE t.ID_Ticket = ?", SELECT_TICKETS);
 
-                let priority_str: String = row.try_get("Ticket_Priority")?;
-                let resolved_priority = match priority_str.to_lowercase().as_str() {
-                    "baixa" => Priority::Baixa, 
-                    "média" | "media" => Priority::Média,
-                    "alta" => Priority::Alta,
-                    _ => Priority::Média // Fallback
-                };
-                
-                Ok(Ticket {
-                    ticket_id: row.try_get("ID_Ticket")?,
-                    ticket_title: row.try_get("Ticket_Title")?,
-                    ticket_status: resolved_status,
-                    ticket_priority: resolved_priority,
-                    ticket_category: resolved_category,
-                    ticket_description: row.try_get("Ticket_Description")?,
-                    ticket_client_id: row.try_get("ID_User_Requesting")?,
-                    ticket_client_name: row.try_get("client_name_from_db")?,
-                })
+        let row = sqlx::query(&query_sql)
+            .bind(ticket_id)
+            .fetch_optional(&state.pool)
+            .await?;
+
+        row.as_ref().map(Ticket::from_row).transpose()
+    }
+
+    // Move o ticket para um novo status. A condição sobre o status atual evita
+    // que duas transições concorrentes partam do mesmo estado.
+    // Também regista os marcos usados pelo SLA (primeira resposta e resolução)
+    // e a transição no histórico, feita por actor_id.
+    pub async fn update_ticket_status(
+        state: Arc<AppState>,
+        ticket_id: i32,
+        current_status: StatusTicket,
+        new_status: StatusTicket,
+        actor_id: i32,
+    ) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
+        let mut set_clause = String::from("Ticket_Status = ?");
+        if new_status.counts_as_response() {
+            set_clause.push_str(", Ticket_First_Response_At = COALESCE(Ticket_First_Response_At, UTC_TIMESTAMP())");
+        }
+        match new_status {
+            StatusTicket::Resolvido | StatusTicket::Fechado => {
+                set_clause.push_str(", Ticket_Resolved_At = COALESCE(Ticket_Resolved_At, UTC_TIMESTAMP())");
+            }
+            StatusTicket::Reaberto => set_clause.push_str(", Ticket_Resolved_At = NULL"),
+            _ => {}
+        }
+        let query_sql = format!(
+            "UPDATE Tickets SET {} WHERE ID_Ticket = ? AND Ticket_Status = ?",
+            set_clause
+        );
+
+        let mut tx = state.pool.begin().await?;
+        let result = sqlx::query(&query_sql)
+            .bind(new_status.to_string())
+            .bind(ticket_id)
+            .bind(current_status.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() > 0 {
+            TicketHistoryEntry::record(&mut tx, HistoryRecord {
+                ticket_id,
+                actor_id: Some(actor_id),
+                action: HistoryAction::Status,
+                old_value: Some(&current_status.to_string()),
+                new_value: Some(&new_status.to_string()),
+                is_internal: false,
             })
-            .collect()
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    // Regista a primeira resposta da equipa (só tem efeito na primeira vez)
+    pub async fn mark_first_response(
+        state: Arc<AppState>,
+        ticket_id: i32,
+    ) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
+        sqlx::query(
+            "UPDATE Tickets SET Ticket_First_Response_At = UTC_TIMESTAMP()
+             WHERE ID_Ticket = ? AND Ticket_First_Response_At IS NULL"
+        )
+        .bind(ticket_id)
+        .execute(&state.pool)
+        .await
     }
 
-    // Atualiza a prioridade de um ticket específico
+    // Atualiza a prioridade de um ticket específico e recalcula os prazos de SLA
+    // a partir da abertura do ticket, conforme a política da nova prioridade.
+    // Mudanças efetivas ficam no histórico em nome de actor_id.
     pub async fn update_ticket_priority(
         state: Arc<AppState>,
         ticket_id: i32,
         new_priority: Priority,
+        actor_id: i32,
     ) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
-        let query_sql = "UPDATE Tickets SET Ticket_Priority = ? WHERE ID_Ticket = ?";
-        
-        // Converte o enum para a string que o DB espera (lowercase, conforme rename_all)
-        let priority_str_for_db = match new_priority {
-            Priority::Baixa => "baixa",
-            Priority::Média => "média", // Se o DB armazena com acento
-            Priority::Alta  => "alta",
+        let Some(ticket) = Ticket::get_ticket_by_id(state.clone(), ticket_id).await? else {
+            return Ok(sqlx::mysql::MySqlQueryResult::default()); // Nenhuma linha afetada
         };
 
-        sqlx::query(query_sql)
-            .bind(priority_str_for_db)
+        let (response_due, resolution_due) =
+            match SlaPolicy::find_policy(state.clone(), new_priority, ticket.ticket_category).await? {
+                Some(policy) => {
+                    let (response_due, resolution_due) = policy.deadlines_from(ticket.ticket_created_at, &state.calendar);
+                    (Some(response_due), Some(resolution_due))
+                }
+                None => (None, None),
+            };
+
+        let query_sql = "UPDATE Tickets SET Ticket_Priority = ?, Ticket_Response_Due = ?, Ticket_Resolution_Due = ?
+            WHERE ID_Ticket = ?";
+
+        let mut tx = state.pool.begin().await?;
+        let result = sqlx::query(query_sql)
+            .bind(new_priority.as_db_str()) // Converte o enum para a string que o DB espera
+            .bind(response_due)
+            .bind(resolution_due)
             .bind(ticket_id)
-            .execute(&state.pool)
-            .await
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() > 0 && ticket.ticket_priority != new_priority {
+            TicketHistoryEntry::record(&mut tx, HistoryRecord {
+                ticket_id,
+                actor_id: Some(actor_id),
+                action: HistoryAction::Prioridade,
+                old_value: Some(&ticket.ticket_priority.to_string()),
+                new_value: Some(&new_priority.to_string()),
+                is_internal: false,
+            })
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    // Atribui o ticket a um membro da equipa (None remove a atribuição).
+    // O histórico guarda os nomes do responsável anterior e do novo.
+    pub async fn assign_ticket(
+        state: Arc<AppState>,
+        ticket_id: i32,
+        assignee_id: Option<i32>,
+        actor_id: i32,
+    ) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        let previous: Option<(Option<i32>, Option<String>)> = sqlx::query_as(
+            "SELECT t.ID_User_Assigned, a.User_Name FROM Tickets t
+             LEFT JOIN Users a ON t.ID_User_Assigned = a.ID_User
+             WHERE t.ID_Ticket = ? FOR UPDATE"
+        )
+        .bind(ticket_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let result = sqlx::query("UPDATE Tickets SET ID_User_Assigned = ? WHERE ID_Ticket = ?")
+            .bind(assignee_id)
+            .bind(ticket_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some((previous_id, previous_name)) = previous {
+            if previous_id != assignee_id {
+                let new_name: Option<String> = match assignee_id {
+                    Some(user_id) => sqlx::query_scalar("SELECT User_Name FROM Users WHERE ID_User = ?")
+                        .bind(user_id)
+                        .fetch_optional(&mut *tx)
+                        .await?,
+                    None => None,
+                };
+                TicketHistoryEntry::record(&mut tx, HistoryRecord {
+                    ticket_id,
+                    actor_id: Some(actor_id),
+                    action: HistoryAction::Atribuicao,
+                    old_value: previous_name.as_deref(),
+                    new_value: new_name.as_deref(),
+                    is_internal: false,
+                })
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(result)
+    }
+}
+
+// Tamanho de página padrão e máximo das listagens de tickets
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 200;
+
+// Campo de ordenação das listagens
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketSortField {
+    #[default]
+    CreatedAt,
+    Id,
+    Title,
+    Priority,
+    Status,
+}
+
+impl TicketSortField {
+    // Expressão SQL usada no ORDER BY e na comparação do cursor.
+    // Prioridade e status ordenam pela sequência natural, não alfabeticamente.
+    fn sql_expr(&self) -> &'static str {
+        match self {
+            TicketSortField::CreatedAt => "t.Ticket_Created_At",
+            TicketSortField::Id => "t.ID_Ticket",
+            TicketSortField::Title => "t.Ticket_Title",
+            TicketSortField::Priority => "FIELD(t.Ticket_Priority, 'baixa', 'média', 'alta')",
+            TicketSortField::Status => {
+                "FIELD(t.Ticket_Status, 'Novo', 'Em Atendimento', 'Aguardando Cliente', 'Resolvido', 'Reaberto', 'Fechado')"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Parâmetros (query string) de filtro, ordenação e paginação das listagens de tickets.
+// status, priority e category aceitam vários valores separados por vírgulas.
+#[derive(Debug, Deserialize)]
+pub struct TicketListQuery {
+    pub status: Option<String>,
+    pub priority: Option<String>,
+    pub category: Option<String>,
+    pub requester_id: Option<i32>,
+    pub assignee_id: Option<i32>,
+    pub created_from: Option<NaiveDate>, // Inclusivo (AAAA-MM-DD, UTC)
+    pub created_to: Option<NaiveDate>,   // Inclusivo (AAAA-MM-DD, UTC)
+    pub q: Option<String>,               // Texto livre: título, descrição, cliente ou número
+    pub sort: Option<TicketSortField>,
+    pub dir: Option<SortDirection>,
+    pub cursor: Option<String>,          // next_cursor devolvido pela página anterior
+    pub limit: Option<u32>,
+}
+
+impl TicketListQuery {
+    // Pedidos sem cursor nem limit mantêm a resposta antiga de GET /tickets (lista completa)
+    pub fn is_paged(&self) -> bool {
+        self.cursor.is_some() || self.limit.is_some()
+    }
+}
+
+// Conjunto de tickets que o utilizador pode listar, aplicado antes dos filtros
+#[derive(Debug, Clone, Copy)]
+pub enum TicketScope {
+    All,
+    Requester(i32), // Tickets abertos pelo utilizador
+    Assignee(i32),  // Tickets atribuídos ao técnico
+}
+
+// Página de resultados: total considera todos os filtros, mas não o cursor
+#[derive(Debug, Serialize)]
+pub struct TicketPage<T> {
+    pub tickets: Vec<T>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+// Posição do último ticket de uma página, codificada no cursor
+#[derive(Debug, Deserialize, Serialize)]
+struct TicketCursor {
+    sort: TicketSortField,
+    dir: SortDirection,
+    value: serde_json::Value,
+    id: i32,
+}
+
+impl TicketCursor {
+    // Forma opaca enviada ao cliente (JSON em hexadecimal)
+    fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+}
+
+// Valor de ordenação do cursor, já convertido para o tipo do campo
+#[derive(Debug, PartialEq)]
+enum CursorValue {
+    At(NaiveDateTime),
+    Text(String),
+    Rank(i64),
+    None, // Ordenação por ID: basta o ID do ticket
+}
+
+impl CursorValue {
+    fn push_bind(&self, builder: &mut QueryBuilder<'_, MySql>) {
+        match self {
+            CursorValue::At(at) => builder.push_bind(*at),
+            CursorValue::Text(text) => builder.push_bind(text.clone()),
+            CursorValue::Rank(rank) => builder.push_bind(*rank),
+            CursorValue::None => builder,
+        };
+    }
+}
+
+// Filtros já validados a partir de TicketListQuery
+#[derive(Debug)]
+pub struct TicketFilter {
+    statuses: Vec<StatusTicket>,
+    priorities: Vec<Priority>,
+    categories: Vec<Category>,
+    pub requester_id: Option<i32>,
+    pub assignee_id: Option<i32>,
+    created_from: Option<NaiveDate>,
+    created_to: Option<NaiveDate>,
+    text: Option<String>,
+    sort: TicketSortField,
+    dir: SortDirection,
+    after: Option<(CursorValue, i32)>,
+    limit: u32,
+}
+
+// Interpreta uma lista separada por vírgulas ("Novo,Reaberto")
+fn parse_list<T: FromStr<Err = String>>(raw: &Option<String>) -> Result<Vec<T>, String> {
+    raw.as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(T::from_str)
+        .collect()
+}
+
+// Padrão LIKE que procura o texto literal: os curingas (% e _) e o escape (\) são escapados
+fn like_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+impl TicketListQuery {
+    // Valida os parâmetros; o erro é uma mensagem para o utilizador
+    pub fn parse(&self) -> Result<TicketFilter, String> {
+        let sort = self.sort.unwrap_or_default();
+        let dir = self.dir.unwrap_or_default();
+
+        let after = match self.cursor.as_deref().filter(|c| !c.is_empty()) {
+            Some(cursor) => {
+                let cursor: TicketCursor = hex::decode(cursor)
+                    .ok()
+                    .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                    .ok_or("Cursor inválido.")?;
+                if cursor.sort != sort || cursor.dir != dir {
+                    return Err("O cursor pertence a outra ordenação.".to_string());
+                }
+                let value = match sort {
+                    TicketSortField::CreatedAt => serde_json::from_value(cursor.value).ok().map(CursorValue::At),
+                    TicketSortField::Title => cursor.value.as_str().map(|v| CursorValue::Text(v.to_string())),
+                    TicketSortField::Priority | TicketSortField::Status => cursor.value.as_i64().map(CursorValue::Rank),
+                    TicketSortField::Id => Some(CursorValue::None),
+                };
+                Some((value.ok_or("Cursor inválido.")?, cursor.id))
+            }
+            None => None,
+        };
+
+        Ok(TicketFilter {
+            statuses: parse_list(&self.status)?,
+            priorities: parse_list(&self.priority)?,
+            categories: parse_list(&self.category)?,
+            requester_id: self.requester_id,
+            assignee_id: self.assignee_id,
+            created_from: self.created_from,
+            created_to: self.created_to,
+            text: self.q.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(str::to_string),
+            sort,
+            dir,
+            after,
+            limit: self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+        })
+    }
+}
+
+impl TicketFilter {
+    // Acrescenta as condições do escopo e dos filtros (sem o cursor) ao WHERE
+    fn push_conditions(&self, builder: &mut QueryBuilder<'_, MySql>, scope: TicketScope) {
+        builder.push(" WHERE 1 = 1");
+        match scope {
+            TicketScope::All => {}
+            TicketScope::Requester(user_id) => {
+                builder.push(" AND t.ID_User_Requesting = ").push_bind(user_id);
+            }
+            TicketScope::Assignee(user_id) => {
+                builder.push(" AND t.ID_User_Assigned = ").push_bind(user_id);
+            }
+        }
+
+        // Sem filtro de status, as listagens pessoais mostram apenas os tickets não fechados
+        if !self.statuses.is_empty() {
+            builder.push(" AND t.Ticket_Status IN (");
+            let mut values = builder.separated(", ");
+            for status in &self.statuses {
+                values.push_bind(status.to_string());
+            }
+            builder.push(")");
+        } else if !matches!(scope, TicketScope::All) {
+            builder.push(" AND t.Ticket_Status <> 'Fechado'");
+        }
+        if !self.priorities.is_empty() {
+            builder.push(" AND t.Ticket_Priority IN (");
+            let mut values = builder.separated(", ");
+            for priority in &self.priorities {
+                values.push_bind(priority.as_db_str());
+            }
+            builder.push(")");
+        }
+        if !self.categories.is_empty() {
+            builder.push(" AND t.Ticket_category IN (");
+            let mut values = builder.separated(", ");
+            for category in &self.categories {
+                values.push_bind(category.to_string());
+            }
+            builder.push(")");
+        }
+
+        if let Some(requester_id) = self.requester_id {
+            builder.push(" AND t.ID_User_Requesting = ").push_bind(requester_id);
+        }
+        if let Some(assignee_id) = self.assignee_id {
+            builder.push(" AND t.ID_User_Assigned = ").push_bind(assignee_id);
+        }
+        if let Some(from) = self.created_from {
+            builder.push(" AND t.Ticket_Created_At >= ").push_bind(from.and_time(NaiveTime::MIN));
+        }
+        if let Some(to) = self.created_to {
+            let end = (to + Duration::days(1)).and_time(NaiveTime::MIN);
+            builder.push(" AND t.Ticket_Created_At < ").push_bind(end);
+        }
+
+        if let Some(text) = &self.text {
+            let pattern = like_pattern(text);
+            builder
+                .push(" AND (t.Ticket_Title LIKE ")
+                .push_bind(pattern.clone())
+                .push(" OR t.Ticket_Description LIKE ")
+                .push_bind(pattern.clone())
+                .push(" OR u.User_Name LIKE ")
+                .push_bind(pattern);
+            if let Ok(ticket_id) = text.trim_start_matches('#').parse::<i32>() {
+                builder.push(" OR t.ID_Ticket = ").push_bind(ticket_id);
+            }
+            builder.push(")");
+        }
+    }
+
+    // Condição do cursor: tickets depois de (valor, id) na ordenação escolhida
+    fn push_cursor(&self, builder: &mut QueryBuilder<'_, MySql>) {
+        let Some((value, id)) = &self.after else {
+            return;
+        };
+        let expr = self.sort.sql_expr();
+        let op = if self.dir == SortDirection::Asc { ">" } else { "<" };
+
+        if let CursorValue::None = value {
+            builder.push(format!(" AND t.ID_Ticket {} ", op)).push_bind(*id);
+            return;
+        }
+
+        builder.push(format!(" AND ({} {} ", expr, op));
+        value.push_bind(builder);
+        builder.push(format!(" OR ({} = ", expr));
+        value.push_bind(builder);
+        builder.push(format!(" AND t.ID_Ticket {} ", op)).push_bind(*id).push("))");
+    }
+
+    // Valor de ordenação de uma linha, para montar o próximo cursor
+    fn cursor_value(&self, row: &MySqlRow) -> Result<serde_json::Value, sqlx::Error> {
+        Ok(match self.sort {
+            TicketSortField::CreatedAt => {
+                serde_json::to_value(row.try_get::<NaiveDateTime, _>("sort_key")?).unwrap_or_default()
+            }
+            TicketSortField::Title => serde_json::Value::from(row.try_get::<String, _>("sort_key")?),
+            TicketSortField::Priority | TicketSortField::Status => {
+                serde_json::Value::from(row.try_get::<i64, _>("sort_key")?)
+            }
+            TicketSortField::Id => serde_json::Value::Null,
+        })
+    }
+}
+
+impl Ticket {
+    // Lista todos os tickets do escopo que passam nos filtros, sem paginação
+    pub async fn search_all_tickets(
+        state: Arc<AppState>,
+        scope: TicketScope,
+        filter: &TicketFilter,
+    ) -> Result<Vec<Ticket>, sqlx::Error> {
+        let mut builder = QueryBuilder::<MySql>::new(SELECT_TICKETS);
+        filter.push_conditions(&mut builder, scope);
+        let dir = if filter.dir == SortDirection::Asc { "ASC" } else { "DESC" };
+        builder.push(format!(" ORDER BY {} {}, t.ID_Ticket {}", filter.sort.sql_expr(), dir, dir));
+
+        let rows = builder.build().fetch_all(&state.pool).await?;
+        rows.iter().map(Ticket::from_row).collect()
+    }
+
+    // Lista uma página de tickets do escopo, com filtros, ordenação e cursor
+    pub async fn search_tickets(
+        state: Arc<AppState>,
+        scope: TicketScope,
+        filter: &TicketFilter,
+    ) -> Result<TicketPage<Ticket>, sqlx::Error> {
+        let mut count_builder = QueryBuilder::<MySql>::new("SELECT COUNT(*) AS total FROM (");
+        count_builder.push(SELECT_TICKETS);
+        filter.push_conditions(&mut count_builder, scope);
+        count_builder.push(") AS filtered");
+        let total: i64 = count_builder.build().fetch_one(&state.pool).await?.try_get("total")?;
+
+        // A expressão de ordenação vem como coluna extra para gerar o cursor
+        let expr = filter.sort.sql_expr();
+        let select = SELECT_TICKETS.replacen("SELECT", &format!("SELECT {} AS sort_key,", expr), 1);
+        let mut builder = QueryBuilder::<MySql>::new(select);
+        filter.push_conditions(&mut builder, scope);
+        filter.push_cursor(&mut builder);
+        let dir = if filter.dir == SortDirection::Asc { "ASC" } else { "DESC" };
+        builder.push(format!(" ORDER BY {} {}, t.ID_Ticket {} LIMIT ", expr, dir, dir));
+        builder.push_bind(filter.limit + 1); // Uma linha a mais indica se há próxima página
+
+        let mut rows = builder.build().fetch_all(&state.pool).await?;
+        let has_more = rows.len() > filter.limit as usize;
+        rows.truncate(filter.limit as usize);
+
+        let next_cursor = match rows.last() {
+            Some(last) if has_more => {
+                let cursor = TicketCursor {
+                    sort: filter.sort,
+                    dir: filter.dir,
+                    value: filter.cursor_value(last)?,
+                    id: last.try_get("ID_Ticket")?,
+                };
+                Some(cursor.encode())
+            }
+            _ => None,
+        };
+
+        Ok(TicketPage {
+            tickets: rows.iter().map(Ticket::from_row).collect::<Result<_, _>>()?,
+            total,
+            next_cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Query, http::Uri};
+
+    fn query(query_string: &str) -> Result<TicketListQuery, String> {
+        let uri: Uri = format!("/tickets?{}", query_string).parse().unwrap();
+        Query::<TicketListQuery>::try_from_uri(&uri).map(|Query(query)| query).map_err(|e| e.body_text())
+    }
+
+    fn parse(query_string: &str) -> Result<TicketFilter, String> {
+        query(query_string)?.parse()
+    }
+
+    // Tabela completa de transições: linha = estado atual, coluna = estado pedido
+    #[test]
+    fn status_transition_table() {
+        use StatusTicket::*;
+        let all = [Novo, EmAtendimento, AguardandoCliente, Resolvido, Fechado, Reaberto];
+        let table = [
+            //                 Novo   EmAt.  Aguard. Resolv. Fech.  Reab.
+            (Novo,              [false, true,  true,  true,  true,  false]),
+            (EmAtendimento,     [false, false, true,  true,  true,  false]),
+            (AguardandoCliente, [false, true,  false, true,  true,  false]),
+            (Resolvido,         [false, false, false, false, true,  true]),
+            (Fechado,           [false, false, false, false, false, true]),
+            (Reaberto,          [false, true,  true,  true,  true,  false]),
+        ];
+        for (from, allowed) in table {
+            for (to, expected) in all.iter().zip(allowed) {
+                assert_eq!(from.can_transition_to(*to), expected, "{} -> {}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn same_status_is_not_a_transition() {
+        use StatusTicket::*;
+        for status in [Novo, EmAtendimento, AguardandoCliente, Resolvido, Fechado, Reaberto] {
+            assert!(!status.can_transition_to(status), "{}", status);
+        }
+    }
+
+    #[test]
+    fn closed_tickets_can_only_be_reopened() {
+        assert_eq!(StatusTicket::Fechado.allowed_transitions(), [StatusTicket::Reaberto]);
+        assert!(StatusTicket::Reaberto.is_client_transition());
+        assert!(StatusTicket::Fechado.is_client_transition());
+        assert!(!StatusTicket::Resolvido.is_client_transition());
+        // O valor legado "Aberto" é lido como Novo
+        assert_eq!(StatusTicket::from_str("Aberto"), Ok(StatusTicket::Novo));
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = TicketCursor { sort: TicketSortField::Title, dir: SortDirection::Asc, value: "Impressora".into(), id: 7 };
+        let filter = parse(&format!("sort=title&dir=asc&cursor={}", cursor.encode())).unwrap();
+        assert_eq!(filter.after, Some((CursorValue::Text("Impressora".to_string()), 7)));
+
+        let at = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(9, 30, 0).unwrap();
+        let cursor = TicketCursor {
+            sort: TicketSortField::CreatedAt,
+            dir: SortDirection::Desc,
+            value: serde_json::to_value(at).unwrap(),
+            id: 42,
+        };
+        assert_eq!(parse(&format!("cursor={}", cursor.encode())).unwrap().after, Some((CursorValue::At(at), 42)));
+
+        let cursor = TicketCursor { sort: TicketSortField::Priority, dir: SortDirection::Desc, value: 3.into(), id: 5 };
+        let filter = parse(&format!("sort=priority&cursor={}", cursor.encode())).unwrap();
+        assert_eq!(filter.after, Some((CursorValue::Rank(3), 5)));
+
+        assert_eq!(parse("cursor=").unwrap().after, None);
+    }
+
+    #[test]
+    fn rejects_garbage_and_tampered_cursors() {
+        for garbage in ["zz", "abc", "7b7d", &hex::encode("não é json")] {
+            assert_eq!(parse(&format!("cursor={}", garbage)).unwrap_err(), "Cursor inválido.");
+        }
+
+        // Cursor de outra ordenação ou direção
+        let title = TicketCursor { sort: TicketSortField::Title, dir: SortDirection::Asc, value: "a".into(), id: 1 };
+        assert!(parse(&format!("sort=title&dir=desc&cursor={}", title.encode())).is_err());
+        assert!(parse(&format!("sort=id&dir=asc&cursor={}", title.encode())).is_err());
+
+        // Valor com o tipo errado para o campo
+        let tampered = TicketCursor { sort: TicketSortField::CreatedAt, dir: SortDirection::Desc, value: "ontem".into(), id: 1 };
+        assert_eq!(parse(&format!("cursor={}", tampered.encode())).unwrap_err(), "Cursor inválido.");
+        let tampered = TicketCursor { sort: TicketSortField::Priority, dir: SortDirection::Desc, value: "alta".into(), id: 1 };
+        assert!(parse(&format!("sort=priority&cursor={}", tampered.encode())).is_err());
+        let truncated = &title.encode()[..10];
+        assert!(parse(&format!("sort=title&dir=asc&cursor={}", truncated)).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_sort_fields_and_values() {
+        assert!(query("sort=Ticket_Title").is_err());
+        assert!(query("sort=title;DROP%20TABLE%20Tickets").is_err());
+        assert!(query("dir=up").is_err());
+        assert!(parse("status=Novo,Perdido").is_err());
+        assert!(parse("priority=urgente").is_err());
+
+        let filter = parse("sort=status&dir=asc&status=Novo,%20Reaberto").unwrap();
+        assert_eq!((filter.sort, filter.dir), (TicketSortField::Status, SortDirection::Asc));
+        assert_eq!(filter.statuses, [StatusTicket::Novo, StatusTicket::Reaberto]);
+    }
+
+    #[test]
+    fn limit_is_clamped() {
+        assert_eq!(parse("").unwrap().limit, DEFAULT_PAGE_SIZE);
+        assert_eq!(parse("limit=0").unwrap().limit, 1);
+        assert_eq!(parse("limit=10000").unwrap().limit, MAX_PAGE_SIZE);
+        assert!(!query("").unwrap().is_paged());
+        assert!(query("limit=10").unwrap().is_paged());
+    }
+
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("impressora"), "%impressora%");
+        assert_eq!(like_pattern("100%"), "%100\\%%");
+        assert_eq!(like_pattern("nome_do_ficheiro"), "%nome\\_do\\_ficheiro%");
+        assert_eq!(like_pattern("C:\\temp"), "%C:\\\\temp%");
+        assert_eq!(parse("q=%20%20%20").unwrap().text, None);
     }
 }
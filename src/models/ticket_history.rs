@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{mysql::MySqlRow, MySqlConnection, Row};
+use std::fmt;
+use crate::models::appstate::AppState;
+
+// Tamanho máximo do trecho de texto guardado nos valores do histórico
+const MAX_VALUE_CHARS: usize = 200;
+
+// Tipo de alteração registada no histórico de um ticket
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    Criado,
+    Status,
+    Prioridade,
+    Atribuicao,
+    Comentario,
+    NotaInterna,
+    Anexo,
+}
+
+impl fmt::Display for HistoryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryAction::Criado => write!(f, "criado"),
+            HistoryAction::Status => write!(f, "status"),
+            HistoryAction::Prioridade => write!(f, "prioridade"),
+            HistoryAction::Atribuicao => write!(f, "atribuicao"),
+            HistoryAction::Comentario => write!(f, "comentario"),
+            HistoryAction::NotaInterna => write!(f, "nota_interna"),
+            HistoryAction::Anexo => write!(f, "anexo"),
+        }
+    }
+}
+
+// Entrada do histórico (imutável: a tabela recusa UPDATE e DELETE)
+#[derive(Debug, Serialize)]
+pub struct TicketHistoryEntry {
+    pub history_id: i32,
+    pub history_ticket_id: i32,
+    pub history_actor_id: Option<i32>, // None = ação do sistema
+    pub history_actor_name: Option<String>,
+    pub history_action: String,
+    pub history_old_value: Option<String>,
+    pub history_new_value: Option<String>,
+    pub history_is_internal: bool, // Visível apenas para a equipa
+    pub history_created_at: NaiveDateTime, // UTC
+}
+
+// Nova entrada a registar, na mesma transação da alteração que a originou
+pub struct HistoryRecord<'a> {
+    pub ticket_id: i32,
+    pub actor_id: Option<i32>,
+    pub action: HistoryAction,
+    pub old_value: Option<&'a str>,
+    pub new_value: Option<&'a str>,
+    pub is_internal: bool,
+}
+
+// Corta textos longos (descrições, comentários) para o histórico
+fn excerpt(value: &str) -> String {
+    let mut text: String = value.chars().take(MAX_VALUE_CHARS).collect();
+    if value.chars().count() > MAX_VALUE_CHARS {
+        text.push('…');
+    }
+    text
+}
+
+impl TicketHistoryEntry {
+    // Converte uma linha da consulta do histórico numa TicketHistoryEntry
+    fn from_row(row: &MySqlRow) -> Result<TicketHistoryEntry, sqlx::Error> {
+        Ok(TicketHistoryEntry {
+            history_id: row.try_get("ID_History")?,
+            history_ticket_id: row.try_get("ID_Ticket")?,
+            history_actor_id: row.try_get("ID_User_Actor")?,
+            history_actor_name: row.try_get("actor_name_from_db")?,
+            history_action: row.try_get("History_Action")?,
+            history_old_value: row.try_get("History_Old_Value")?,
+            history_new_value: row.try_get("History_New_Value")?,
+            history_is_internal: row.try_get("History_Is_Internal")?,
+            history_created_at: row.try_get("History_Created_At")?,
+        })
+    }
+
+    // Regista uma entrada. Recebe a conexão da transação da alteração,
+    // para que a alteração e o seu registo sejam gravados juntos ou não sejam gravados.
+    pub async fn record(conn: &mut MySqlConnection, record: HistoryRecord<'_>) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO Ticket_History (
+                ID_Ticket, ID_User_Actor, History_Action, History_Old_Value,
+                History_New_Value, History_Is_Internal, History_Created_At
+            ) VALUES (?, ?, ?, ?, ?, ?, UTC_TIMESTAMP())"
+        )
+        .bind(record.ticket_id)
+        .bind(record.actor_id)
+        .bind(record.action.to_string())
+        .bind(record.old_value.map(excerpt))
+        .bind(record.new_value.map(excerpt))
+        .bind(record.is_internal)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    // Histórico de um ticket em ordem cronológica; sem include_internal omite
+    // as entradas ligadas a notas internas
+    pub async fn get_history_by_ticket(
+        state: Arc<AppState>,
+        ticket_id: i32,
+        include_internal: bool,
+    ) -> Result<Vec<TicketHistoryEntry>, sqlx::Error> {
+        let visibility_filter = if include_internal { "" } else { " AND h.History_Is_Internal = FALSE" };
+        let query_sql = format!(
+            "SELECT
+                h.ID_History, h.ID_Ticket, h.ID_User_Actor, u.User_Name AS actor_name_from_db,
+                h.History_Action, h.History_Old_Value, h.History_New_Value,
+                h.History_Is_Internal, h.History_Created_At
+            FROM Ticket_History h
+            LEFT JOIN Users u ON h.ID_User_Actor = u.ID_User
+            WHERE h.ID_Ticket = ?{}
+            ORDER BY h.History_Created_At, h.ID_History",
+            visibility_filter
+        );
+
+        let rows = sqlx::query(&query_sql)
+            .bind(ticket_id)
+            .fetch_all(&state.pool)
+            .await?;
+
+        rows.iter().map(TicketHistoryEntry::from_row).collect()
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use sqlx::Row;
+use crate::models::appstate::AppState;
+use crate::services::auth_service::hash_token;
+use crate::services::totp::normalize_recovery_code;
+
+// Estado da verificação em duas etapas (TOTP) de um utilizador
+pub struct TwoFactor {
+    pub secret: Option<String>, // Segredo pendente (configuração iniciada) ou ativo
+    pub enabled: bool,
+}
+
+impl TwoFactor {
+    pub async fn get_by_user(state: Arc<AppState>, user_id: i32) -> Result<TwoFactor, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT User_Totp_Secret, User_Totp_Enabled FROM Users WHERE ID_User = ?"
+        )
+        .bind(user_id)
+        .fetch_one(&state.pool)
+        .await?;
+
+        Ok(TwoFactor {
+            secret: row.try_get("User_Totp_Secret")?,
+            enabled: row.try_get("User_Totp_Enabled")?,
+        })
+    }
+
+    // Guarda o segredo de uma configuração iniciada; só passa a ser exigido depois de confirmado
+    pub async fn save_pending_secret(state: Arc<AppState>, user_id: i32, secret: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE Users SET User_Totp_Secret = ?, User_Totp_Last_Step = NULL
+             WHERE ID_User = ? AND User_Totp_Enabled = FALSE"
+        )
+        .bind(secret)
+        .bind(user_id)
+        .execute(&state.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Ativa a verificação e substitui os códigos de recuperação (guardados apenas como hash)
+    pub async fn enable(state: Arc<AppState>, user_id: i32, step: i64, recovery_codes: &[String]) -> Result<(), sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        sqlx::query("UPDATE Users SET User_Totp_Enabled = TRUE, User_Totp_Last_Step = ? WHERE ID_User = ?")
+            .bind(step)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM User_Recovery_Codes WHERE ID_User = ?")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        for code in recovery_codes {
+            sqlx::query("INSERT INTO User_Recovery_Codes (ID_User, Code_Hash) VALUES (?, ?)")
+                .bind(user_id)
+                .bind(hash_token(&normalize_recovery_code(code)))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn disable(state: Arc<AppState>, user_id: i32) -> Result<(), sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        sqlx::query(
+            "UPDATE Users SET User_Totp_Secret = NULL, User_Totp_Enabled = FALSE, User_Totp_Last_Step = NULL
+             WHERE ID_User = ?"
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM User_Recovery_Codes WHERE ID_User = ?")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Regista o passo TOTP usado. Devolve false se esse passo (ou um posterior) já tiver
+    // sido aceite, ou seja, se o código está a ser reutilizado.
+    pub async fn consume_step(state: Arc<AppState>, user_id: i32, step: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE Users SET User_Totp_Last_Step = ?
+             WHERE ID_User = ? AND (User_Totp_Last_Step IS NULL OR User_Totp_Last_Step < ?)"
+        )
+        .bind(step)
+        .bind(user_id)
+        .bind(step)
+        .execute(&state.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Usa um código de recuperação. Devolve false se não existir ou já tiver sido usado.
+    pub async fn consume_recovery_code(state: Arc<AppState>, user_id: i32, code: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE User_Recovery_Codes SET Code_Used_At = UTC_TIMESTAMP()
+             WHERE ID_User = ? AND Code_Hash = ? AND Code_Used_At IS NULL"
+        )
+        .bind(user_id)
+        .bind(hash_token(&normalize_recovery_code(code)))
+        .execute(&state.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -2,10 +2,10 @@
 
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, Row, Type}; // Removido 'query' não utilizado do import
+use sqlx::{mysql::MySqlRow, FromRow, Row, Type}; // Removido 'query' não utilizado do import
 use std::str::FromStr;
 use std::fmt;
-use crate::models::appstate::AppState;
+use crate::models::{appstate::AppState, session::UserSession};
 
 // Estrutura para erro de parsing de UserRole
 #[derive(Debug)]
@@ -24,6 +24,7 @@ impl std::error::Error for UserRoleParseError {}
 #[sqlx(type_name = "User_Role")] // Mapeia para o tipo ENUM 'User_Role' no banco de dados
 pub enum UserRole {
     Cliente,
+    Tecnico,
     Administrador,
 }
 
@@ -31,16 +32,25 @@ impl fmt::Display for UserRole {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             UserRole::Cliente => write!(f, "Cliente"),
+            UserRole::Tecnico => write!(f, "Tecnico"),
             UserRole::Administrador => write!(f, "Administrador"),
         }
     }
 }
 
+impl UserRole {
+    // Papéis da equipa de suporte (em oposição aos clientes)
+    pub fn is_staff(&self) -> bool {
+        matches!(self, UserRole::Tecnico | UserRole::Administrador)
+    }
+}
+
 impl FromStr for UserRole {
     type Err = String; // O erro ao converter de string é uma String
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "cliente" => Ok(UserRole::Cliente),
+            "tecnico" | "técnico" => Ok(UserRole::Tecnico),
             "administrador" => Ok(UserRole::Administrador),
             _ => Err(format!("Valor inválido para UserRole: {}", s)),
         }
@@ -60,6 +70,33 @@ pub struct User {
     pub user_password: String, // Deve armazenar a senha com hash
     #[sqlx(rename = "User_Role")]
     pub user_role: UserRole,
+    #[sqlx(rename = "User_Active")]
+    pub user_active: bool, // Contas desativadas não entram nem recebem tickets
+    #[sqlx(rename = "User_Email_Verified")]
+    pub user_email_verified: bool, // Contas do registo público só entram depois de confirmar o email
+    #[sqlx(rename = "User_Service_Account")]
+    pub user_service_account: bool, // Contas de serviço (integrações): só acedem com chaves de API
+}
+
+// Dados para um administrador criar um utilizador (POST /admin/users)
+#[derive(Debug, Deserialize)]
+pub struct NewUserPayload {
+    pub user_name: String,
+    pub user_email: String,
+    pub user_password: String,
+    pub user_role: String,
+}
+
+// Dados públicos de um utilizador (sem a senha), para listagens
+#[derive(Debug, Serialize)]
+pub struct UserSummary {
+    pub user_id: i32,
+    pub user_name: String,
+    pub user_email: String,
+    pub user_role: UserRole,
+    pub user_active: bool,
+    pub user_email_verified: bool,
+    pub user_service_account: bool,
 }
 
 impl User {
@@ -71,18 +108,23 @@ impl User {
             user_email: email,
             user_password: password, // Importante: esta senha já deve estar com hash
             user_role: role,
+            user_active: true,
+            user_email_verified: true,
+            user_service_account: false,
         }
     }
 
     // Salva um novo utilizador no banco de dados
     pub async fn save_user_in_db(new_user: &User, state: Arc<AppState>) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
         sqlx::query(
-            "INSERT INTO Users (User_Name, User_Email, User_Password, User_Role) VALUES (?, ?, ?, ?)"
+            "INSERT INTO Users (User_Name, User_Email, User_Password, User_Role, User_Email_Verified, User_Service_Account) VALUES (?, ?, ?, ?, ?, ?)"
         )
         .bind(&new_user.user_name)
         .bind(&new_user.user_email)
         .bind(&new_user.user_password) // Assume que new_user.user_password já está com hash
         .bind(new_user.user_role.to_string())
+        .bind(new_user.user_email_verified)
+        .bind(new_user.user_service_account)
         .execute(&state.pool)
         .await
     }
@@ -90,12 +132,17 @@ impl User {
     // Busca um utilizador pelo email
     pub async fn get_user_by_email(email: String, state: Arc<AppState>) -> Result<User, sqlx::Error> {
         let row = sqlx::query(
-            "SELECT ID_User, User_Name, User_Email, User_Password, User_Role FROM Users WHERE User_Email = ?"
+            "SELECT ID_User, User_Name, User_Email, User_Password, User_Role, User_Active, User_Email_Verified, User_Service_Account FROM Users WHERE User_Email = ?"
         )
         .bind(email)
         .fetch_one(&state.pool)
         .await?;
-    
+
+        User::from_row(&row)
+    }
+
+    // Converte uma linha da tabela Users num User
+    fn from_row(row: &MySqlRow) -> Result<User, sqlx::Error> {
         let user_role_str: String = row.try_get("User_Role")?;
         // Converte a string do banco para o enum UserRole, tratando possíveis erros de decodificação
         let user_role_enum = UserRole::from_str(&user_role_str)
@@ -107,6 +154,114 @@ impl User {
             user_email: row.try_get("User_Email")?,
             user_password: row.try_get("User_Password")?,
             user_role: user_role_enum,
+            user_active: row.try_get("User_Active")?,
+            user_email_verified: row.try_get("User_Email_Verified")?,
+            user_service_account: row.try_get("User_Service_Account")?,
         })
     }
+
+    // Busca um utilizador pelo ID (None se não existir)
+    pub async fn get_user_by_id(user_id: i32, state: Arc<AppState>) -> Result<Option<User>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT ID_User, User_Name, User_Email, User_Password, User_Role, User_Active, User_Email_Verified, User_Service_Account FROM Users WHERE ID_User = ?"
+        )
+        .bind(user_id)
+        .fetch_optional(&state.pool)
+        .await?;
+
+        row.as_ref().map(User::from_row).transpose()
+    }
+
+    // Lista todos os utilizadores, para a gestão de contas pelos administradores
+    pub async fn get_all_users(state: Arc<AppState>) -> Result<Vec<UserSummary>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT ID_User, User_Name, User_Email, User_Password, User_Role, User_Active, User_Email_Verified, User_Service_Account FROM Users ORDER BY User_Name"
+        )
+        .fetch_all(&state.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| User::from_row(row).map(UserSummary::from))
+            .collect()
+    }
+
+    // Altera o papel de um utilizador. As sessões abertas são encerradas para que o novo
+    // papel valha de imediato (os tokens emitidos levam o papel antigo).
+    pub async fn update_user_role(state: Arc<AppState>, user_id: i32, role: UserRole) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        let result = sqlx::query("UPDATE Users SET User_Role = ? WHERE ID_User = ?")
+            .bind(role.to_string())
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        UserSession::revoke_all_sessions(&mut tx, user_id).await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    // Ativa ou desativa uma conta; ao desativar, encerra todas as sessões dela
+    pub async fn set_user_active(state: Arc<AppState>, user_id: i32, active: bool) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        let result = sqlx::query("UPDATE Users SET User_Active = ? WHERE ID_User = ?")
+            .bind(active)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        if !active {
+            UserSession::revoke_all_sessions(&mut tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    // Lista as contas de serviço (integrações)
+    pub async fn get_service_accounts(state: Arc<AppState>) -> Result<Vec<UserSummary>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT ID_User, User_Name, User_Email, User_Password, User_Role, User_Active, User_Email_Verified, User_Service_Account FROM Users
+             WHERE User_Service_Account = TRUE ORDER BY User_Name"
+        )
+        .fetch_all(&state.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| User::from_row(row).map(UserSummary::from))
+            .collect()
+    }
+
+    // Marca o email como verificado (ex.: confirmado por um fornecedor de identidade)
+    pub async fn mark_email_verified(state: Arc<AppState>, user_id: i32) -> Result<sqlx::mysql::MySqlQueryResult, sqlx::Error> {
+        sqlx::query("UPDATE Users SET User_Email_Verified = TRUE WHERE ID_User = ?")
+            .bind(user_id)
+            .execute(&state.pool)
+            .await
+    }
+
+    // Lista os utilizadores da equipa de suporte (técnicos e administradores)
+    pub async fn get_staff_users(state: Arc<AppState>) -> Result<Vec<UserSummary>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT ID_User, User_Name, User_Email, User_Password, User_Role, User_Active, User_Email_Verified, User_Service_Account FROM Users
+             WHERE User_Role IN ('Tecnico', 'Administrador') AND User_Active = TRUE AND User_Service_Account = FALSE
+             ORDER BY User_Name"
+        )
+        .fetch_all(&state.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| User::from_row(row).map(UserSummary::from))
+            .collect()
+    }
+}
+
+impl From<User> for UserSummary {
+    fn from(user: User) -> Self {
+        Self {
+            user_id: user.user_id.unwrap_or_default(),
+            user_name: user.user_name,
+            user_email: user.user_email,
+            user_role: user.user_role,
+            user_active: user.user_active,
+            user_email_verified: user.user_email_verified,
+            user_service_account: user.user_service_account,
+        }
+    }
 }
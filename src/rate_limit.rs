@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// Acima deste número de IPs guardados, as janelas já terminadas são descartadas
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+// Endereço IP do cliente. Por omissão é o da ligação TCP; com TRUST_PROXY_HEADERS=true
+// (aplicação atrás de um proxy reverso) usa o primeiro endereço de X-Forwarded-For.
+pub struct ClientIp(pub IpAddr);
+
+fn trust_proxy_headers() -> bool {
+    static TRUST: OnceLock<bool> = OnceLock::new();
+    *TRUST.get_or_init(|| {
+        std::env::var("TRUST_PROXY_HEADERS").is_ok_and(|value| value.trim().eq_ignore_ascii_case("true"))
+    })
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if trust_proxy_headers() {
+            let forwarded = parts
+                .headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .and_then(|value| value.trim().parse().ok());
+            if let Some(ip) = forwarded {
+                return Ok(ClientIp(ip));
+            }
+        }
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        Ok(ClientIp(ip))
+    }
+}
+
+// Resposta 429 com o cabeçalho Retry-After (em segundos)
+pub fn too_many_requests<T: serde::Serialize>(retry_after_secs: u64, body: T) -> Response {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs.max(1)));
+    response
+}
+
+struct Window {
+    started_at: Instant,
+    requests: u32,
+}
+
+// Limite de pedidos por IP em janelas fixas, guardado em memória (por instância).
+// Aplica-se a qualquer router com:
+//     .layer(middleware::from_fn_with_state(RateLimiter::per_minute(30), rate_limit))
+#[derive(Clone)]
+pub struct RateLimiter {
+    max_requests: u32,
+    window: Duration,
+    clients: Arc<Mutex<HashMap<IpAddr, Window>>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        Self {
+            max_requests,
+            window,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn per_minute(max_requests: u32) -> Self {
+        Self::new(max_requests, Duration::from_secs(60))
+    }
+
+    // Conta um pedido do IP. Devolve o tempo até ao fim da janela se o limite foi excedido.
+    fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut clients = self.clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if clients.len() >= MAX_TRACKED_CLIENTS {
+            clients.retain(|_, window| now.duration_since(window.started_at) < self.window);
+        }
+
+        let window = clients.entry(ip).or_insert(Window { started_at: now, requests: 0 });
+        if now.duration_since(window.started_at) >= self.window {
+            window.started_at = now;
+            window.requests = 0;
+        }
+        if window.requests >= self.max_requests {
+            return Err(self.window.saturating_sub(now.duration_since(window.started_at)));
+        }
+        window.requests += 1;
+        Ok(())
+    }
+}
+
+// Middleware do limite de pedidos: responde 429 (com Retry-After) quando o IP excede o limite.
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    ClientIp(ip): ClientIp,
+    request: Request,
+    next: Next,
+) -> Response {
+    match limiter.check(ip) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => too_many_requests(
+            retry_after.as_secs_f64().ceil() as u64,
+            json!({"erro": "Demasiados pedidos. Tente novamente mais tarde."}),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_each_ip_within_the_window() {
+        let limiter = RateLimiter::new(3, Duration::from_secs(60));
+        let start = Instant::now();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "2001:db8::1".parse().unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(ip, start), Ok(()));
+        }
+        assert_eq!(limiter.check_at(ip, start + Duration::from_secs(20)), Err(Duration::from_secs(40)));
+        // Os outros IPs têm o seu próprio contador
+        assert_eq!(limiter.check_at(other, start + Duration::from_secs(20)), Ok(()));
+    }
+
+    #[test]
+    fn a_new_window_resets_the_count() {
+        let limiter = RateLimiter::per_minute(1);
+        let start = Instant::now();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        assert_eq!(limiter.check_at(ip, start), Ok(()));
+        assert!(limiter.check_at(ip, start + Duration::from_secs(59)).is_err());
+        assert_eq!(limiter.check_at(ip, start + Duration::from_secs(60)), Ok(()));
+        assert!(limiter.check_at(ip, start + Duration::from_secs(61)).is_err());
+    }
+
+    // Os clones partilham o contador (um limitador para várias rotas)
+    #[test]
+    fn clones_share_the_count() {
+        let limiter = RateLimiter::per_minute(1);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+
+        assert_eq!(limiter.clone().check_at(ip, now), Ok(()));
+        assert!(limiter.check_at(ip, now).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 
 use std::sync::Arc;
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -8,9 +9,30 @@ use crate::{
     controllers::admin_controller::{
         admin_dashboard_page_handler, 
         get_admin_dashboard_data_api, 
-        set_ticket_priority_handler
+        set_ticket_priority_handler,
+        assign_ticket_handler,
+        list_sla_policies_handler,
+        save_sla_policy_handler,
+        list_users_handler,
+        create_user_handler,
+        set_user_role_handler,
+        deactivate_user_handler,
+        activate_user_handler,
+        create_invitation_handler,
+        list_invitations_handler,
+        revoke_invitation_handler,
+        list_roles_handler,
+        set_role_permissions_handler
     },
-    models::appstate::AppState,
+    controllers::api_key_controller::{
+        list_service_accounts_handler,
+        create_service_account_handler,
+        list_service_account_keys_handler,
+        create_service_account_key_handler,
+        revoke_api_key_handler
+    },
+    auth::require_permission,
+    models::{appstate::AppState, permission::Permission},
 };
 
 pub struct AdminRoute;
@@ -18,13 +40,41 @@ pub struct AdminRoute;
 impl AdminRoute {
     // Cria as rotas específicas para a área de administração.
     pub fn create_admin_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+        // Cada rota exige a permissão indicada (ver bd/017_permissoes.sql)
+        let require = |permission: Permission| middleware::from_fn_with_state((state.clone(), permission), require_permission);
         Router::new()
             // Rota para exibir o painel principal do administrador.
             .route("/dashboard", get(admin_dashboard_page_handler))
             // Rota de API para buscar os dados a serem exibidos no dashboard do admin.
             .route("/dashboard-data", get(get_admin_dashboard_data_api))
-            // Rota para o administrador definir a prioridade de um ticket específico.
-            .route("/tickets/:id/set-priority", post(set_ticket_priority_handler))
+            // Rota para definir a prioridade de um ticket específico.
+            .route("/tickets/:id/set-priority", post(set_ticket_priority_handler).route_layer(require(Permission::TicketSetPriority)))
+            // Rota para atribuir um ticket a um técnico.
+            .route("/tickets/:id/assign", post(assign_ticket_handler).route_layer(require(Permission::TicketAssign)))
+            // Rotas de API para consultar e configurar as políticas de SLA.
+            .route("/sla-policies", get(list_sla_policies_handler).post(save_sla_policy_handler).route_layer(require(Permission::SlaManage)))
+            // Rotas de API para a gestão de utilizadores (papéis e contas ativas).
+            .route("/users", get(list_users_handler).post(create_user_handler).route_layer(require(Permission::UserManage)))
+            .route("/users/:id/role", post(set_user_role_handler).route_layer(require(Permission::UserManage)))
+            .route("/users/:id/deactivate", post(deactivate_user_handler).route_layer(require(Permission::UserManage)))
+            .route("/users/:id/activate", post(activate_user_handler).route_layer(require(Permission::UserManage)))
+            // Rotas de API para os convites da equipa.
+            .route("/invitations", get(list_invitations_handler).post(create_invitation_handler).route_layer(require(Permission::InvitationManage)))
+            .route("/invitations/:id/revoke", post(revoke_invitation_handler).route_layer(require(Permission::InvitationManage)))
+            // Rotas de API para as contas de serviço das integrações e as suas chaves.
+            .route(
+                "/service-accounts",
+                get(list_service_accounts_handler).post(create_service_account_handler).route_layer(require(Permission::ServiceAccountManage)),
+            )
+            .route(
+                "/service-accounts/:id/api-keys",
+                get(list_service_account_keys_handler).post(create_service_account_key_handler).route_layer(require(Permission::ServiceAccountManage)),
+            )
+            // Revoga qualquer chave de API (pessoal ou de uma conta de serviço).
+            .route("/api-keys/:id/revoke", post(revoke_api_key_handler).route_layer(require(Permission::ServiceAccountManage)))
+            // Rotas de API para consultar e editar as permissões dos papéis.
+            .route("/roles", get(list_roles_handler).route_layer(require(Permission::RoleManage)))
+            .route("/roles/:role/permissions", post(set_role_permissions_handler).route_layer(require(Permission::RoleManage)))
             .with_state(state)
     }
 }
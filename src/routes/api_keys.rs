@@ -0,0 +1,27 @@
+use std::sync::Arc;
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use crate::{
+    controllers::api_key_controller::{
+        list_my_api_keys_handler,
+        create_my_api_key_handler,
+        revoke_my_api_key_handler
+    },
+    models::appstate::AppState,
+};
+
+pub struct ApiKeyRoute;
+
+impl ApiKeyRoute {
+    // Cria as rotas de gestão das chaves de API pessoais.
+    // Só são acessíveis com uma sessão de login: uma chave não cria nem revoga chaves.
+    pub fn create_api_key_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+        Router::new()
+            // GET: lista as chaves do utilizador; POST: cria uma chave (o segredo só é devolvido agora).
+            .route("/", get(list_my_api_keys_handler).post(create_my_api_key_handler))
+            .route("/:id/revoke", post(revoke_my_api_key_handler))
+            .with_state(state)
+    }
+}
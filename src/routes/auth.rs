@@ -2,6 +2,7 @@ use std::sync::Arc;
 use std::collections::HashMap; // Necessário para Query<HashMap<String, String>>
 use axum::{
     extract::{State, Query},
+    middleware,
     response::{Html, IntoResponse}, // Html é usado para a resposta de erro
     routing::{get, post},
     Router,
@@ -11,18 +12,47 @@ use tera; // Para tera::Context
 
 // Importe o AppState e o handler de login do controller
 use crate::models::appstate::AppState;
+use crate::auth::internal_redirect;
 use crate::controllers; // Usado para controllers::auth_controller::login
+use crate::rate_limit::{rate_limit, RateLimiter};
+
+// Pedidos por minuto e por IP nos POSTs que verificam a senha ou um código TOTP
+const AUTH_REQUESTS_PER_MINUTE: u32 = 30;
 
 pub struct AuthRoute;
 
 impl AuthRoute {
     // Cria as rotas para autenticação (login).
     pub fn get_authenticated(state: Arc<AppState>) -> Router<Arc<AppState>> {
+        // Um só limitador (e contador por IP) para todas as rotas que testam credenciais
+        let limiter = RateLimiter::per_minute(AUTH_REQUESTS_PER_MINUTE);
+        let limited = || middleware::from_fn_with_state(limiter.clone(), rate_limit);
+
         Router::new()
             // Rota para exibir a página de login.
             .route("/", get(render_login_page))
             // Rota para processar a submissão do formulário de login.
-            .route("/", post(controllers::auth_controller::login))
+            .route("/", post(controllers::auth_controller::login).route_layer(limited()))
+            // Rota para trocar o refresh token por um novo par de tokens.
+            .route("/refresh", post(controllers::auth_controller::refresh))
+            // Troca o access token pelos cookies da sessão do navegador (páginas protegidas).
+            .route("/session", post(controllers::auth_controller::open_browser_session))
+            // Segunda etapa do login e gestão da verificação em duas etapas (TOTP).
+            .route("/2fa", get(controllers::auth_controller::two_factor_status))
+            .route("/2fa", post(controllers::auth_controller::verify_second_factor).route_layer(limited()))
+            .route("/2fa/setup", post(controllers::auth_controller::setup_two_factor))
+            .route("/2fa/enable", post(controllers::auth_controller::enable_two_factor).route_layer(limited()))
+            .route("/2fa/disable", post(controllers::auth_controller::disable_two_factor).route_layer(limited()))
+            // Login por SSO (OpenID Connect): redirecionamento para o fornecedor e regresso.
+            .route("/oidc", get(controllers::oidc_controller::start_oidc_login))
+            .route("/oidc/callback", get(controllers::oidc_controller::oidc_callback))
+            .with_state(state)
+    }
+
+    // Cria a rota de logout (encerra a sessão no servidor).
+    pub fn logout_route(state: Arc<AppState>) -> Router<Arc<AppState>> {
+        Router::new()
+            .route("/", post(controllers::auth_controller::logout))
             .with_state(state)
     }
 }
@@ -40,6 +70,10 @@ pub async fn render_login_page(
     // Verifica se há um parâmetro 'success' na URL (ex: após registo bem-sucedido).
     let success_value = params.get("success").map_or("", |s| s.as_str());
     context.insert("success", success_value);
+    context.insert("oidc_enabled", &state.oidc.is_some());
+    // Página protegida que levou ao login, para onde login.js volta depois de entrar
+    let next = params.get("next").and_then(|next| internal_redirect(next)).unwrap_or("");
+    context.insert("next", next);
 
     match state.tera.render(template_name, &context) {
         Ok(rendered_html) => Html(rendered_html).into_response(),
@@ -3,4 +3,6 @@
 pub mod auth;       
 pub mod ticket;     
 pub mod admin;      
-pub mod register;   
\ No newline at end of file
+pub mod register;   
+pub mod password;
+pub mod api_keys;
\ No newline at end of file
@@ -0,0 +1,31 @@
+use std::sync::Arc;
+use axum::{
+    middleware,
+    routing::get,
+    Router,
+};
+use crate::{
+    controllers::password_controller::{
+        render_forgot_password_page, render_reset_password_page, request_password_reset, reset_password,
+    },
+    models::appstate::AppState,
+    rate_limit::{rate_limit, RateLimiter},
+};
+
+// Pedidos por minuto e por IP (cada pedido pode enviar um email)
+const PASSWORD_REQUESTS_PER_MINUTE: u32 = 5;
+
+pub struct PasswordRoute;
+
+impl PasswordRoute {
+    // Cria as rotas de recuperação de senha.
+    pub fn create_password_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+        Router::new()
+            // GET: página "esqueci a senha"; POST: envia o link de redefinição por email.
+            .route("/forgot", get(render_forgot_password_page).post(request_password_reset))
+            // GET: formulário de nova senha (link do email); POST: grava a nova senha.
+            .route("/reset", get(render_reset_password_page).post(reset_password))
+            .layer(middleware::from_fn_with_state(RateLimiter::per_minute(PASSWORD_REQUESTS_PER_MINUTE), rate_limit))
+            .with_state(state)
+    }
+}
@@ -1,14 +1,20 @@
 use std::sync::Arc;
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::get,
     Router,
 };
 use crate::{
     // Importa os handlers do controller de utilizador (user_controller)
-    controllers::user_controller::{create_user, render_register_page}, 
+    controllers::user_controller::{accept_invitation, create_user, render_invite_page, render_register_page},
+    controllers::verification_controller::{render_resend_verification_page, resend_verification, verify_email},
     models::appstate::AppState,
+    rate_limit::{rate_limit, RateLimiter},
 };
 
+// Contas criadas por minuto e por IP
+const REGISTER_REQUESTS_PER_MINUTE: u32 = 10;
+
 pub struct RegisterRoute;
 
 impl RegisterRoute {
@@ -19,6 +25,12 @@ impl RegisterRoute {
             // - GET: renderizar a página de registo.
             // - POST: processar a criação de um novo utilizador.
             .route("/", get(render_register_page).post(create_user))
+            // Aceitação de um convite da equipa (link enviado pelo administrador)
+            .route("/invite", get(render_invite_page).post(accept_invitation))
+            // Confirmação do email das contas novas e reenvio do link
+            .route("/verify", get(verify_email))
+            .route("/verify/resend", get(render_resend_verification_page).post(resend_verification))
+            .layer(middleware::from_fn_with_state(RateLimiter::per_minute(REGISTER_REQUESTS_PER_MINUTE), rate_limit))
             .with_state(state)
     }
 }
@@ -1,17 +1,24 @@
 use std::sync::Arc;
 use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
     routing::{get, post},
     Router
 };
 use crate::{
+    auth::require_permission,
+    controllers::attachment_controller::{download_attachment, list_attachments},
+    controllers::comment_controller::{create_comment, list_comments},
     controllers::ticket_controller::{
         create_ticket, 
         list_tickets,
         new_ticket,
-        close_ticket,
-        get_my_open_tickets_api
+        transition_ticket,
+        get_ticket_history,
+        get_my_open_tickets_api,
+        get_my_assigned_tickets_api
     },
-    models::appstate::AppState
+    models::{appstate::AppState, permission::Permission}
 };
 
 pub struct TicketRoute;
@@ -25,6 +32,8 @@ impl TicketRoute {
             .route("/", get(new_ticket).post(create_ticket)) 
             // GET /api/my-open-tickets: Endpoint de API para o frontend buscar os tickets abertos do utilizador logado.
             .route("/api/my-open-tickets", get(get_my_open_tickets_api))
+            // O POST aceita anexos em multipart/form-data; o limite padrão do axum (2 MB) seria curto.
+            .layer(DefaultBodyLimit::max(state.attachment_limits.max_request_bytes()))
             .with_state(state)
     }
 
@@ -33,13 +42,42 @@ impl TicketRoute {
     pub fn list_tickets_route(state: Arc<AppState>) -> Router<Arc<AppState>> {
         Router::new()
             .route("/", get(list_tickets))
+            // GET /assigned: tickets atribuídos ao técnico autenticado.
+            .route(
+                "/assigned",
+                get(get_my_assigned_tickets_api)
+                    .route_layer(middleware::from_fn_with_state((state.clone(), Permission::TicketListAssigned), require_permission)),
+            )
             .with_state(state)
     }
 
-    // Define a rota para fechar um ticket específico.
-    pub fn close_ticket_route(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    // Define a rota para mover um ticket específico no ciclo de vida (fechar, reabrir, etc.).
+    pub fn transition_ticket_route(state: Arc<AppState>) -> Router<Arc<AppState>> {
         Router::new()
-            .route("/:id/close", post(close_ticket))
+            .route("/:id/transition", post(transition_ticket))
+            .with_state(state)
+    }
+
+    // Define a rota do histórico de alterações (auditoria) de um ticket.
+    pub fn history_route(state: Arc<AppState>) -> Router<Arc<AppState>> {
+        Router::new()
+            .route("/:id/history", get(get_ticket_history))
+            .with_state(state)
+    }
+
+    // Define as rotas da conversa (comentários e respostas) de um ticket.
+    pub fn comments_route(state: Arc<AppState>) -> Router<Arc<AppState>> {
+        Router::new()
+            .route("/:id/comments", get(list_comments).post(create_comment))
+            .layer(DefaultBodyLimit::max(state.attachment_limits.max_request_bytes()))
+            .with_state(state)
+    }
+
+    // Define as rotas de consulta e download dos anexos de um ticket.
+    pub fn attachments_route(state: Arc<AppState>) -> Router<Arc<AppState>> {
+        Router::new()
+            .route("/:id/attachments", get(list_attachments))
+            .route("/:id/attachments/:attachment_id", get(download_attachment))
             .with_state(state)
     }
 }
@@ -0,0 +1,160 @@
+use std::env;
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::models::{
+    appstate::AppState,
+    user::{User, UserRole},
+};
+use crate::services::auth_service::{generate_opaque_token, password_hash, password_verify};
+use crate::services::ldap_auth::LdapAuthProvider;
+
+// Fornecedor de autenticação por email (ou nome de utilizador) e senha, usado em POST /login.
+// Devolve a conta local do utilizador autenticado, None se as credenciais não forem aceites
+// por este fornecedor, ou um erro quando não foi possível consultá-lo.
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn authenticate(&self, state: &Arc<AppState>, login: &str, password: &str) -> Result<Option<User>, String>;
+}
+
+// Escolhe os fornecedores conforme AUTH_PROVIDERS ("local", o padrão, e/ou "ldap"),
+// consultados pela ordem indicada
+pub fn providers_from_env() -> Result<Vec<Box<dyn AuthProvider>>, String> {
+    let value = env::var("AUTH_PROVIDERS").unwrap_or_else(|_| "local".to_string());
+    let mut providers: Vec<Box<dyn AuthProvider>> = Vec::new();
+    for name in value.split(',').map(|name| name.trim().to_lowercase()).filter(|name| !name.is_empty()) {
+        match name.as_str() {
+            "local" => providers.push(Box::new(LocalAuthProvider)),
+            "ldap" => providers.push(Box::new(LdapAuthProvider::from_env()?)),
+            other => return Err(format!("AUTH_PROVIDERS inválido: {}", other)),
+        }
+    }
+    if providers.is_empty() {
+        return Err("AUTH_PROVIDERS precisa indicar pelo menos um fornecedor!".to_string());
+    }
+    Ok(providers)
+}
+
+// Autentica nos fornecedores configurados, pela ordem. Se nenhum aceitar as credenciais e
+// algum tiver falhado, devolve o erro (a senha pode estar certa num fornecedor indisponível).
+pub async fn authenticate(state: &Arc<AppState>, login: &str, password: &str) -> Result<Option<User>, String> {
+    let mut last_error = None;
+    for provider in &state.auth_providers {
+        match provider.authenticate(state, login, password).await {
+            Ok(Some(user)) => return Ok(Some(user)),
+            Ok(None) => {}
+            Err(e) => last_error = Some(format!("{}: {}", provider.name(), e)),
+        }
+    }
+    match last_error {
+        Some(e) => Err(e),
+        None => Ok(None),
+    }
+}
+
+// Senha guardada com bcrypt na tabela Users
+pub struct LocalAuthProvider;
+
+#[async_trait]
+impl AuthProvider for LocalAuthProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn authenticate(&self, state: &Arc<AppState>, login: &str, password: &str) -> Result<Option<User>, String> {
+        match User::get_user_by_email(login.to_string(), state.clone()).await {
+            // Contas de serviço só acedem com chaves de API
+            Ok(user) if !user.user_service_account && password_verify(password, &user.user_password) => Ok(Some(user)),
+            Ok(_) | Err(sqlx::Error::RowNotFound) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+// Identidade confirmada por um sistema externo (diretório LDAP, fornecedor OIDC)
+#[derive(Debug)]
+pub struct ExternalIdentity {
+    pub email: String,
+    pub name: String,
+    pub role: Option<UserRole>, // None quando não há grupos mapeados para decidir o papel
+}
+
+// Grupos externos que dão os papéis da equipa
+pub struct RoleMapping {
+    admin_groups: Vec<String>,
+    tecnico_groups: Vec<String>,
+}
+
+impl RoleMapping {
+    // Lê as listas <PREFIXO>_ADMIN_GROUPS e <PREFIXO>_TECNICO_GROUPS (separadas por ";" ou ",")
+    pub fn from_env(prefix: &str, separator: char) -> Self {
+        let groups = |var: String| -> Vec<String> {
+            env::var(var)
+                .unwrap_or_default()
+                .split(separator)
+                .map(|group| group.trim().to_string())
+                .filter(|group| !group.is_empty())
+                .collect()
+        };
+        Self {
+            admin_groups: groups(format!("{}_ADMIN_GROUPS", prefix)),
+            tecnico_groups: groups(format!("{}_TECNICO_GROUPS", prefix)),
+        }
+    }
+
+    // Papel para os grupos do utilizador: administradores, depois técnicos, senão cliente.
+    // None se não houver grupos configurados (o papel da conta não é alterado).
+    pub fn role_for<S: AsRef<str>>(&self, groups: &[S]) -> Option<UserRole> {
+        if self.admin_groups.is_empty() && self.tecnico_groups.is_empty() {
+            return None;
+        }
+        let in_any = |mapped: &[String]| {
+            groups.iter().any(|group| mapped.iter().any(|m| m.eq_ignore_ascii_case(group.as_ref())))
+        };
+
+        Some(if in_any(&self.admin_groups) {
+            UserRole::Administrador
+        } else if in_any(&self.tecnico_groups) {
+            UserRole::Tecnico
+        } else {
+            UserRole::Cliente
+        })
+    }
+}
+
+// Encontra a conta local com o email da identidade externa ou cria-a no primeiro login.
+// Quando a identidade traz um papel, a conta acompanha-o. O email fica verificado.
+pub async fn provision_external_user(state: &Arc<AppState>, identity: &ExternalIdentity) -> Result<User, sqlx::Error> {
+    let user_id = match User::get_user_by_email(identity.email.clone(), state.clone()).await {
+        Ok(user) => {
+            let user_id = user.user_id.unwrap_or_default();
+            // Contas desativadas e contas de serviço ficam como estão; quem chama recusa o login
+            if !user.user_active || user.user_service_account {
+                return Ok(user);
+            }
+            if !user.user_email_verified {
+                User::mark_email_verified(state.clone(), user_id).await?;
+            }
+            match identity.role {
+                Some(role) if role != user.user_role => {
+                    User::update_user_role(state.clone(), user_id, role).await?;
+                }
+                _ => {}
+            }
+            user_id
+        }
+        Err(sqlx::Error::RowNotFound) => {
+            // A senha local é aleatória: a conta entra pelo sistema externo
+            let new_user = User::build_user(
+                identity.name.clone(),
+                identity.email.clone(),
+                password_hash(&generate_opaque_token()),
+                identity.role.unwrap_or(UserRole::Cliente),
+            );
+            User::save_user_in_db(&new_user, state.clone()).await?.last_insert_id() as i32
+        }
+        Err(e) => return Err(e),
+    };
+
+    User::get_user_by_id(user_id, state.clone()).await?.ok_or(sqlx::Error::RowNotFound)
+}
@@ -1,15 +1,90 @@
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{encode, decode, Header, EncodingKey, DecodingKey, Validation, errors::Error as JwtError};
 use serde::{Deserialize, Serialize};
 use crate::models::user::UserRole; // Para o tipo UserRole nas claims
+use crate::services::jwt_keys::JwtKeys;
 use bcrypt::{hash, verify, DEFAULT_COST};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+// Validade dos access tokens; a sessão continua com o refresh token
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+// Prefixo do campo "sid" das claims dos pedidos feitos com chaves de API
+const API_KEY_SESSION_PREFIX: &str = "api-key:";
+// Tempo para concluir a segunda etapa do login (código TOTP)
+const MFA_TOKEN_MINUTES: i64 = 5;
 
 // Estrutura das claims contidas no JWT.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,    // Subject (ID do utilizador)
     pub role: String,   // Papel do utilizador (ex: "Cliente", "Administrador")
     pub exp: usize,     // Timestamp de expiração do token
+    pub sid: String,    // Sessão (User_Sessions) à qual o token pertence
+}
+
+impl Claims {
+    // Claims de um pedido autenticado com uma chave de API: a "sessão" é a própria chave.
+    pub fn for_api_key(user_id: i32, user_role: &UserRole, api_key_id: i32) -> Self {
+        Claims {
+            sub: user_id.to_string(),
+            role: user_role.to_string(),
+            exp: (Utc::now() + Duration::minutes(ACCESS_TOKEN_MINUTES)).timestamp() as usize,
+            sid: format!("{}{}", API_KEY_SESSION_PREFIX, api_key_id),
+        }
+    }
+}
+
+// Claims dos tokens de uso específico (segunda etapa do login, convites): assinados como
+// os access tokens, mas sem papel nem sessão, logo não passam como Claims na API.
+#[derive(Debug, Serialize, Deserialize)]
+struct PurposeClaims {
+    sub: String,
+    exp: usize,
+    purpose: String,
+}
+
+const MFA_PURPOSE: &str = "mfa";
+const INVITE_PURPOSE: &str = "invite";
+
+fn purpose_token_gen(secret_key: &str, subject: i32, purpose: &str, expires_at: DateTime<Utc>) -> String {
+    let claims = PurposeClaims {
+        sub: subject.to_string(),
+        exp: expires_at.timestamp() as usize,
+        purpose: purpose.to_string(),
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret_key.as_ref()))
+        .expect("Falha ao gerar token JWT")
+}
+
+fn purpose_token_decode(secret_key: &str, token: &str, purpose: &str) -> Option<i32> {
+    let claims = decode::<PurposeClaims>(token, &DecodingKey::from_secret(secret_key.as_ref()), &Validation::default())
+        .ok()?
+        .claims;
+    if claims.purpose != purpose {
+        return None;
+    }
+    claims.sub.parse().ok()
+}
+
+// Gera o token intermédio entregue depois da senha, trocado em POST /login/2fa pelo código TOTP.
+pub fn mfa_token_gen(secret_key: &str, user_id: i32) -> String {
+    purpose_token_gen(secret_key, user_id, MFA_PURPOSE, Utc::now() + Duration::minutes(MFA_TOKEN_MINUTES))
+}
+
+// Valida o token intermédio e devolve o ID do utilizador.
+pub fn mfa_token_decode(secret_key: &str, token: &str) -> Option<i32> {
+    purpose_token_decode(secret_key, token, MFA_PURPOSE)
+}
+
+// Gera o token assinado do link de um convite, válido até ao fim do convite.
+pub fn invite_token_gen(secret_key: &str, invitation_id: i32, expires_at: DateTime<Utc>) -> String {
+    purpose_token_gen(secret_key, invitation_id, INVITE_PURPOSE, expires_at)
+}
+
+// Valida a assinatura e a validade do link de um convite e devolve o ID do convite.
+pub fn invite_token_decode(secret_key: &str, token: &str) -> Option<i32> {
+    purpose_token_decode(secret_key, token, INVITE_PURPOSE)
 }
 
 // Gera um hash bcrypt para uma senha.
@@ -22,26 +97,30 @@ pub fn password_verify(password: &str, hashed_password: &str) -> bool {
     verify(password, hashed_password).unwrap_or(false)
 }
 
-// Gera um token JWT para um utilizador.
-pub fn jwt_gen(secret_key: &str, user_id: i32, user_role: &UserRole) -> String {
+// Gera um access token JWT de curta duração para uma sessão de um utilizador.
+pub fn jwt_gen(keys: &JwtKeys, user_id: i32, user_role: &UserRole, session_id: &str) -> String {
     let claims = Claims {
         sub: user_id.to_string(),
         role: user_role.to_string(), // Utiliza a implementação Display de UserRole
-        exp: (Utc::now() + Duration::hours(24)).timestamp() as usize,
+        exp: (Utc::now() + Duration::minutes(ACCESS_TOKEN_MINUTES)).timestamp() as usize,
+        sid: session_id.to_string(),
     };
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret_key.as_bytes())
-    )
-    .expect("Falha ao gerar token JWT")
+    keys.sign(&claims)
 }
 
 // Decodifica e valida um token JWT, retornando as claims se for válido.
-pub fn jwt_decode_and_validate(secret_key: &str, token: &str) -> Result<Claims, JwtError> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret_key.as_bytes()),
-        &Validation::default(), // Utiliza validações padrão (algoritmo, expiração)
-    ).map(|token_data| token_data.claims)
+pub fn jwt_decode_and_validate(keys: &JwtKeys, token: &str) -> Result<Claims, JwtError> {
+    keys.verify::<Claims>(token) // Valida a assinatura pelo "kid" e a expiração
+}
+
+// Gera um token opaco aleatório (256 bits, em hexadecimal), usado como ID de sessão e refresh token.
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+// Hash SHA-256 de um token opaco, a única forma em que ele é guardado no banco.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
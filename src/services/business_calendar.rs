@@ -0,0 +1,364 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+
+// Limite de dias percorridos ao somar horas úteis (evita laços infinitos
+// com calendários mal configurados, ex.: todos os dias marcados como feriado).
+const MAX_CALENDAR_DAYS: i64 = 3660;
+
+// Calendário de expediente usado no cálculo de prazos de SLA.
+// Os prazos só correm dentro do horário de trabalho de cada dia da semana,
+// no fuso configurado, e nunca em feriados.
+#[derive(Debug, Clone)]
+pub struct BusinessCalendar {
+    timezone: Tz,
+    // Expediente por dia da semana, indexado a partir de segunda-feira (0 = seg)
+    working_hours: [Option<(NaiveTime, NaiveTime)>; 7],
+    holidays: HashSet<NaiveDate>,
+    // Feriados de data fixa que se repetem todos os anos
+    yearly_holidays: Vec<YearlyHoliday>,
+}
+
+// Feriado com RRULE:FREQ=YEARLY: repete-se no dia e mês do DTSTART, a partir do ano
+// do DTSTART e até ao UNTIL ou durante COUNT ocorrências, se indicados
+#[derive(Debug, Clone)]
+struct YearlyHoliday {
+    first: NaiveDate,
+    until: Option<NaiveDate>,
+    count: Option<i32>,
+}
+
+// Limites de uma RRULE anual: (UNTIL, COUNT)
+type YearlyBounds = (Option<NaiveDate>, Option<i32>);
+
+impl YearlyHoliday {
+    fn matches(&self, date: NaiveDate) -> bool {
+        (date.month(), date.day()) == (self.first.month(), self.first.day())
+            && date >= self.first
+            && self.until.is_none_or(|until| date <= until)
+            && self.count.is_none_or(|count| date.year() - self.first.year() < count)
+    }
+}
+
+impl BusinessCalendar {
+    // Cria um calendário sem feriados e sem expediente configurado
+    pub fn new(timezone: Tz) -> Self {
+        Self {
+            timezone,
+            working_hours: [None; 7],
+            holidays: HashSet::new(),
+            yearly_holidays: Vec::new(),
+        }
+    }
+
+    // Carrega o calendário a partir das variáveis de ambiente:
+    // BUSINESS_TIMEZONE   (padrão "America/Sao_Paulo")
+    // BUSINESS_HOURS      (padrão "seg-sex=08:00-18:00"; ex.: "seg-qui=08:00-18:00;sex=08:00-17:00")
+    // BUSINESS_HOLIDAYS_ICS (opcional; caminho para um ficheiro iCalendar com os feriados)
+    pub fn from_env() -> Result<Self, String> {
+        let timezone_name = env::var("BUSINESS_TIMEZONE").unwrap_or_else(|_| "America/Sao_Paulo".to_string());
+        let timezone: Tz = timezone_name
+            .parse()
+            .map_err(|_| format!("Fuso horário inválido: {}", timezone_name))?;
+
+        let mut calendar = BusinessCalendar::new(timezone);
+        let hours = env::var("BUSINESS_HOURS").unwrap_or_else(|_| "seg-sex=08:00-18:00".to_string());
+        calendar.set_working_hours_from_spec(&hours)?;
+
+        if let Ok(path) = env::var("BUSINESS_HOLIDAYS_ICS") {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Falha ao ler {}: {}", path, e))?;
+            calendar.import_ics_holidays(&content)?;
+        }
+
+        Ok(calendar)
+    }
+
+    // Define o expediente de um dia da semana (0 = segunda ... 6 = domingo)
+    pub fn set_working_hours(&mut self, weekday: usize, open: NaiveTime, close: NaiveTime) {
+        self.working_hours[weekday] = if open < close { Some((open, close)) } else { None };
+    }
+
+    // Interpreta uma especificação como "seg-sex=08:00-18:00;sab=08:00-12:00"
+    pub fn set_working_hours_from_spec(&mut self, spec: &str) -> Result<(), String> {
+        for entry in spec.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let (days, hours) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expediente inválido: {}", entry))?;
+            let (open, close) = hours
+                .split_once('-')
+                .ok_or_else(|| format!("Horário inválido: {}", hours))?;
+            let open = NaiveTime::parse_from_str(open.trim(), "%H:%M")
+                .map_err(|_| format!("Horário inválido: {}", open))?;
+            let close = NaiveTime::parse_from_str(close.trim(), "%H:%M")
+                .map_err(|_| format!("Horário inválido: {}", close))?;
+
+            let (first, last) = match days.split_once('-') {
+                Some((first, last)) => (weekday_index(first)?, weekday_index(last)?),
+                None => {
+                    let day = weekday_index(days)?;
+                    (day, day)
+                }
+            };
+            if first > last {
+                return Err(format!("Intervalo de dias inválido: {}", days));
+            }
+            for weekday in first..=last {
+                self.set_working_hours(weekday, open, close);
+            }
+        }
+        Ok(())
+    }
+
+    // Importa feriados de um ficheiro iCalendar (RFC 5545).
+    // Cada VEVENT conta como feriado do DTSTART até à véspera do DTEND;
+    // eventos com RRULE:FREQ=YEARLY repetem-se todos os anos na mesma data,
+    // a partir do DTSTART e respeitando UNTIL e COUNT.
+    pub fn import_ics_holidays(&mut self, content: &str) -> Result<usize, String> {
+        let mut imported = 0;
+        let mut in_event = false;
+        let mut start: Option<NaiveDate> = None;
+        let mut end: Option<NaiveDate> = None;
+        let mut yearly: Option<YearlyBounds> = None;
+
+        for line in unfold_ics_lines(content) {
+            let (name, value) = match line.split_once(':') {
+                Some((name, value)) => (name, value.trim()),
+                None => continue,
+            };
+            // Remove parâmetros como ";VALUE=DATE" do nome da propriedade
+            let name = name.split(';').next().unwrap_or(name).to_ascii_uppercase();
+
+            match (name.as_str(), value) {
+                ("BEGIN", "VEVENT") => {
+                    in_event = true;
+                    start = None;
+                    end = None;
+                    yearly = None;
+                }
+                ("END", "VEVENT") if in_event => {
+                    in_event = false;
+                    let first = start.ok_or("VEVENT sem DTSTART")?;
+                    if let Some((until, count)) = yearly {
+                        self.yearly_holidays.push(YearlyHoliday { first, until, count });
+                        imported += 1;
+                        continue;
+                    }
+                    // DTEND é exclusivo; sem DTEND o evento dura um dia
+                    let last = end.map(|e| e - Duration::days(1)).unwrap_or(first).max(first);
+                    let mut day = first;
+                    while day <= last {
+                        self.holidays.insert(day);
+                        imported += 1;
+                        day += Duration::days(1);
+                    }
+                }
+                ("DTSTART", _) if in_event => start = Some(parse_ics_date(value)?),
+                ("DTEND", _) if in_event => end = Some(parse_ics_date(value)?),
+                ("RRULE", _) if in_event => yearly = parse_yearly_rrule(value)?,
+                _ => {}
+            }
+        }
+        Ok(imported)
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date) || self.yearly_holidays.iter().any(|holiday| holiday.matches(date))
+    }
+
+    // Expediente de uma data local (None em fins de semana sem expediente e feriados)
+    fn working_window(&self, date: NaiveDate) -> Option<(NaiveTime, NaiveTime)> {
+        if self.is_holiday(date) {
+            return None;
+        }
+        self.working_hours[date.weekday().num_days_from_monday() as usize]
+    }
+
+    fn to_local(&self, utc: NaiveDateTime) -> NaiveDateTime {
+        self.timezone.from_utc_datetime(&utc).naive_local()
+    }
+
+    fn to_utc(&self, local: NaiveDateTime) -> NaiveDateTime {
+        match self.timezone.from_local_datetime(&local).earliest() {
+            Some(dt) => dt.with_timezone(&Utc).naive_utc(),
+            // Hora inexistente (início do horário de verão): avança uma hora
+            None => self.to_utc(local + Duration::hours(1)),
+        }
+    }
+
+    // Soma `minutes` minutos de expediente a um instante UTC, devolvendo o prazo em UTC
+    pub fn add_business_minutes(&self, start_utc: NaiveDateTime, minutes: i64) -> NaiveDateTime {
+        if self.working_hours.iter().all(Option::is_none) {
+            return start_utc + Duration::minutes(minutes); // Sem expediente: relógio corrido
+        }
+
+        let mut remaining = Duration::minutes(minutes.max(0));
+        let mut cursor = self.to_local(start_utc);
+
+        for _ in 0..MAX_CALENDAR_DAYS {
+            let date = cursor.date();
+            if let Some((open, close)) = self.working_window(date) {
+                let open_at = date.and_time(open);
+                let close_at = date.and_time(close);
+                if cursor < open_at {
+                    cursor = open_at;
+                }
+                if cursor < close_at {
+                    let available = close_at - cursor;
+                    if remaining <= available {
+                        return self.to_utc(cursor + remaining);
+                    }
+                    remaining -= available;
+                }
+            }
+            cursor = (date + Duration::days(1)).and_time(NaiveTime::MIN);
+        }
+
+        self.to_utc(cursor + remaining)
+    }
+
+    // Minutos de expediente entre dois instantes UTC (0 se `to` <= `from`)
+    pub fn business_minutes_between(&self, from_utc: NaiveDateTime, to_utc: NaiveDateTime) -> i64 {
+        if to_utc <= from_utc {
+            return 0;
+        }
+        if self.working_hours.iter().all(Option::is_none) {
+            return (to_utc - from_utc).num_minutes();
+        }
+
+        let from = self.to_local(from_utc);
+        let to = self.to_local(to_utc);
+        let mut total = Duration::zero();
+        let mut date = from.date();
+
+        while date <= to.date() {
+            if let Some((open, close)) = self.working_window(date) {
+                let start = from.max(date.and_time(open));
+                let end = to.min(date.and_time(close));
+                if end > start {
+                    total += end - start;
+                }
+            }
+            date += Duration::days(1);
+        }
+        total.num_minutes()
+    }
+}
+
+// Converte "seg", "ter", ... (ou "mon", "tue", ...) no índice a partir de segunda-feira
+fn weekday_index(name: &str) -> Result<usize, String> {
+    match name.trim().to_lowercase().as_str() {
+        "seg" | "mon" => Ok(0),
+        "ter" | "tue" => Ok(1),
+        "qua" | "wed" => Ok(2),
+        "qui" | "thu" => Ok(3),
+        "sex" | "fri" => Ok(4),
+        "sab" | "sáb" | "sat" => Ok(5),
+        "dom" | "sun" => Ok(6),
+        other => Err(format!("Dia da semana inválido: {}", other)),
+    }
+}
+
+// Junta as linhas "dobradas" do iCalendar (continuação começa com espaço ou tab)
+fn unfold_ics_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.lines() {
+        let raw = raw.trim_end_matches('\r');
+        match (raw.strip_prefix(' ').or_else(|| raw.strip_prefix('\t')), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+// Lê uma RRULE; só as anuais são suportadas (as restantes são ignoradas)
+fn parse_yearly_rrule(value: &str) -> Result<Option<YearlyBounds>, String> {
+    let mut yearly = false;
+    let mut until = None;
+    let mut count = None;
+    for part in value.to_ascii_uppercase().split(';') {
+        match part.split_once('=') {
+            Some(("FREQ", freq)) => yearly = freq == "YEARLY",
+            Some(("UNTIL", date)) => until = Some(parse_ics_date(date)?),
+            Some(("COUNT", n)) => {
+                count = Some(n.parse::<i32>().map_err(|_| format!("COUNT inválido na RRULE: {}", value))?);
+            }
+            _ => {}
+        }
+    }
+    Ok(yearly.then_some((until, count)))
+}
+
+// Lê uma data iCalendar ("20261225" ou "20261225T000000Z"), usando só a parte da data
+fn parse_ics_date(value: &str) -> Result<NaiveDate, String> {
+    let date_part = value.get(..8).ok_or_else(|| format!("Data iCalendar inválida: {}", value))?;
+    NaiveDate::parse_from_str(date_part, "%Y%m%d")
+        .map_err(|_| format!("Data iCalendar inválida: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(timezone: &str) -> BusinessCalendar {
+        let mut calendar = BusinessCalendar::new(timezone.parse().unwrap());
+        calendar.set_working_hours_from_spec("seg-sex=08:00-18:00").unwrap();
+        calendar
+    }
+
+    fn utc(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn sexta_ao_fim_do_dia_continua_na_segunda() {
+        let calendar = calendar("America/Sao_Paulo");
+        // Sexta 2026-03-06 17:00 em São Paulo (UTC-3): falta 1h de expediente
+        let start = utc("2026-03-06 20:00");
+        assert_eq!(calendar.add_business_minutes(start, 120), utc("2026-03-09 12:00")); // segunda 09:00 local
+        assert_eq!(calendar.business_minutes_between(start, utc("2026-03-09 12:00")), 120);
+    }
+
+    #[test]
+    fn feriado_nao_conta() {
+        let mut calendar = calendar("America/Sao_Paulo");
+        calendar
+            .import_ics_holidays("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20260421\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")
+            .unwrap();
+        // Segunda 2026-04-20 17:00 local; terça 21 é feriado
+        let start = utc("2026-04-20 20:00");
+        assert_eq!(calendar.add_business_minutes(start, 120), utc("2026-04-22 12:00")); // quarta 09:00 local
+    }
+
+    #[test]
+    fn rrule_anual_respeita_dtstart_until_e_count() {
+        let mut calendar = calendar("America/Sao_Paulo");
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20201225\r\nRRULE:FREQ=YEARLY\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20200601\r\nRRULE:FREQ=YEARLY;UNTIL=20220601\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20200701\r\nRRULE:FREQ=YEARLY;COUNT=2\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        assert_eq!(calendar.import_ics_holidays(ics).unwrap(), 3);
+
+        let date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap();
+        assert!(calendar.is_holiday(date("2031-12-25")));
+        assert!(!calendar.is_holiday(date("2019-12-25")));
+        assert!(calendar.is_holiday(date("2022-06-01")));
+        assert!(!calendar.is_holiday(date("2023-06-01")));
+        assert!(calendar.is_holiday(date("2021-07-01")));
+        assert!(!calendar.is_holiday(date("2022-07-01")));
+    }
+
+    #[test]
+    fn mudanca_de_hora_usa_o_fuso_de_cada_dia() {
+        let calendar = calendar("America/New_York");
+        // Sexta 2026-03-06 17:00 EST (UTC-5); o horário de verão começa no domingo 08
+        let start = utc("2026-03-06 22:00");
+        let due = calendar.add_business_minutes(start, 120);
+        assert_eq!(due, utc("2026-03-09 13:00")); // segunda 09:00 EDT (UTC-4)
+        assert_eq!(calendar.business_minutes_between(start, due), 120);
+    }
+}
@@ -0,0 +1,371 @@
+use mail_parser::{Message, MessageParser, MimeHeaders};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::{
+    appstate::AppState,
+    attachment::{StoreError, UploadedFile},
+    comment::{NewTicketComment, TicketComment},
+    permission::{Permission, RolePermissions},
+    ticket::{Category, NewTicket, Ticket},
+    user::User,
+};
+use crate::services::notification_service::{notify_ticket_event, TicketEvent};
+use crate::upload::sniff_content_type;
+
+// Tamanho máximo do título de um ticket criado a partir do assunto do email
+const MAX_TITLE_CHARS: usize = 200;
+
+// Canal de entrada por email. Lê as mensagens de uma Maildir (alimentada por
+// fetchmail, getmail, offlineimap, mbsync ou pelo próprio servidor de correio):
+// cada ficheiro em new/ vira um ticket ou, se o assunto tiver "[Chamado #id]",
+// uma mensagem na conversa desse ticket. Configuração:
+// INBOUND_MAILDIR (sem ela o canal fica desligado), INBOUND_AUTHSERV_ID (obrigatório;
+// ver sender_authenticated), INBOUND_POLL_SECONDS (padrão 60) e INBOUND_DEFAULT_CATEGORY
+// (categoria dos tickets criados; padrão "Software").
+pub struct InboundEmailConfig {
+    maildir: PathBuf,
+    authserv_id: String,
+    poll_interval: Duration,
+    default_category: Category,
+}
+
+impl InboundEmailConfig {
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let maildir = match env::var("INBOUND_MAILDIR") {
+            Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => return Ok(None),
+        };
+        let authserv_id = match env::var("INBOUND_AUTHSERV_ID") {
+            Ok(value) if !value.trim().is_empty() => value.trim().to_string(),
+            _ => return Err("INBOUND_AUTHSERV_ID é obrigatório com INBOUND_MAILDIR".to_string()),
+        };
+        let poll_seconds = match env::var("INBOUND_POLL_SECONDS") {
+            Ok(value) => value.parse().map_err(|_| "INBOUND_POLL_SECONDS inválido".to_string())?,
+            Err(_) => 60,
+        };
+        let default_category = match env::var("INBOUND_DEFAULT_CATEGORY") {
+            Ok(value) => Category::from_str(&value)?,
+            Err(_) => Category::Software,
+        };
+
+        Ok(Some(Self {
+            maildir,
+            authserv_id,
+            poll_interval: Duration::from_secs(poll_seconds.max(1)),
+            default_category,
+        }))
+    }
+}
+
+// Resultado do processamento de uma mensagem
+enum Outcome {
+    Processed,
+    Rejected(String), // Mensagem válida que não pode virar ticket (remetente desconhecido, etc.)
+}
+
+// Inicia a leitura periódica da Maildir em segundo plano
+pub fn spawn_maildir_poller(state: Arc<AppState>, config: InboundEmailConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.poll_interval);
+        loop {
+            interval.tick().await;
+            // Uma falha de leitura é tentada de novo no próximo ciclo (em produção, logar o erro).
+            let _ = poll_maildir(state.clone(), &config).await;
+        }
+    });
+}
+
+// Processa todas as mensagens novas. Depois de tratada, cada mensagem passa para
+// cur/ com a flag "S" (vista); as rejeitadas recebem também a flag "F" para revisão manual.
+// Mensagens com erro de banco ficam em new/ e são tentadas de novo no próximo ciclo.
+async fn poll_maildir(state: Arc<AppState>, config: &InboundEmailConfig) -> Result<(), String> {
+    let new_dir = config.maildir.join("new");
+    let cur_dir = config.maildir.join("cur");
+    let mut entries = tokio::fs::read_dir(&new_dir).await.map_err(|e| e.to_string())?;
+
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let raw = tokio::fs::read(&path).await.map_err(|e| e.to_string())?;
+
+        let flags = match process_message(state.clone(), config, &raw).await {
+            Ok(Outcome::Processed) => "S",
+            Ok(Outcome::Rejected(_reason)) => "FS", // Em produção, logar o motivo.
+            Err(_) => {
+                // Em produção, logar o erro 'e' detalhado.
+                continue;
+            }
+        };
+        move_to_cur(&path, &cur_dir, flags).await?;
+    }
+    Ok(())
+}
+
+async fn move_to_cur(path: &Path, cur_dir: &Path, flags: &str) -> Result<(), String> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let unique = file_name.split(":2,").next().unwrap_or(file_name);
+    tokio::fs::rename(path, cur_dir.join(format!("{}:2,{}", unique, flags)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn process_message(
+    state: Arc<AppState>,
+    config: &InboundEmailConfig,
+    raw: &[u8],
+) -> Result<Outcome, StoreError> {
+    let Some(message) = MessageParser::default().parse(raw) else {
+        return Ok(Outcome::Rejected("mensagem malformada".to_string()));
+    };
+
+    // Respostas automáticas (férias, devoluções) não viram tickets nem mensagens
+    if message
+        .header_raw("Auto-Submitted")
+        .is_some_and(|value| !value.trim().eq_ignore_ascii_case("no"))
+    {
+        return Ok(Outcome::Rejected("resposta automática".to_string()));
+    }
+
+    let Some(sender) = message.from().and_then(|from| from.first()).and_then(|addr| addr.address()) else {
+        return Ok(Outcome::Rejected("remetente ausente".to_string()));
+    };
+    // O From: pode ser forjado por qualquer um; só vale se o domínio passou no DKIM/SPF/DMARC
+    let sender_domain = sender.rsplit_once('@').map(|(_, domain)| domain.trim()).unwrap_or_default();
+    let authentication_results = message
+        .headers_raw()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Authentication-Results"))
+        .map(|(_, value)| value);
+    if !sender_authenticated(authentication_results, &config.authserv_id, sender_domain) {
+        return Ok(Outcome::Rejected(format!("remetente não autenticado: {}", sender)));
+    }
+    let user = match User::get_user_by_email(sender.trim().to_string(), state.clone()).await {
+        Ok(user) if !user.user_active => {
+            return Ok(Outcome::Rejected(format!("conta desativada: {}", sender)));
+        }
+        // Só se associa a mensagem a uma conta cujo dono provou ter acesso ao email
+        Ok(user) if !user.user_email_verified => {
+            return Ok(Outcome::Rejected(format!("email por confirmar: {}", sender)));
+        }
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => {
+            return Ok(Outcome::Rejected(format!("remetente sem conta: {}", sender)));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let user_id = user.user_id.unwrap_or_default();
+    let permissions = RolePermissions::for_role(&state, &user.user_role).await?;
+
+    let subject = message.subject().unwrap_or("").trim().to_string();
+    let body = strip_quoted_reply(&message.body_text(0).unwrap_or_default());
+    let files = collect_attachments(&state, &message);
+
+    // Resposta a um ticket do próprio remetente. Por email ninguém exerce permissões
+    // da equipa: respostas a chamados de outros clientes abrem um chamado novo.
+    if let Some(ticket_id) = ticket_reference(&subject) {
+        if let Some(ticket) = Ticket::get_ticket_by_id(state.clone(), ticket_id).await? {
+            if ticket.ticket_client_id == user_id {
+                if body.is_empty() && files.is_empty() {
+                    return Ok(Outcome::Rejected("resposta vazia".to_string()));
+                }
+                append_reply(state, ticket_id, &user, body, &files).await?;
+                return Ok(Outcome::Processed);
+            }
+        }
+    }
+
+    if !permissions.contains(Permission::TicketCreate) {
+        return Ok(Outcome::Rejected(format!("sem permissão para abrir chamados: {}", sender)));
+    }
+    let new_ticket = NewTicket {
+        ticket_title: if subject.is_empty() {
+            "(sem assunto)".to_string()
+        } else {
+            subject.chars().take(MAX_TITLE_CHARS).collect()
+        },
+        ticket_description: if body.is_empty() { "(sem conteúdo)".to_string() } else { body },
+        ticket_client_id: user_id,
+        ticket_category: config.default_category,
+    };
+    // Se um anexo falhar, nada é gravado e a mensagem fica em new/ para o próximo ciclo
+    let (ticket, _) = Ticket::save_new_ticket_in_db(&new_ticket, state.clone(), &files).await?;
+    let ticket_id = ticket.ticket_id.unwrap_or_default();
+    notify_ticket_event(state, ticket_id, user_id, TicketEvent::Created);
+    Ok(Outcome::Processed)
+}
+
+// Acrescenta a resposta do cliente à conversa do ticket, como um comentário público
+async fn append_reply(
+    state: Arc<AppState>,
+    ticket_id: i32,
+    user: &User,
+    body: String,
+    files: &[UploadedFile],
+) -> Result<(), StoreError> {
+    let user_id = user.user_id.unwrap_or_default();
+    let new_comment = NewTicketComment {
+        comment_body: body,
+        comment_parent_id: None,
+        comment_is_internal: false,
+    };
+    let comment = TicketComment::save_new_comment_in_db(&new_comment, ticket_id, user_id, state.clone(), files).await?;
+
+    notify_ticket_event(state, ticket_id, user_id, TicketEvent::Commented {
+        author_name: comment.comment_author_name,
+        body: comment.comment_body,
+        internal: false,
+    });
+    Ok(())
+}
+
+// Anexos do email que respeitam os mesmos limites do envio pela aplicação;
+// os restantes são descartados.
+fn collect_attachments(state: &AppState, message: &Message) -> Vec<UploadedFile> {
+    let limits = &state.attachment_limits;
+    message
+        .attachments()
+        .filter_map(|part| {
+            let bytes = part.contents();
+            let content_type = sniff_content_type(bytes).to_string();
+            if !limits.is_allowed_mime(&content_type) || bytes.len() > limits.max_file_bytes {
+                return None;
+            }
+            Some(UploadedFile {
+                file_name: part.attachment_name().unwrap_or("anexo").to_string(),
+                content_type,
+                bytes: bytes.to_vec(),
+            })
+        })
+        .take(limits.max_files)
+        .collect()
+}
+
+// Indica se o servidor de correio de confiança (identificado pelo authserv-id dos cabeçalhos
+// Authentication-Results, RFC 8601) autenticou o domínio do From: com dkim=pass (header.d),
+// spf=pass (smtp.mailfrom) ou dmarc=pass (header.from). Cabeçalhos de outros servidores são
+// ignorados; o servidor tem de remover os que chegam de fora com o seu próprio authserv-id.
+fn sender_authenticated<'a>(
+    mut authentication_results: impl Iterator<Item = &'a str>,
+    authserv_id: &str,
+    sender_domain: &str,
+) -> bool {
+    if sender_domain.is_empty() {
+        return false;
+    }
+    authentication_results.any(|value| {
+        // Comentários entre parênteses não fazem parte dos resultados
+        let mut depth = 0;
+        let value: String = value
+            .chars()
+            .filter(|&c| match c {
+                '(' => {
+                    depth += 1;
+                    false
+                }
+                ')' => {
+                    depth = (depth - 1).max(0);
+                    false
+                }
+                _ => depth == 0,
+            })
+            .collect();
+
+        let mut parts = value.split(';');
+        let trusted = parts
+            .next()
+            .and_then(|id| id.split_whitespace().next())
+            .is_some_and(|id| id.eq_ignore_ascii_case(authserv_id));
+        trusted && parts.any(|result| {
+            let mut tokens = result.split_whitespace();
+            let Some((method, outcome)) = tokens.next().and_then(|t| t.split_once('=')) else {
+                return false;
+            };
+            let property = match method.to_ascii_lowercase().as_str() {
+                "dkim" => "header.d",
+                "spf" => "smtp.mailfrom",
+                "dmarc" => "header.from",
+                _ => return false,
+            };
+            outcome.eq_ignore_ascii_case("pass")
+                && tokens.filter_map(|t| t.split_once('=')).any(|(name, domain)| {
+                    let domain = domain.rsplit_once('@').map_or(domain, |(_, d)| d);
+                    name.eq_ignore_ascii_case(property) && domain.eq_ignore_ascii_case(sender_domain)
+                })
+        })
+    })
+}
+
+// Procura a referência "Chamado #123" (inserida no assunto das notificações)
+fn ticket_reference(subject: &str) -> Option<i32> {
+    let lower = subject.to_lowercase();
+    let start = lower.find("chamado #")? + "chamado #".len();
+    let digits: String = lower[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+// Remove o histórico citado de uma resposta ("> ..." e tudo depois de "Em ... escreveu:")
+fn strip_quoted_reply(text: &str) -> String {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let lower = trimmed.to_lowercase();
+        let is_reply_header = (lower.starts_with("em ") && lower.ends_with("escreveu:"))
+            || (lower.starts_with("on ") && lower.ends_with("wrote:"))
+            || lower.starts_with("-----original message-----")
+            || lower.starts_with("-----mensagem original-----");
+        if is_reply_header {
+            break;
+        }
+        if !trimmed.starts_with('>') {
+            lines.push(line);
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticket_reference_reads_the_notification_subject() {
+        assert_eq!(ticket_reference("Re: [Chamado #123] Impressora avariada"), Some(123));
+        assert_eq!(ticket_reference("RES: [chamado #7]"), Some(7));
+        assert_eq!(ticket_reference("Chamado # sem número"), None);
+        assert_eq!(ticket_reference("Pedido novo"), None);
+    }
+
+    #[test]
+    fn strip_quoted_reply_keeps_only_the_new_text() {
+        let text = "Já funciona, obrigado.\n\nEm 12/03/2026, Suporte escreveu:\n> Reinicie o computador.";
+        assert_eq!(strip_quoted_reply(text), "Já funciona, obrigado.");
+
+        let text = "Segue o log.\n> citação\nOn Mon, Support wrote:\nmais histórico";
+        assert_eq!(strip_quoted_reply(text), "Segue o log.");
+    }
+
+    #[test]
+    fn sender_authenticated_requires_a_trusted_pass_for_the_from_domain() {
+        let header = "mx.helpdesk.local; dkim=pass (assinatura válida) header.d=cliente.pt header.s=s1; spf=fail";
+        assert!(sender_authenticated([header].into_iter(), "mx.helpdesk.local", "cliente.pt"));
+        assert!(sender_authenticated(
+            ["mx.helpdesk.local; spf=pass smtp.mailfrom=ana@cliente.pt"].into_iter(),
+            "mx.helpdesk.local",
+            "cliente.pt",
+        ));
+        // Outro domínio, outro servidor ou resultado diferente de pass não contam
+        assert!(!sender_authenticated([header].into_iter(), "mx.helpdesk.local", "empresa.pt"));
+        assert!(!sender_authenticated([header].into_iter(), "mx.atacante.net", "cliente.pt"));
+        assert!(!sender_authenticated(
+            ["mx.helpdesk.local; dkim=fail header.d=cliente.pt"].into_iter(),
+            "mx.helpdesk.local",
+            "cliente.pt",
+        ));
+        assert!(!sender_authenticated(std::iter::empty(), "mx.helpdesk.local", "cliente.pt"));
+    }
+}
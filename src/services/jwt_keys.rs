@@ -0,0 +1,371 @@
+use std::{env, fs};
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    errors::{Error as JwtError, ErrorKind},
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters,
+        OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use rsa::{
+    pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey},
+    pkcs8::{der::pem, DecodePrivateKey, DecodePublicKey, ObjectIdentifier, PrivateKeyInfo, SubjectPublicKeyInfoRef},
+    traits::PublicKeyParts,
+    RsaPrivateKey, RsaPublicKey,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+const RSA_ENCRYPTION_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const MIN_RSA_BITS: usize = 2048;
+
+// Chaves que assinam e validam os access tokens.
+// Sem JWT_KEYS_DIR, os tokens são assinados em HS256 com JWT_SECRET (sem "kid").
+// Com JWT_KEYS_DIR, cada ficheiro <kid>.pem da pasta é uma chave RSA (RS256) ou Ed25519 (EdDSA):
+// as chaves privadas podem assinar, as públicas só validam, e todas são publicadas no JWKS.
+// Uma chave retirada da pasta deixa de validar, e os tokens sem "kid" são recusados, exceto
+// com JWT_ACCEPT_LEGACY_HS256=true (transição de HS256 sem encerrar as sessões).
+pub struct JwtKeys {
+    signing: SigningKey,
+    keys: Vec<VerificationKey>,
+    secret: Option<DecodingKey>, // JWT_SECRET, para os tokens sem "kid" (None = recusados)
+}
+
+struct SigningKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+struct VerificationKey {
+    jwk: Jwk,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+// Chave lida de um ficheiro PEM: parte pública (JWK) e, se for privada, a chave de assinatura
+struct PemKey {
+    jwk: Jwk,
+    algorithm: Algorithm,
+    encoding_key: Option<EncodingKey>,
+}
+
+impl JwtKeys {
+    pub fn from_env(secret: &str) -> Result<JwtKeys, String> {
+        let dir = match env::var("JWT_KEYS_DIR") {
+            Ok(dir) if !dir.trim().is_empty() => dir,
+            _ => return Ok(Self::legacy(secret)),
+        };
+        let accept_legacy = match env::var("JWT_ACCEPT_LEGACY_HS256") {
+            Ok(value) => match value.trim().to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                other => return Err(format!("JWT_ACCEPT_LEGACY_HS256 inválido: {}", other)),
+            },
+            Err(_) => false,
+        };
+        let signing_kid = env::var("JWT_SIGNING_KID").ok().filter(|kid| !kid.trim().is_empty());
+        Self::from_dir(secret, &dir, accept_legacy, signing_kid.as_deref())
+    }
+
+    // Só JWT_SECRET: assina e valida em HS256, sem "kid"
+    fn legacy(secret: &str) -> JwtKeys {
+        JwtKeys {
+            signing: SigningKey { kid: None, algorithm: Algorithm::HS256, key: EncodingKey::from_secret(secret.as_bytes()) },
+            keys: Vec::new(),
+            secret: Some(DecodingKey::from_secret(secret.as_bytes())),
+        }
+    }
+
+    fn from_dir(secret: &str, dir: &str, accept_legacy: bool, signing_kid: Option<&str>) -> Result<JwtKeys, String> {
+        let secret = accept_legacy.then(|| DecodingKey::from_secret(secret.as_bytes()));
+
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .map_err(|e| format!("Não foi possível ler JWT_KEYS_DIR ({}): {}", dir, e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "pem"))
+            .collect();
+        paths.sort();
+
+        let mut keys = Vec::new();
+        let mut signers = Vec::new();
+        for path in paths {
+            let kid = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+            let bytes = fs::read(&path).map_err(|e| format!("Não foi possível ler {}: {}", path.display(), e))?;
+            let key = load_pem(&kid, &bytes).map_err(|e| format!("Chave JWT inválida em {}: {}", path.display(), e))?;
+            if let Some(encoding_key) = key.encoding_key {
+                signers.push(SigningKey { kid: Some(kid), algorithm: key.algorithm, key: encoding_key });
+            }
+            let decoding_key = DecodingKey::from_jwk(&key.jwk).map_err(|e| format!("Chave JWT inválida em {}: {}", path.display(), e))?;
+            keys.push(VerificationKey { jwk: key.jwk, algorithm: key.algorithm, key: decoding_key });
+        }
+        if keys.is_empty() {
+            return Err(format!("JWT_KEYS_DIR ({}) não tem chaves .pem", dir));
+        }
+
+        // Com várias chaves privadas (ex.: a nova já publicada e a antiga ainda em uso), JWT_SIGNING_KID escolhe a que assina
+        let signing = match signing_kid {
+            Some(kid) => {
+                let kid = kid.trim();
+                let position = signers.iter().position(|signer| signer.kid.as_deref() == Some(kid));
+                match position {
+                    Some(position) => signers.swap_remove(position),
+                    None => return Err(format!("JWT_SIGNING_KID ({}) não corresponde a uma chave privada em JWT_KEYS_DIR", kid)),
+                }
+            }
+            None if signers.len() == 1 => signers.remove(0),
+            None if signers.is_empty() => return Err("JWT_KEYS_DIR precisa ter uma chave privada para assinar os tokens".to_string()),
+            None => return Err("Há várias chaves privadas em JWT_KEYS_DIR: indique a que assina em JWT_SIGNING_KID".to_string()),
+        };
+        Ok(JwtKeys { signing, keys, secret })
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> String {
+        let header = Header { kid: self.signing.kid.clone(), ..Header::new(self.signing.algorithm) };
+        encode(&header, claims, &self.signing.key).expect("Falha ao gerar token JWT")
+    }
+
+    // Valida a assinatura (com a chave do "kid" e só no algoritmo dessa chave) e a validade do token
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> Result<T, JwtError> {
+        let header = decode_header(token)?;
+        let (key, algorithm) = match header.kid.as_deref() {
+            None => match &self.secret {
+                Some(secret) => (secret, Algorithm::HS256),
+                None => return Err(ErrorKind::InvalidSignature.into()),
+            },
+            Some(kid) => match self.keys.iter().find(|key| key.jwk.common.key_id.as_deref() == Some(kid)) {
+                Some(key) => (&key.key, key.algorithm),
+                None => return Err(ErrorKind::InvalidSignature.into()),
+            },
+        };
+        decode::<T>(token, key, &Validation::new(algorithm)).map(|data| data.claims)
+    }
+
+    // Chaves públicas para outros serviços validarem os tokens (GET /.well-known/jwks.json)
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet { keys: self.keys.iter().map(|key| key.jwk.clone()).collect() }
+    }
+}
+
+// Lê uma chave RSA ou Ed25519, privada (PKCS#8 ou PKCS#1) ou pública (SPKI ou PKCS#1)
+fn load_pem(kid: &str, bytes: &[u8]) -> Result<PemKey, String> {
+    let (label, der) = pem::decode_vec(bytes).map_err(|e| e.to_string())?;
+    match label {
+        "PRIVATE KEY" => {
+            let info = PrivateKeyInfo::try_from(der.as_slice()).map_err(|e| e.to_string())?;
+            if info.algorithm.oid == RSA_ENCRYPTION_OID {
+                let key = RsaPrivateKey::from_pkcs8_der(&der).map_err(|e| e.to_string())?;
+                rsa_key(kid, &RsaPublicKey::from(&key), Some(bytes))
+            } else if info.algorithm.oid == ED25519_OID {
+                let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der).map_err(|e| e.to_string())?;
+                ed25519_key(kid, pair.public_key().as_ref(), Some(bytes))
+            } else {
+                Err(format!("algoritmo não suportado ({}); use RSA ou Ed25519", info.algorithm.oid))
+            }
+        }
+        "RSA PRIVATE KEY" => {
+            let key = RsaPrivateKey::from_pkcs1_der(&der).map_err(|e| e.to_string())?;
+            rsa_key(kid, &RsaPublicKey::from(&key), Some(bytes))
+        }
+        "PUBLIC KEY" => {
+            let info = SubjectPublicKeyInfoRef::try_from(der.as_slice()).map_err(|e| e.to_string())?;
+            if info.algorithm.oid == RSA_ENCRYPTION_OID {
+                rsa_key(kid, &RsaPublicKey::from_public_key_der(&der).map_err(|e| e.to_string())?, None)
+            } else if info.algorithm.oid == ED25519_OID {
+                ed25519_key(kid, info.subject_public_key.raw_bytes(), None)
+            } else {
+                Err(format!("algoritmo não suportado ({}); use RSA ou Ed25519", info.algorithm.oid))
+            }
+        }
+        "RSA PUBLIC KEY" => rsa_key(kid, &RsaPublicKey::from_pkcs1_der(&der).map_err(|e| e.to_string())?, None),
+        other => Err(format!("tipo de PEM não suportado: {}", other)),
+    }
+}
+
+fn common_parameters(kid: &str, algorithm: KeyAlgorithm) -> CommonParameters {
+    CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(algorithm),
+        key_id: Some(kid.to_string()),
+        ..Default::default()
+    }
+}
+
+fn rsa_key(kid: &str, public: &RsaPublicKey, private_pem: Option<&[u8]>) -> Result<PemKey, String> {
+    if public.size() * 8 < MIN_RSA_BITS {
+        return Err(format!("as chaves RSA precisam ter pelo menos {} bits", MIN_RSA_BITS));
+    }
+    let encoding_key = private_pem.map(EncodingKey::from_rsa_pem).transpose().map_err(|e| e.to_string())?;
+    let jwk = Jwk {
+        common: common_parameters(kid, KeyAlgorithm::RS256),
+        algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: BASE64URL_NOPAD.encode(&public.n().to_bytes_be()),
+            e: BASE64URL_NOPAD.encode(&public.e().to_bytes_be()),
+        }),
+    };
+    Ok(PemKey { jwk, algorithm: Algorithm::RS256, encoding_key })
+}
+
+fn ed25519_key(kid: &str, public: &[u8], private_pem: Option<&[u8]>) -> Result<PemKey, String> {
+    if public.len() != 32 {
+        return Err("chave pública Ed25519 inválida".to_string());
+    }
+    let encoding_key = private_pem.map(EncodingKey::from_ed_pem).transpose().map_err(|e| e.to_string())?;
+    let jwk = Jwk {
+        common: common_parameters(kid, KeyAlgorithm::EdDSA),
+        algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: BASE64URL_NOPAD.encode(public),
+        }),
+    };
+    Ok(PemKey { jwk, algorithm: Algorithm::EdDSA, encoding_key })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::auth_service::Claims;
+    use rsa::pkcs8::der::pem::LineEnding;
+    use std::path::{Path, PathBuf};
+
+    const SECRET: &str = "segredo-de-teste";
+
+    fn claims() -> Claims {
+        Claims {
+            sub: "42".to_string(),
+            role: "Cliente".to_string(),
+            exp: (chrono::Utc::now() + chrono::Duration::minutes(15)).timestamp() as usize,
+            sid: "sessao".to_string(),
+        }
+    }
+
+    // Pasta temporária com chaves Ed25519: (kid, privada?) para cada ficheiro <kid>.pem
+    fn keys_dir(name: &str, keys: &[(&str, bool)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("helpdesk-jwt-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (kid, private) in keys {
+            // Chave determinística por kid, para a mesma chave aparecer em várias pastas
+            let seed: [u8; 32] = ring::digest::digest(&ring::digest::SHA256, kid.as_bytes()).as_ref().try_into().unwrap();
+            let pem = if *private {
+                // PKCS#8 v1 de uma chave Ed25519 (RFC 8410): prefixo fixo seguido da semente
+                let mut der = vec![0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20];
+                der.extend_from_slice(&seed);
+                pem::encode_string("PRIVATE KEY", LineEnding::LF, &der).unwrap()
+            } else {
+                let pair = Ed25519KeyPair::from_seed_unchecked(&seed).unwrap();
+                let mut der = vec![0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+                der.extend_from_slice(pair.public_key().as_ref());
+                pem::encode_string("PUBLIC KEY", LineEnding::LF, &der).unwrap()
+            };
+            fs::write(dir.join(format!("{}.pem", kid)), pem).unwrap();
+        }
+        dir
+    }
+
+    fn load(dir: &Path, accept_legacy: bool, signing_kid: Option<&str>) -> JwtKeys {
+        JwtKeys::from_dir(SECRET, dir.to_str().unwrap(), accept_legacy, signing_kid).unwrap()
+    }
+
+    #[test]
+    fn signs_and_verifies_with_the_active_kid() {
+        let dir = keys_dir("ativa", &[("2026-01", true), ("2026-02", true)]);
+        let keys = load(&dir, false, Some("2026-02"));
+
+        let token = keys.sign(&claims());
+        assert_eq!(decode_header(&token).unwrap().kid.as_deref(), Some("2026-02"));
+        assert_eq!(decode_header(&token).unwrap().alg, Algorithm::EdDSA);
+        let verified: Claims = keys.verify(&token).unwrap();
+        assert_eq!(verified.sub, "42");
+
+        // Um token adulterado deixa de validar
+        let signature_start = token.rfind('.').unwrap() + 1;
+        let replacement = if token[signature_start..].starts_with('A') { "B" } else { "A" };
+        let tampered = format!("{}{}{}", &token[..signature_start], replacement, &token[signature_start + 1..]);
+        assert!(keys.verify::<Claims>(&tampered).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn verifies_tokens_of_a_retired_kid_while_it_is_published() {
+        let old_dir = keys_dir("antiga", &[("2026-01", true)]);
+        let token = load(&old_dir, false, None).sign(&claims());
+
+        // A chave antiga fica só com a parte pública; a nova passa a assinar
+        let dir = keys_dir("rotacao", &[("2026-01", false), ("2026-02", true)]);
+        let keys = load(&dir, false, None);
+        assert!(keys.verify::<Claims>(&token).is_ok());
+        assert_eq!(decode_header(&keys.sign(&claims())).unwrap().kid.as_deref(), Some("2026-02"));
+
+        // Retirada da pasta, deixa de validar
+        let dir_without_old = keys_dir("retirada", &[("2026-02", true)]);
+        assert!(load(&dir_without_old, false, None).verify::<Claims>(&token).is_err());
+
+        for dir in [old_dir, dir, dir_without_old] {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_kids() {
+        let other_dir = keys_dir("outra", &[("desconhecida", true)]);
+        let token = load(&other_dir, false, None).sign(&claims());
+
+        let dir = keys_dir("conhecida", &[("2026-02", true)]);
+        let error = load(&dir, true, None).verify::<Claims>(&token).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidSignature);
+
+        for dir in [other_dir, dir] {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn kid_less_tokens_need_the_legacy_opt_in() {
+        let legacy_token = JwtKeys::legacy(SECRET).sign(&claims());
+        assert_eq!(decode_header(&legacy_token).unwrap().kid, None);
+        assert!(JwtKeys::legacy(SECRET).verify::<Claims>(&legacy_token).is_ok());
+
+        let dir = keys_dir("legado", &[("2026-02", true)]);
+        assert!(load(&dir, false, None).verify::<Claims>(&legacy_token).is_err());
+        assert!(load(&dir, true, None).verify::<Claims>(&legacy_token).is_ok());
+
+        // Mesmo aceitando HS256, o segredo tem de ser o de JWT_SECRET
+        let forged = encode(&Header::new(Algorithm::HS256), &claims(), &EncodingKey::from_secret(b"outro")).unwrap();
+        assert!(load(&dir, true, None).verify::<Claims>(&forged).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn needs_a_signing_kid_when_several_private_keys_exist() {
+        let dir = keys_dir("varias", &[("2026-01", true), ("2026-02", true)]);
+        assert!(JwtKeys::from_dir(SECRET, dir.to_str().unwrap(), false, None).is_err());
+        assert!(JwtKeys::from_dir(SECRET, dir.to_str().unwrap(), false, Some("2027-01")).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn jwks_publishes_only_public_parameters() {
+        let dir = keys_dir("jwks", &[("2026-01", false), ("2026-02", true)]);
+        let jwks = serde_json::to_value(load(&dir, false, None).jwks()).unwrap();
+
+        let keys = jwks["keys"].as_array().unwrap();
+        assert_eq!(keys.len(), 2);
+        for (key, kid) in keys.iter().zip(["2026-01", "2026-02"]) {
+            assert_eq!(key["kid"], kid);
+            assert_eq!(key["kty"], "OKP");
+            assert_eq!(key["crv"], "Ed25519");
+            assert_eq!(key["alg"], "EdDSA");
+            assert_eq!(key["use"], "sig");
+            assert_eq!(BASE64URL_NOPAD.decode(key["x"].as_str().unwrap().as_bytes()).unwrap().len(), 32);
+            assert!(key.get("d").is_none());
+        }
+        assert!(JwtKeys::legacy(SECRET).jwks().keys.is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
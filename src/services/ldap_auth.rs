@@ -0,0 +1,146 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use ldap3::{ldap_escape, LdapConnAsync, LdapConnSettings, LdapError, Scope, SearchEntry};
+use crate::models::{appstate::AppState, user::User};
+use crate::services::auth_provider::{provision_external_user, AuthProvider, ExternalIdentity, RoleMapping};
+
+// Código LDAP de credenciais inválidas no bind
+const LDAP_INVALID_CREDENTIALS: u32 = 49;
+const LDAP_TIMEOUT_SECONDS: u64 = 5;
+
+// Filtro de pesquisa com o login escapado (RFC 4515): "*", "(", ")", "\" e NUL não
+// podem alterar o filtro configurado
+fn user_filter_for(user_filter: &str, login: &str) -> String {
+    user_filter.replace("{login}", &ldap_escape(login))
+}
+
+// Autenticação num diretório LDAP / Active Directory: procura a entrada do utilizador com
+// a conta de serviço (ou anonimamente), confirma a senha com um bind como essa entrada e
+// associa a conta local pelo email, mapeando os grupos (memberOf) para o papel.
+pub struct LdapAuthProvider {
+    url: String,
+    starttls: bool,
+    bind_dn: Option<String>,
+    bind_password: String,
+    base_dn: String,
+    user_filter: String,
+    email_attribute: String,
+    name_attribute: String,
+    group_attribute: String,
+    roles: RoleMapping,
+}
+
+impl LdapAuthProvider {
+    pub fn from_env() -> Result<Self, String> {
+        let var = |name: &str| env::var(name).map_err(|_| format!("{} precisa estar configurado!", name));
+        let user_filter = env::var("LDAP_USER_FILTER")
+            .unwrap_or_else(|_| "(|(mail={login})(userPrincipalName={login})(sAMAccountName={login}))".to_string());
+        if !user_filter.contains("{login}") {
+            return Err("LDAP_USER_FILTER precisa conter {login}".to_string());
+        }
+
+        Ok(Self {
+            url: var("LDAP_URL")?,
+            starttls: env::var("LDAP_STARTTLS").map(|v| v.trim().eq_ignore_ascii_case("true")).unwrap_or(false),
+            bind_dn: env::var("LDAP_BIND_DN").ok().filter(|dn| !dn.is_empty()),
+            bind_password: env::var("LDAP_BIND_PASSWORD").unwrap_or_default(),
+            base_dn: var("LDAP_BASE_DN")?,
+            user_filter,
+            email_attribute: env::var("LDAP_EMAIL_ATTRIBUTE").unwrap_or_else(|_| "mail".to_string()),
+            name_attribute: env::var("LDAP_NAME_ATTRIBUTE").unwrap_or_else(|_| "displayName".to_string()),
+            group_attribute: env::var("LDAP_GROUP_ATTRIBUTE").unwrap_or_else(|_| "memberOf".to_string()),
+            // Os DNs dos grupos têm vírgulas, por isso as listas usam ";"
+            roles: RoleMapping::from_env("LDAP", ';'),
+        })
+    }
+
+    // Confirma as credenciais no diretório e devolve a identidade do utilizador
+    async fn verify(&self, login: &str, password: &str) -> Result<Option<ExternalIdentity>, LdapError> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(Duration::from_secs(LDAP_TIMEOUT_SECONDS))
+            .set_starttls(self.starttls);
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.url).await?;
+        ldap3::drive!(conn);
+
+        if let Some(bind_dn) = &self.bind_dn {
+            ldap.simple_bind(bind_dn, &self.bind_password).await?.success()?;
+        }
+
+        let filter = user_filter_for(&self.user_filter, login);
+        let attributes = [self.email_attribute.as_str(), self.name_attribute.as_str(), "cn", self.group_attribute.as_str()];
+        let (entries, _) = ldap
+            .with_timeout(Duration::from_secs(LDAP_TIMEOUT_SECONDS))
+            .search(&self.base_dn, Scope::Subtree, &filter, attributes)
+            .await?
+            .success()?;
+        // Um login que corresponda a várias entradas é ambíguo e não é aceite
+        let mut entries = entries.into_iter();
+        let (Some(entry), None) = (entries.next(), entries.next()) else {
+            let _ = ldap.unbind().await;
+            return Ok(None);
+        };
+        let entry = SearchEntry::construct(entry);
+
+        // Confirma a senha com um bind como o próprio utilizador
+        let bind = ldap.simple_bind(&entry.dn, password).await?;
+        let _ = ldap.unbind().await;
+        if bind.rc == LDAP_INVALID_CREDENTIALS {
+            return Ok(None);
+        }
+        bind.success()?;
+
+        let first = |attribute: &str| entry.attrs.get(attribute).and_then(|values| values.first()).cloned();
+        let Some(email) = first(&self.email_attribute).map(|email| email.trim().to_lowercase()) else {
+            return Ok(None); // Sem email não há como associar a conta local
+        };
+        let name = first(&self.name_attribute)
+            .or_else(|| first("cn"))
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default().to_string());
+        let groups = entry.attrs.get(&self.group_attribute).cloned().unwrap_or_default();
+
+        Ok(Some(ExternalIdentity { role: self.roles.role_for(&groups), email, name }))
+    }
+}
+
+#[async_trait]
+impl AuthProvider for LdapAuthProvider {
+    fn name(&self) -> &'static str {
+        "ldap"
+    }
+
+    async fn authenticate(&self, state: &Arc<AppState>, login: &str, password: &str) -> Result<Option<User>, String> {
+        // Um bind com senha vazia é um bind anónimo e seria aceite pelo servidor
+        if login.trim().is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+        match self.verify(login.trim(), password).await.map_err(|e| e.to_string())? {
+            Some(identity) => provision_external_user(state, &identity).await.map(Some).map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_filter_substitutes_every_login_placeholder() {
+        assert_eq!(
+            user_filter_for("(|(mail={login})(sAMAccountName={login}))", "ana@empresa.pt"),
+            "(|(mail=ana@empresa.pt)(sAMAccountName=ana@empresa.pt))"
+        );
+    }
+
+    #[test]
+    fn user_filter_escapes_filter_syntax_in_the_login() {
+        assert_eq!(user_filter_for("(mail={login})", "*"), "(mail=\\2a)");
+        assert_eq!(
+            user_filter_for("(mail={login})", "x)(|(uid=*))"),
+            "(mail=x\\29\\28|\\28uid=\\2a\\29\\29)"
+        );
+        assert_eq!(user_filter_for("(uid={login})", "a\\b\0"), "(uid=a\\5cb\\00)");
+    }
+}
@@ -1 +1,12 @@
-pub mod auth_service;
\ No newline at end of file
+pub mod auth_service;
+pub mod business_calendar;
+pub mod attachment_storage;
+pub mod notification_service;
+pub mod inbound_email;
+pub mod totp;
+pub mod registration_policy;
+pub mod oidc;
+pub mod auth_provider;
+pub mod ldap_auth;
+pub mod jwt_keys;
+pub mod session_cookie;
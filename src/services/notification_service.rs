@@ -0,0 +1,436 @@
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::env;
+use std::sync::Arc;
+use tera::{Context, Tera};
+
+use crate::models::{
+    appstate::AppState,
+    email_verification::VERIFICATION_TOKEN_HOURS,
+    invitation::{Invitation, INVITATION_DAYS},
+    password_reset::RESET_TOKEN_MINUTES,
+    ticket::{Priority, Ticket},
+    user::User,
+};
+
+// Eventos de um ticket que geram email
+#[derive(Debug, Clone)]
+pub enum TicketEvent {
+    Created,
+    PriorityChanged { old: Priority, new: Priority },
+    Commented { author_name: String, body: String, internal: bool },
+    Closed,
+}
+
+impl TicketEvent {
+    // Template Tera (em src/templates/emails) usado para o corpo do email
+    fn template(&self) -> &'static str {
+        match self {
+            TicketEvent::Created => "emails/ticket_created.html",
+            TicketEvent::PriorityChanged { .. } => "emails/ticket_priority_changed.html",
+            TicketEvent::Commented { .. } => "emails/ticket_commented.html",
+            TicketEvent::Closed => "emails/ticket_closed.html",
+        }
+    }
+
+    // O assunto leva sempre "[Chamado #id]" para identificar o ticket nas respostas
+    fn subject(&self, ticket: &Ticket) -> String {
+        let ticket_id = ticket.ticket_id.unwrap_or_default();
+        match self {
+            TicketEvent::Created => format!("[Chamado #{}] Chamado aberto: {}", ticket_id, ticket.ticket_title),
+            TicketEvent::PriorityChanged { new, .. } => {
+                format!("[Chamado #{}] Prioridade alterada para {}", ticket_id, new)
+            }
+            TicketEvent::Commented { .. } => format!("[Chamado #{}] Nova mensagem: {}", ticket_id, ticket.ticket_title),
+            TicketEvent::Closed => format!("[Chamado #{}] Chamado fechado: {}", ticket_id, ticket.ticket_title),
+        }
+    }
+
+    fn insert_into(&self, context: &mut Context) {
+        match self {
+            TicketEvent::PriorityChanged { old, new } => {
+                context.insert("old_priority", &old.to_string());
+                context.insert("new_priority", &new.to_string());
+            }
+            TicketEvent::Commented { author_name, body, internal } => {
+                context.insert("comment_author", author_name);
+                context.insert("comment_body", body);
+                context.insert("comment_is_internal", internal);
+            }
+            TicketEvent::Created | TicketEvent::Closed => {}
+        }
+    }
+}
+
+// Envio de emails por SMTP. Configurado pelas variáveis de ambiente:
+// SMTP_HOST (sem ela as notificações ficam desligadas), SMTP_PORT,
+// SMTP_TLS ("none" — padrão, adequado a um servidor SMTP local de testes —, "starttls" ou "tls"),
+// SMTP_USERNAME / SMTP_PASSWORD (opcionais), MAIL_FROM e APP_BASE_URL (links nos emails).
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    base_url: String,
+}
+
+impl Mailer {
+    pub fn from_env() -> Result<Option<Mailer>, String> {
+        let host = match env::var("SMTP_HOST") {
+            Ok(host) if !host.trim().is_empty() => host,
+            _ => return Ok(None),
+        };
+        let tls = env::var("SMTP_TLS").unwrap_or_else(|_| "none".to_string()).to_lowercase();
+
+        let mut builder = match tls.as_str() {
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host).port(25),
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host).map_err(|e| e.to_string())?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host).map_err(|e| e.to_string())?,
+            other => return Err(format!("SMTP_TLS inválido: {}", other)),
+        };
+        if let Ok(port) = env::var("SMTP_PORT") {
+            builder = builder.port(port.parse().map_err(|_| "SMTP_PORT inválido".to_string())?);
+        }
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        let from = env::var("MAIL_FROM")
+            .unwrap_or_else(|_| "Help Desk <noreply@localhost>".to_string())
+            .parse()
+            .map_err(|_| "MAIL_FROM inválido".to_string())?;
+        let base_url = env::var("APP_BASE_URL")
+            .unwrap_or_else(|_| "http://localhost:8080".to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        Ok(Some(Mailer { transport: builder.build(), from, base_url }))
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // Envia um email HTML para um destinatário
+    pub async fn send(&self, to: &str, subject: &str, html: String) -> Result<(), String> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse().map_err(|_| format!("Endereço inválido: {}", to))?)
+            .subject(subject)
+            .header(ContentType::TEXT_HTML)
+            .body(html)
+            .map_err(|e| e.to_string())?;
+
+        self.transport.send(message).await.map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+// Dispara, em segundo plano, os emails de um evento do ticket.
+// Destinatários: o cliente que abriu o chamado e o técnico responsável, exceto quem
+// causou o evento (na abertura o cliente recebe a confirmação). Notas internas só vão à equipa.
+// Falhas de envio nunca afetam o pedido que originou o evento.
+pub fn notify_ticket_event(state: Arc<AppState>, ticket_id: i32, actor_id: i32, event: TicketEvent) {
+    if state.mailer.is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        // Uma falha no envio não afeta o pedido já concluído (em produção, logar o erro).
+        let _ = send_ticket_event(state, ticket_id, actor_id, event).await;
+    });
+}
+
+async fn send_ticket_event(
+    state: Arc<AppState>,
+    ticket_id: i32,
+    actor_id: i32,
+    event: TicketEvent,
+) -> Result<(), String> {
+    let Some(mailer) = state.mailer.as_ref() else {
+        return Ok(());
+    };
+    let ticket = Ticket::get_ticket_by_id(state.clone(), ticket_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "chamado não encontrado".to_string())?;
+
+    let mut emails = Vec::new();
+    for user_id in ticket_event_recipients(&ticket, actor_id, &event) {
+        let user = User::get_user_by_id(user_id, state.clone()).await.map_err(|e| e.to_string())?;
+        if let Some(user) = user {
+            emails.push(user.user_email);
+        }
+    }
+    deliver_ticket_event(mailer, &state.tera, &ticket, &event, &emails).await
+}
+
+// IDs dos destinatários de um evento (regras descritas em notify_ticket_event)
+fn ticket_event_recipients(ticket: &Ticket, actor_id: i32, event: &TicketEvent) -> Vec<i32> {
+    let internal = matches!(event, TicketEvent::Commented { internal: true, .. });
+    let mut recipient_ids = Vec::new();
+    if !internal && (ticket.ticket_client_id != actor_id || matches!(event, TicketEvent::Created)) {
+        recipient_ids.push(ticket.ticket_client_id);
+    }
+    if let Some(assignee_id) = ticket.ticket_assignee_id {
+        if assignee_id != actor_id && !recipient_ids.contains(&assignee_id) {
+            recipient_ids.push(assignee_id);
+        }
+    }
+    recipient_ids
+}
+
+// Renderiza o email do evento e envia-o a cada endereço
+async fn deliver_ticket_event(
+    mailer: &Mailer,
+    tera: &Tera,
+    ticket: &Ticket,
+    event: &TicketEvent,
+    emails: &[String],
+) -> Result<(), String> {
+    let mut context = Context::new();
+    context.insert("ticket", ticket);
+    context.insert("app_url", mailer.base_url());
+    event.insert_into(&mut context);
+    let html = tera.render(event.template(), &context).map_err(|e| e.to_string())?;
+    let subject = event.subject(ticket);
+
+    for email in emails {
+        mailer.send(email, &subject, html.clone()).await?;
+    }
+    Ok(())
+}
+
+// Envia, em segundo plano, o link de redefinição de senha. Em segundo plano também para
+// que o tempo de resposta não revele se o email pedido tem conta.
+pub fn send_password_reset(state: Arc<AppState>, user: User, token: String) {
+    tokio::spawn(async move {
+        let Some(mailer) = state.mailer.as_ref() else {
+            return;
+        };
+        let mut context = Context::new();
+        context.insert("user_name", &user.user_name);
+        context.insert("app_url", mailer.base_url());
+        context.insert("reset_url", &format!("{}/password/reset?token={}", mailer.base_url(), token));
+        context.insert("valid_minutes", &RESET_TOKEN_MINUTES);
+
+        // Em produção, logar o erro de envio.
+        let _ = match state.tera.render("emails/password_reset.html", &context) {
+            Ok(html) => mailer.send(&user.user_email, "Redefinição de senha", html).await,
+            Err(e) => Err(e.to_string()),
+        };
+    });
+}
+
+// Envia, em segundo plano, o link de confirmação do email de uma conta nova.
+pub fn send_email_verification(state: Arc<AppState>, user: User, token: String) {
+    tokio::spawn(async move {
+        let Some(mailer) = state.mailer.as_ref() else {
+            return;
+        };
+        let mut context = Context::new();
+        context.insert("user_name", &user.user_name);
+        context.insert("app_url", mailer.base_url());
+        context.insert("verify_url", &format!("{}/register/verify?token={}", mailer.base_url(), token));
+        context.insert("valid_hours", &VERIFICATION_TOKEN_HOURS);
+
+        // Em produção, logar o erro de envio.
+        let _ = match state.tera.render("emails/email_verification.html", &context) {
+            Ok(html) => mailer.send(&user.user_email, "Confirme o seu e-mail", html).await,
+            Err(e) => Err(e.to_string()),
+        };
+    });
+}
+
+// Envia, em segundo plano, o link de um convite para a equipa.
+pub fn send_invitation(state: Arc<AppState>, invitation: &Invitation, invite_path: String) {
+    let email = invitation.invitation_email.clone();
+    let role = invitation.invitation_role.to_string();
+    let invited_by = invitation.invitation_invited_by_name.clone();
+    tokio::spawn(async move {
+        let Some(mailer) = state.mailer.as_ref() else {
+            return;
+        };
+        let mut context = Context::new();
+        context.insert("app_url", mailer.base_url());
+        context.insert("invite_url", &format!("{}{}", mailer.base_url(), invite_path));
+        context.insert("role", &role);
+        context.insert("invited_by", &invited_by);
+        context.insert("valid_days", &INVITATION_DAYS);
+
+        // Em produção, logar o erro de envio.
+        let _ = match state.tera.render("emails/staff_invitation.html", &context) {
+            Ok(html) => mailer.send(&email, "Convite para a equipa do Help Desk", html).await,
+            Err(e) => Err(e.to_string()),
+        };
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ticket::{Category, StatusTicket};
+    use chrono::NaiveDate;
+    use std::sync::Mutex;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    const CLIENT_ID: i32 = 10;
+    const ASSIGNEE_ID: i32 = 20;
+
+    // Mensagem recebida pelo servidor SMTP de teste: destinatários (RCPT TO) e conteúdo (DATA)
+    #[derive(Debug, Clone)]
+    struct Received {
+        recipients: Vec<String>,
+        data: String,
+    }
+
+    type Inbox = Arc<Mutex<Vec<Received>>>;
+
+    // Servidor SMTP mínimo em 127.0.0.1 que aceita tudo e guarda as mensagens
+    async fn smtp_sink() -> (u16, Inbox) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let inbox: Inbox = Arc::default();
+        let shared = inbox.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let inbox = shared.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = stream.into_split();
+                    let mut lines = BufReader::new(read).lines();
+                    let mut recipients = Vec::new();
+                    write.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let command = line.to_uppercase();
+                        let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+                            b"250 sink\r\n"
+                        } else if let Some(address) = command.strip_prefix("RCPT TO:") {
+                            recipients.push(address.trim_matches(['<', '>', ' ']).to_lowercase());
+                            b"250 OK\r\n"
+                        } else if command == "DATA" {
+                            write.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await.unwrap();
+                            let mut data = String::new();
+                            while let Ok(Some(line)) = lines.next_line().await {
+                                if line == "." {
+                                    break;
+                                }
+                                data.push_str(&line);
+                                data.push('\n');
+                            }
+                            let recipients = std::mem::take(&mut recipients);
+                            inbox.lock().unwrap().push(Received { recipients, data });
+                            b"250 OK\r\n"
+                        } else if command == "QUIT" {
+                            let _ = write.write_all(b"221 Bye\r\n").await;
+                            break;
+                        } else {
+                            b"250 OK\r\n" // MAIL FROM, RSET, NOOP
+                        };
+                        write.write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+        (port, inbox)
+    }
+
+    fn mailer(port: u16) -> Mailer {
+        Mailer {
+            transport: AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1").port(port).build(),
+            from: "Help Desk <noreply@localhost>".parse().unwrap(),
+            base_url: "http://helpdesk.test".to_string(),
+        }
+    }
+
+    fn ticket() -> Ticket {
+        Ticket {
+            ticket_id: Some(7),
+            ticket_status: StatusTicket::Novo,
+            ticket_priority: Priority::Média,
+            ticket_description: "Não imprime".to_string(),
+            ticket_title: "Impressora avariada".to_string(),
+            ticket_client_id: CLIENT_ID,
+            ticket_category: Category::Hardware,
+            ticket_client_name: "Ana".to_string(),
+            ticket_assignee_id: Some(ASSIGNEE_ID),
+            ticket_assignee_name: Some("Rui".to_string()),
+            ticket_created_at: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(9, 0, 0).unwrap(),
+            ticket_response_due: None,
+            ticket_resolution_due: None,
+            ticket_first_response_at: None,
+            ticket_resolved_at: None,
+        }
+    }
+
+    fn email_of(user_id: i32) -> String {
+        match user_id {
+            CLIENT_ID => "ana@cliente.test".to_string(),
+            ASSIGNEE_ID => "rui@suporte.test".to_string(),
+            _ => unreachable!(),
+        }
+    }
+
+    // Envia o evento como send_ticket_event, com os emails dos utilizadores de teste
+    async fn deliver(port: u16, actor_id: i32, event: TicketEvent) {
+        let tera = Tera::new("./src/templates/**/*.html").unwrap();
+        let ticket = ticket();
+        let emails: Vec<String> = ticket_event_recipients(&ticket, actor_id, &event).into_iter().map(email_of).collect();
+        deliver_ticket_event(&mailer(port), &tera, &ticket, &event, &emails).await.unwrap();
+    }
+
+    fn summary(inbox: &Inbox) -> Vec<(Vec<String>, String)> {
+        inbox
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|message| {
+                let subject = message.data.lines().find_map(|line| line.strip_prefix("Subject: ")).unwrap_or_default();
+                (message.recipients.clone(), subject.to_string())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn ticket_created_mails_the_client_and_the_assignee() {
+        let (port, inbox) = smtp_sink().await;
+        deliver(port, CLIENT_ID, TicketEvent::Created).await;
+
+        let subject = "[Chamado #7] Chamado aberto: Impressora avariada".to_string();
+        assert_eq!(
+            summary(&inbox),
+            [
+                (vec!["ana@cliente.test".to_string()], subject.clone()),
+                (vec!["rui@suporte.test".to_string()], subject),
+            ]
+        );
+        assert!(inbox.lock().unwrap()[0].data.contains("Content-Type: text/html"));
+    }
+
+    #[tokio::test]
+    async fn ticket_closed_skips_whoever_closed_it() {
+        let (port, inbox) = smtp_sink().await;
+        deliver(port, ASSIGNEE_ID, TicketEvent::Closed).await;
+        deliver(port, CLIENT_ID, TicketEvent::Closed).await;
+
+        let subject = "[Chamado #7] Chamado fechado: Impressora avariada".to_string();
+        assert_eq!(
+            summary(&inbox),
+            [
+                (vec!["ana@cliente.test".to_string()], subject.clone()),
+                (vec!["rui@suporte.test".to_string()], subject),
+            ]
+        );
+    }
+
+    #[test]
+    fn internal_notes_only_reach_the_team() {
+        let note = TicketEvent::Commented { author_name: "Rui".to_string(), body: "nota".to_string(), internal: true };
+        assert_eq!(ticket_event_recipients(&ticket(), ASSIGNEE_ID, &note), Vec::<i32>::new());
+        assert_eq!(ticket_event_recipients(&ticket(), 30, &note), [ASSIGNEE_ID]);
+
+        let reply = TicketEvent::Commented { author_name: "Ana".to_string(), body: "olá".to_string(), internal: false };
+        assert_eq!(ticket_event_recipients(&ticket(), CLIENT_ID, &reply), [ASSIGNEE_ID]);
+        let unassigned = Ticket { ticket_assignee_id: None, ..ticket() };
+        assert_eq!(ticket_event_recipients(&unassigned, CLIENT_ID, &TicketEvent::Created), [CLIENT_ID]);
+    }
+}
@@ -0,0 +1,316 @@
+use std::env;
+use chrono::{Duration, Utc};
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::{
+    decode, decode_header, encode, jwk::JwkSet, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::{OnceCell, RwLock};
+use crate::models::user::UserRole;
+use crate::services::auth_provider::{ExternalIdentity, RoleMapping};
+
+// Tempo para concluir o login no fornecedor de identidade
+const PENDING_LOGIN_MINUTES: i64 = 10;
+const PENDING_LOGIN_PURPOSE: &str = "oidc";
+
+// Cliente OpenID Connect (fluxo authorization code com PKCE) do login por SSO.
+// A configuração do fornecedor (discovery) e as chaves públicas (JWKS) são obtidas
+// no primeiro login e ficam em memória; as chaves são recarregadas quando aparece
+// um "kid" desconhecido (rotação no fornecedor).
+pub struct OidcClient {
+    issuer: String,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_url: String,
+    scopes: String,
+    groups_claim: String,
+    roles: RoleMapping,
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+    jwks: RwLock<Option<JwkSet>>,
+}
+
+// Campos usados do documento /.well-known/openid-configuration
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenEndpointResponse {
+    id_token: String,
+}
+
+// Login em curso: guardado num cookie assinado até ao regresso do fornecedor
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    exp: usize,
+    purpose: String,
+}
+
+impl PendingLogin {
+    fn new() -> Self {
+        PendingLogin {
+            state: random_token(),
+            nonce: random_token(),
+            code_verifier: random_token(),
+            exp: (Utc::now() + Duration::minutes(PENDING_LOGIN_MINUTES)).timestamp() as usize,
+            purpose: PENDING_LOGIN_PURPOSE.to_string(),
+        }
+    }
+
+    // Compara o state devolvido pelo fornecedor com o guardado no cookie
+    pub fn matches_state(&self, state: Option<&str>) -> bool {
+        state == Some(self.state.as_str())
+    }
+
+    // O ID token tem de trazer o nonce deste login (impede a reutilização de tokens de outro login)
+    fn matches_nonce(&self, claims: &Value) -> bool {
+        claims.get("nonce").and_then(Value::as_str) == Some(self.nonce.as_str())
+    }
+}
+
+impl OidcClient {
+    pub fn from_env() -> Result<Option<OidcClient>, String> {
+        let issuer = match env::var("OIDC_ISSUER") {
+            Ok(issuer) if !issuer.trim().is_empty() => issuer.trim().trim_end_matches('/').to_string(),
+            _ => return Ok(None),
+        };
+        let client_id = env::var("OIDC_CLIENT_ID").map_err(|_| "OIDC_CLIENT_ID precisa estar configurado!".to_string())?;
+        let client_secret = env::var("OIDC_CLIENT_SECRET").ok().filter(|secret| !secret.is_empty());
+        let redirect_url = match env::var("OIDC_REDIRECT_URL") {
+            Ok(url) => url,
+            Err(_) => format!(
+                "{}/login/oidc/callback",
+                env::var("APP_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string()).trim_end_matches('/')
+            ),
+        };
+        Ok(Some(OidcClient {
+            issuer,
+            client_id,
+            client_secret,
+            redirect_url,
+            scopes: env::var("OIDC_SCOPES").unwrap_or_else(|_| "openid email profile".to_string()),
+            groups_claim: env::var("OIDC_GROUPS_CLAIM").unwrap_or_else(|_| "groups".to_string()),
+            roles: RoleMapping::from_env("OIDC", ','),
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+            jwks: RwLock::new(None),
+        }))
+    }
+
+    // O cookie do login em curso só deve seguir por HTTPS quando a aplicação está em HTTPS
+    pub fn uses_https(&self) -> bool {
+        self.redirect_url.starts_with("https://")
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata, String> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!("{}/.well-known/openid-configuration", self.issuer);
+                let metadata: ProviderMetadata = self.get_json(&url).await?;
+                if metadata.issuer.trim_end_matches('/') != self.issuer {
+                    return Err(format!("O issuer do fornecedor ({}) não corresponde a OIDC_ISSUER", metadata.issuer));
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    async fn get_json<T: for<'de> Deserialize<'de>>(&self, url: &str) -> Result<T, String> {
+        let response = self.http.get(url).send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("{} respondeu {}", url, response.status()));
+        }
+        let body = response.bytes().await.map_err(|e| e.to_string())?;
+        serde_json::from_slice(&body).map_err(|e| format!("Resposta inválida de {}: {}", url, e))
+    }
+
+    // Prepara o redirecionamento para o fornecedor: URL de autorização e dados a guardar no cookie
+    pub async fn authorization_request(&self) -> Result<(String, PendingLogin), String> {
+        let metadata = self.metadata().await?;
+        let pending = PendingLogin::new();
+        let code_challenge = BASE64URL_NOPAD.encode(&Sha256::digest(pending.code_verifier.as_bytes()));
+
+        let mut url = reqwest::Url::parse(&metadata.authorization_endpoint).map_err(|e| e.to_string())?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_url)
+            .append_pair("scope", &self.scopes)
+            .append_pair("state", &pending.state)
+            .append_pair("nonce", &pending.nonce)
+            .append_pair("code_challenge", &code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        Ok((url.to_string(), pending))
+    }
+
+    // Troca o código de autorização pelo ID token e valida-o (assinatura, issuer, audiência, validade e nonce)
+    pub async fn exchange_code(&self, code: &str, pending: &PendingLogin) -> Result<ExternalIdentity, String> {
+        let metadata = self.metadata().await?;
+        let mut params = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_url.as_str()),
+            ("code_verifier", pending.code_verifier.as_str()),
+        ];
+        let mut request = self.http.post(&metadata.token_endpoint);
+        match &self.client_secret {
+            Some(secret) => request = request.basic_auth(&self.client_id, Some(secret)),
+            None => params.push(("client_id", self.client_id.as_str())), // Cliente público: só PKCE
+        }
+        let response = request.form(&params).send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("O fornecedor recusou o código ({}): {}", status, body));
+        }
+        let body = response.bytes().await.map_err(|e| e.to_string())?;
+        let tokens: TokenEndpointResponse =
+            serde_json::from_slice(&body).map_err(|e| format!("Resposta inválida do token endpoint: {}", e))?;
+
+        let claims = self.validate_id_token(&tokens.id_token).await?;
+        if !pending.matches_nonce(&claims) {
+            return Err("Nonce do ID token inválido".to_string());
+        }
+        self.identity_from_claims(&claims)
+    }
+
+    async fn validate_id_token(&self, id_token: &str) -> Result<Value, String> {
+        let header = decode_header(id_token).map_err(|e| e.to_string())?;
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err("ID token assinado com algoritmo simétrico".to_string());
+        }
+        let key = self.decoding_key(header.kid.as_deref()).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer, &format!("{}/", self.issuer)]);
+        validation.set_audience(&[&self.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        decode::<Value>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| format!("ID token inválido: {}", e))
+    }
+
+    // Chave pública do fornecedor para o "kid" do token; recarrega o JWKS se o kid não for conhecido
+    async fn decoding_key(&self, kid: Option<&str>) -> Result<DecodingKey, String> {
+        if let Some(key) = find_key(self.jwks.read().await.as_ref(), kid)? {
+            return Ok(key);
+        }
+        let jwks: JwkSet = self.get_json(&self.metadata().await?.jwks_uri).await?;
+        let key = find_key(Some(&jwks), kid)?;
+        *self.jwks.write().await = Some(jwks);
+        key.ok_or_else(|| "Chave do ID token não encontrada no JWKS do fornecedor".to_string())
+    }
+
+    fn identity_from_claims(&self, claims: &Value) -> Result<ExternalIdentity, String> {
+        let email = claims
+            .get("email")
+            .and_then(Value::as_str)
+            .map(|email| email.trim().to_lowercase())
+            .filter(|email| email.contains('@'))
+            .ok_or_else(|| "O ID token não tem o email do utilizador (scope \"email\")".to_string())?;
+        // A conta é associada pelo email, por isso o fornecedor tem de o ter verificado
+        if claims.get("email_verified").and_then(Value::as_bool) == Some(false) {
+            return Err("O email não está verificado no fornecedor de identidade".to_string());
+        }
+        let name = ["name", "preferred_username"]
+            .iter()
+            .find_map(|claim| claims.get(*claim).and_then(Value::as_str))
+            .map(str::to_string)
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default().to_string());
+
+        Ok(ExternalIdentity { role: self.role_from_groups(claims), email, name })
+    }
+
+    // Papel pelos grupos do token; sem a claim dos grupos (scope não pedido) o papel não muda
+    fn role_from_groups(&self, claims: &Value) -> Option<UserRole> {
+        let groups: Vec<&str> = match claims.get(&self.groups_claim)? {
+            Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+            Value::String(value) => vec![value.as_str()],
+            _ => return None,
+        };
+        self.roles.role_for(&groups)
+    }
+}
+
+fn find_key(jwks: Option<&JwkSet>, kid: Option<&str>) -> Result<Option<DecodingKey>, String> {
+    let Some(jwks) = jwks else {
+        return Ok(None);
+    };
+    let jwk = match kid {
+        Some(kid) => jwks.find(kid),
+        // Sem "kid", só é possível escolher se o fornecedor publicar uma única chave
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    };
+    jwk.map(|jwk| DecodingKey::from_jwk(jwk).map_err(|e| e.to_string())).transpose()
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+// Assina os dados do login em curso para o cookie (HttpOnly) que acompanha o redirecionamento
+pub fn pending_login_encode(secret_key: &str, pending: &PendingLogin) -> String {
+    encode(&Header::default(), pending, &EncodingKey::from_secret(secret_key.as_ref()))
+        .expect("Falha ao gerar token JWT")
+}
+
+pub fn pending_login_decode(secret_key: &str, value: &str) -> Option<PendingLogin> {
+    let pending = decode::<PendingLogin>(value, &DecodingKey::from_secret(secret_key.as_ref()), &Validation::default())
+        .ok()?
+        .claims;
+    (pending.purpose == PENDING_LOGIN_PURPOSE).then_some(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SECRET: &str = "segredo-de-teste";
+
+    #[test]
+    fn pending_login_round_trips_through_the_cookie() {
+        let pending = PendingLogin::new();
+        let decoded = pending_login_decode(SECRET, &pending_login_encode(SECRET, &pending)).expect("cookie válido");
+        assert_eq!(decoded.state, pending.state);
+        assert_eq!(decoded.nonce, pending.nonce);
+        assert_eq!(decoded.code_verifier, pending.code_verifier);
+
+        assert!(pending_login_decode("outro-segredo", &pending_login_encode(SECRET, &pending)).is_none());
+    }
+
+    #[test]
+    fn pending_login_rejects_other_purposes_and_expired_cookies() {
+        let other_purpose = PendingLogin { purpose: "mfa".to_string(), ..PendingLogin::new() };
+        assert!(pending_login_decode(SECRET, &pending_login_encode(SECRET, &other_purpose)).is_none());
+
+        let expired = PendingLogin { exp: (Utc::now() - Duration::minutes(5)).timestamp() as usize, ..PendingLogin::new() };
+        assert!(pending_login_decode(SECRET, &pending_login_encode(SECRET, &expired)).is_none());
+    }
+
+    #[test]
+    fn state_and_nonce_must_match_the_pending_login() {
+        let pending = PendingLogin::new();
+        assert!(pending.matches_state(Some(&pending.state)));
+        assert!(!pending.matches_state(Some("outro")));
+        assert!(!pending.matches_state(None));
+
+        assert!(pending.matches_nonce(&json!({ "nonce": pending.nonce })));
+        assert!(!pending.matches_nonce(&json!({ "nonce": "outro" })));
+        assert!(!pending.matches_nonce(&json!({ "sub": "123" })));
+    }
+}
@@ -0,0 +1,151 @@
+use std::env;
+use std::sync::OnceLock;
+
+// Domínios de email aceites no registo público (/register). As contas criadas por
+// administradores ou por convite não passam por aqui.
+//
+// Cada entrada é um domínio exato ("empresa.pt") ou, com "*.", os seus subdomínios
+// ("*.empresa.pt"). A lista de bloqueio prevalece; sem lista de permissão, qualquer
+// domínio que não esteja bloqueado é aceite.
+pub struct EmailDomainPolicy {
+    allowed: Vec<String>,
+    denied: Vec<String>,
+}
+
+impl EmailDomainPolicy {
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            allowed: domain_list("REGISTRATION_ALLOWED_DOMAINS")?,
+            denied: domain_list("REGISTRATION_DENIED_DOMAINS")?,
+        })
+    }
+
+    // Verifica o domínio de um email; o erro é a mensagem a mostrar no formulário de registo
+    pub fn check(&self, email: &str) -> Result<(), String> {
+        let Some((_, domain)) = email.trim().rsplit_once('@') else {
+            return Err("Indique um e-mail válido.".to_string());
+        };
+        // "empresa.pt." é o mesmo domínio que "empresa.pt" (ponto final do DNS)
+        let domain = domain.trim_end_matches('.').to_lowercase();
+
+        if self.denied.iter().any(|pattern| matches_domain(pattern, &domain)) {
+            return Err(format!("Não é possível registar-se com e-mails do domínio {}.", domain));
+        }
+        if !self.allowed.is_empty() && !self.allowed.iter().any(|pattern| matches_domain(pattern, &domain)) {
+            return Err(format!(
+                "O registo está reservado a e-mails dos domínios: {}.",
+                self.allowed.join(", ")
+            ));
+        }
+        Ok(())
+    }
+}
+
+// REGISTRATION_SKIP_EMAIL_VERIFICATION=true: sem SMTP configurado, as contas do registo
+// público ficam confirmadas de imediato (apenas para desenvolvimento). Sem esta opção e
+// sem SMTP, o registo público é recusado, pois não há como confirmar o email.
+pub fn skip_email_verification() -> bool {
+    static SKIP: OnceLock<bool> = OnceLock::new();
+    *SKIP.get_or_init(|| {
+        env::var("REGISTRATION_SKIP_EMAIL_VERIFICATION").is_ok_and(|value| value.trim().eq_ignore_ascii_case("true"))
+    })
+}
+
+// Lê uma lista de domínios separados por vírgulas (variável ausente ou vazia = lista vazia)
+fn domain_list(var: &str) -> Result<Vec<String>, String> {
+    parse_domain_list(var, &env::var(var).unwrap_or_default())
+}
+
+fn parse_domain_list(var: &str, value: &str) -> Result<Vec<String>, String> {
+    value
+        .split(',')
+        .map(|entry| entry.trim().to_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let domain = entry.strip_prefix("*.").unwrap_or(&entry);
+            if domain.is_empty() || domain.contains(['@', '*', ' ']) || !domain.contains('.') {
+                return Err(format!("{} inválido: {}", var, entry));
+            }
+            Ok(entry)
+        })
+        .collect()
+}
+
+fn matches_domain(pattern: &str, domain: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(parent) => domain
+            .strip_suffix(parent)
+            .is_some_and(|prefix| prefix.ends_with('.')),
+        None => pattern == domain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowed: &str, denied: &str) -> EmailDomainPolicy {
+        EmailDomainPolicy {
+            allowed: parse_domain_list("REGISTRATION_ALLOWED_DOMAINS", allowed).unwrap(),
+            denied: parse_domain_list("REGISTRATION_DENIED_DOMAINS", denied).unwrap(),
+        }
+    }
+
+    #[test]
+    fn empty_policy_accepts_any_domain() {
+        let policy = policy("", "");
+        assert!(policy.check("ana@empresa.pt").is_ok());
+        assert!(policy.check("ana@gmail.com").is_ok());
+        assert!(policy.check("sem-arroba").is_err());
+    }
+
+    #[test]
+    fn exact_domains_and_case_folding() {
+        let policy = policy("Empresa.PT", "");
+        assert!(policy.check("ana@empresa.pt").is_ok());
+        assert!(policy.check("  Ana@EMPRESA.pt ").is_ok());
+        assert!(policy.check("ana@empresa.pt.").is_ok());
+        assert!(policy.check("ana@lisboa.empresa.pt").is_err());
+        assert!(policy.check("ana@outraempresa.pt").is_err());
+        assert!(policy.check("ana@empresa.pt.evil.com").is_err());
+        // Só conta o último "@"
+        assert!(policy.check("empresa.pt@evil.com").is_err());
+        assert!(policy.check("\"ana@empresa.pt\"@evil.com").is_err());
+    }
+
+    #[test]
+    fn wildcards_match_only_subdomains() {
+        let policy = policy("*.example.com", "");
+        assert!(policy.check("ana@mail.example.com").is_ok());
+        assert!(policy.check("ana@a.b.Example.com").is_ok());
+        assert!(policy.check("ana@example.com").is_err());
+        assert!(policy.check("ana@badexample.com").is_err());
+        assert!(policy.check("ana@example.com.evil").is_err());
+        assert!(policy.check("ana@mail.example.com.evil").is_err());
+    }
+
+    #[test]
+    fn denied_domains_win_over_allowed() {
+        let both = policy("*.empresa.pt, empresa.pt", "externos.empresa.pt, mailinator.com");
+        assert!(both.check("ana@empresa.pt").is_ok());
+        assert!(both.check("ana@lisboa.empresa.pt").is_ok());
+        assert!(both.check("ana@externos.empresa.pt").is_err());
+        assert!(both.check("ana@EXTERNOS.empresa.pt.").is_err());
+
+        let denied_only = policy("", "*.mailinator.com, mailinator.com");
+        assert!(denied_only.check("ana@mailinator.com").is_err());
+        assert!(denied_only.check("ana@x.mailinator.com").is_err());
+        assert!(denied_only.check("ana@gmail.com").is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        for entry in ["*", "*.", "localhost", "ana@empresa.pt", "*.*.empresa.pt", "em presa.pt"] {
+            assert!(parse_domain_list("REGISTRATION_ALLOWED_DOMAINS", entry).is_err(), "{}", entry);
+        }
+        assert_eq!(
+            parse_domain_list("REGISTRATION_ALLOWED_DOMAINS", " A.pt ,, *.B.pt ").unwrap(),
+            ["a.pt", "*.b.pt"]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::env;
+use axum::http::{header, HeaderMap, HeaderValue};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use crate::services::auth_service::Claims;
+
+// Cookie (HttpOnly) com a sessão do navegador, usado pelas páginas e pelas chamadas à API
+pub const SESSION_COOKIE: &str = "helpdesk_session";
+// Cookie legível pelo JavaScript com o token CSRF, repetido no cabeçalho X-CSRF-Token
+pub const CSRF_COOKIE: &str = "helpdesk_csrf";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+// Validade do cookie; a sessão pode terminar antes (logout, troca de senha, conta desativada)
+const SESSION_COOKIE_DAYS: i64 = 7;
+const SESSION_PURPOSE: &str = "session";
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Conteúdo assinado do cookie. O ID da sessão vai em "session" (e não em "sid") para que
+// o valor do cookie não seja aceite como access token no cabeçalho Authorization.
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionCookieClaims {
+    sub: String,
+    role: String,
+    session: String,
+    exp: usize,
+    purpose: String,
+}
+
+// Atributos dos cookies da sessão. Secure quando a aplicação é servida por HTTPS
+// (APP_BASE_URL, ou SESSION_COOKIE_SECURE="true"/"false" para o forçar).
+pub struct SessionCookies {
+    secure: bool,
+}
+
+impl SessionCookies {
+    pub fn from_env() -> Result<SessionCookies, String> {
+        let secure = match env::var("SESSION_COOKIE_SECURE") {
+            Ok(value) => match value.trim().to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                other => return Err(format!("SESSION_COOKIE_SECURE inválido: {}", other)),
+            },
+            Err(_) => env::var("APP_BASE_URL").is_ok_and(|url| url.trim().starts_with("https://")),
+        };
+        Ok(SessionCookies { secure })
+    }
+
+    // Cabeçalhos Set-Cookie que abrem a sessão do navegador para a sessão das claims
+    pub fn issue(&self, secret_key: &str, claims: &Claims) -> [HeaderValue; 2] {
+        let max_age = SESSION_COOKIE_DAYS * 24 * 60 * 60;
+        let session = SessionCookieClaims {
+            sub: claims.sub.clone(),
+            role: claims.role.clone(),
+            session: claims.sid.clone(),
+            exp: (Utc::now() + Duration::days(SESSION_COOKIE_DAYS)).timestamp() as usize,
+            purpose: SESSION_PURPOSE.to_string(),
+        };
+        let value = encode(&Header::default(), &session, &EncodingKey::from_secret(secret_key.as_ref()))
+            .expect("Falha ao gerar token JWT");
+        [
+            self.cookie(SESSION_COOKIE, &value, max_age, true),
+            self.cookie(CSRF_COOKIE, &csrf_token(secret_key, &claims.sid), max_age, false),
+        ]
+    }
+
+    // Cabeçalhos Set-Cookie que apagam a sessão do navegador (logout)
+    pub fn clear(&self) -> [HeaderValue; 2] {
+        [self.cookie(SESSION_COOKIE, "", 0, true), self.cookie(CSRF_COOKIE, "", 0, false)]
+    }
+
+    fn cookie(&self, name: &str, value: &str, max_age: i64, http_only: bool) -> HeaderValue {
+        let cookie = format!(
+            "{}={}; Path=/; Max-Age={}; SameSite=Lax{}{}",
+            name,
+            value,
+            max_age,
+            if http_only { "; HttpOnly" } else { "" },
+            if self.secure { "; Secure" } else { "" },
+        );
+        HeaderValue::from_str(&cookie).expect("Cookie inválido")
+    }
+}
+
+// Lê o valor de um cookie do pedido
+pub fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+// Valida o cookie da sessão e devolve as claims da sessão (a revogação é verificada por quem chama)
+pub fn session_cookie_decode(secret_key: &str, value: &str) -> Option<Claims> {
+    let session = decode::<SessionCookieClaims>(value, &DecodingKey::from_secret(secret_key.as_ref()), &Validation::default())
+        .ok()?
+        .claims;
+    (session.purpose == SESSION_PURPOSE).then_some(Claims {
+        sub: session.sub,
+        role: session.role,
+        exp: session.exp,
+        sid: session.session,
+    })
+}
+
+// Token CSRF da sessão: HMAC do ID da sessão, por isso não precisa de ser guardado no servidor
+pub fn csrf_token(secret_key: &str, session_id: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(format!("csrf:{}", session_id).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+pub fn csrf_token_valid(secret_key: &str, session_id: &str, token: &str) -> bool {
+    let Ok(token) = hex::decode(token.trim()) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(format!("csrf:{}", session_id).as_bytes());
+    mac.verify_slice(&token).is_ok() // Comparação em tempo constante
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "segredo-de-teste";
+
+    fn claims(sid: &str) -> Claims {
+        Claims {
+            sub: "42".to_string(),
+            role: "Cliente".to_string(),
+            exp: (Utc::now() + Duration::minutes(15)).timestamp() as usize,
+            sid: sid.to_string(),
+        }
+    }
+
+    #[test]
+    fn csrf_token_is_bound_to_the_session() {
+        let token = csrf_token(SECRET, "sessao-a");
+        assert!(csrf_token_valid(SECRET, "sessao-a", &token));
+        assert!(csrf_token_valid(SECRET, "sessao-a", &format!(" {} ", token)));
+
+        assert!(!csrf_token_valid(SECRET, "sessao-b", &token));
+        assert!(!csrf_token_valid("outro-segredo", "sessao-a", &token));
+        assert!(!csrf_token_valid(SECRET, "sessao-a", ""));
+        assert!(!csrf_token_valid(SECRET, "sessao-a", "nao-e-hex"));
+        assert!(!csrf_token_valid(SECRET, "sessao-a", &token[..32]));
+    }
+
+    #[test]
+    fn cookie_value_reads_every_cookie_header() {
+        let mut headers = HeaderMap::new();
+        headers.append(header::COOKIE, HeaderValue::from_static("tema=escuro; helpdesk_csrf=abc123"));
+        headers.append(header::COOKIE, HeaderValue::from_static("helpdesk_session=x.y.z;outro=1"));
+
+        assert_eq!(cookie_value(&headers, CSRF_COOKIE), Some("abc123"));
+        assert_eq!(cookie_value(&headers, SESSION_COOKIE), Some("x.y.z"));
+        assert_eq!(cookie_value(&headers, "outro"), Some("1"));
+        assert_eq!(cookie_value(&headers, "helpdesk"), None);
+        assert_eq!(cookie_value(&HeaderMap::new(), SESSION_COOKIE), None);
+    }
+
+    #[test]
+    fn session_cookie_round_trip() {
+        let cookies = SessionCookies { secure: true };
+        let [session, csrf] = cookies.issue(SECRET, &claims("sessao-a"));
+        let session = session.to_str().unwrap();
+        assert!(session.contains("; HttpOnly") && session.ends_with("; Secure"));
+        assert!(!csrf.to_str().unwrap().contains("HttpOnly"));
+
+        let value = session.split(';').next().unwrap().trim_start_matches("helpdesk_session=");
+        let decoded = session_cookie_decode(SECRET, value).unwrap();
+        assert_eq!((decoded.sub.as_str(), decoded.sid.as_str()), ("42", "sessao-a"));
+
+        assert!(session_cookie_decode("outro-segredo", value).is_none());
+        assert!(session_cookie_decode(SECRET, "lixo").is_none());
+    }
+
+    // Um access token (sem purpose = "session") não serve como cookie da sessão
+    #[test]
+    fn session_cookie_rejects_access_tokens() {
+        let token = encode(&Header::default(), &claims("sessao-a"), &EncodingKey::from_secret(SECRET.as_ref())).unwrap();
+        assert!(session_cookie_decode(SECRET, &token).is_none());
+    }
+}
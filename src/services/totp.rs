@@ -0,0 +1,167 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use qrcode::{render::svg, QrCode};
+use rand::RngCore;
+use sha1::Sha1;
+use std::env;
+
+// Parâmetros TOTP (RFC 6238) compatíveis com as aplicações autenticadoras comuns
+const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+// Passos aceites antes e depois do atual, para tolerar relógios ligeiramente desacertados
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// Configuração da verificação em duas etapas: TOTP_ISSUER (nome mostrado na aplicação
+// autenticadora; padrão "Help Desk") e REQUIRE_ADMIN_2FA ("true" obriga os administradores
+// a configurá-la no próximo login).
+pub struct TotpConfig {
+    pub issuer: String,
+    pub require_for_admins: bool,
+}
+
+impl TotpConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let require_for_admins = match env::var("REQUIRE_ADMIN_2FA") {
+            Ok(value) => match value.trim().to_lowercase().as_str() {
+                "true" | "1" | "sim" => true,
+                "false" | "0" | "nao" | "não" | "" => false,
+                other => return Err(format!("REQUIRE_ADMIN_2FA inválido: {}", other)),
+            },
+            Err(_) => false,
+        };
+        Ok(Self {
+            issuer: env::var("TOTP_ISSUER").unwrap_or_else(|_| "Help Desk".to_string()),
+            require_for_admins,
+        })
+    }
+}
+
+// Gera um segredo novo (160 bits, em base32 sem padding)
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+// URI "otpauth://" lida pelas aplicações autenticadoras (normalmente através do QR code)
+pub fn provisioning_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let issuer = utf8_percent_encode(issuer, NON_ALPHANUMERIC).to_string();
+    let account = utf8_percent_encode(account, NON_ALPHANUMERIC).to_string();
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer, account, secret, issuer, DIGITS, STEP_SECONDS
+    )
+}
+
+// QR code (SVG) da URI de configuração
+pub fn qr_svg(uri: &str) -> Result<String, String> {
+    let code = QrCode::new(uri.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+// Valida um código de 6 dígitos. Devolve o passo (contador de 30 s) correspondente,
+// para que o chamador recuse a reutilização do mesmo código.
+pub fn verify_code(secret: &str, code: &str, unix_time: i64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let current_step = unix_time / STEP_SECONDS;
+
+    (current_step - ALLOWED_DRIFT_STEPS..=current_step + ALLOWED_DRIFT_STEPS)
+        .find(|&step| format!("{:0width$}", hotp(&key, step as u64), width = DIGITS as usize) == code)
+}
+
+// HOTP (RFC 4226): HMAC-SHA1 do contador com truncagem dinâmica
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff;
+    binary % 10u32.pow(DIGITS)
+}
+
+// Códigos de recuperação de uso único (formato "xxxxx-xxxxx"), mostrados uma só vez ao utilizador
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            let code = hex::encode(bytes);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+// Forma canónica de um código de recuperação (aceita maiúsculas e espaços), antes do hash
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase().replace(' ', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Segredo dos vetores de teste SHA-1 das RFC 4226 e 6238: "12345678901234567890"
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn hotp_matches_rfc_4226() {
+        let key = BASE32_NOPAD.decode(RFC_SECRET.as_bytes()).unwrap();
+        let expected = [755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp(&key, counter as u64), *code);
+        }
+    }
+
+    // Vetores SHA-1 da RFC 6238 (8 dígitos), truncados aos 6 dígitos usados aqui
+    #[test]
+    fn verify_code_matches_rfc_6238() {
+        let vectors = [
+            (59, "287082"),          // 94287082
+            (1111111109, "081804"),  // 07081804
+            (1111111111, "050471"),  // 14050471
+            (1234567890, "005924"),  // 89005924
+            (2000000000, "279037"),  // 69279037
+            (20000000000, "353130"), // 65353130
+        ];
+        for (time, code) in vectors {
+            assert_eq!(verify_code(RFC_SECRET, code, time), Some(time / STEP_SECONDS));
+        }
+    }
+
+    #[test]
+    fn verify_code_tolerates_one_step_of_drift() {
+        // O código de T=59 pertence ao passo 1
+        assert_eq!(verify_code(RFC_SECRET, "287082", 0), Some(1));
+        assert_eq!(verify_code(RFC_SECRET, "287082", 89), Some(1));
+        assert_eq!(verify_code(RFC_SECRET, "287082", 90), None);
+        assert_eq!(verify_code(RFC_SECRET, "287082", 1000), None);
+    }
+
+    #[test]
+    fn verify_code_rejects_malformed_codes() {
+        assert_eq!(verify_code(RFC_SECRET, " 287082 ", 59), Some(1));
+        assert_eq!(verify_code(RFC_SECRET, "28708", 59), None);
+        assert_eq!(verify_code(RFC_SECRET, "2870820", 59), None);
+        assert_eq!(verify_code(RFC_SECRET, "94287082", 59), None);
+        assert_eq!(verify_code(RFC_SECRET, "28708a", 59), None);
+        assert_eq!(verify_code(RFC_SECRET, "+87082", 59), None);
+        assert_eq!(verify_code(RFC_SECRET, "", 59), None);
+        assert_eq!(verify_code("segredo inválido", "287082", 59), None);
+    }
+
+    #[test]
+    fn recovery_codes_are_normalized_before_hashing() {
+        assert_eq!(normalize_recovery_code("  AbCdE-12345\n"), "abcde-12345");
+        assert_eq!(normalize_recovery_code("abcde -12 345"), "abcde-12345");
+
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(codes.iter().all(|code| normalize_recovery_code(code) == *code && code.len() == 11));
+    }
+}
@@ -0,0 +1,226 @@
+// Conversa (comentários e respostas) de um ticket.
+// Usado tanto na página do cliente como no painel de admin: ambas devem ter
+// um elemento com id="ticket-conversation" onde o painel é desenhado.
+
+// Escapa texto vindo do utilizador antes de o inserir no HTML.
+function escapeHtml(text) {
+  const div = document.createElement("div");
+  div.textContent = text ?? "";
+  return div.innerHTML;
+}
+
+// Formata o timestamp UTC devolvido pela API no horário local.
+function formatCommentDate(naiveUtc) {
+  const date = new Date(`${naiveUtc}Z`);
+  return isNaN(date) ? naiveUtc : date.toLocaleString("pt-BR");
+}
+
+// Monta a árvore de respostas a partir de comment_parent_id.
+// Respostas cujo comentário de origem não foi devolvido pela API sobem para a raiz.
+function buildCommentTree(comments) {
+  const ids = new Set(comments.map((comment) => comment.comment_id));
+  const byParent = new Map();
+  comments.forEach((comment) => {
+    const parent = comment.comment_parent_id ?? null;
+    const key = ids.has(parent) ? parent : null;
+    if (!byParent.has(key)) byParent.set(key, []);
+    byParent.get(key).push(comment);
+  });
+  return byParent;
+}
+
+// Lista de anexos de um comentário; o download é feito via fetch (ver baixarAnexo).
+function renderAttachmentLinks(attachments) {
+  if (!attachments || attachments.length === 0) return "";
+  return `<div class="ticket-attachments">${attachments
+    .map(
+      (attachment) => `<a href="#" class="attachment-link" data-ticket-id="${attachment.attachment_ticket_id}"
+        data-attachment-id="${attachment.attachment_id}" data-file-name="${escapeHtml(attachment.attachment_file_name)}"
+        >📎 ${escapeHtml(attachment.attachment_file_name)}</a>`
+    )
+    .join("")}</div>`;
+}
+
+// Descarrega um anexo com o token no cabeçalho (um link simples não enviaria o Bearer).
+async function baixarAnexo(ticketId, attachmentId, fileName) {
+  const token = localStorage.getItem("token");
+  try {
+    const response = await fetch(`/tickets/${ticketId}/attachments/${attachmentId}`, {
+      headers: { Authorization: `Bearer ${token}` },
+    });
+    if (!response.ok) {
+      const errorData = await response.json().catch(() => ({}));
+      throw new Error(errorData.erro || "Erro ao descarregar anexo");
+    }
+    const url = URL.createObjectURL(await response.blob());
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = fileName;
+    link.click();
+    URL.revokeObjectURL(url);
+  } catch (error) {
+    alert(error.message);
+  }
+}
+
+function renderCommentThread(byParent, parentId, depth) {
+  const children = byParent.get(parentId) || [];
+  return children
+    .map(
+      (comment) => `
+        <div class="ticket-comment${comment.comment_is_internal ? " ticket-comment-internal" : ""}" style="margin-left: ${Math.min(depth, 6) * 24}px">
+          <div class="ticket-comment-meta">
+            <strong>${escapeHtml(comment.comment_author_name)}${comment.comment_is_internal ? " (nota interna)" : ""}</strong>
+            <small>${escapeHtml(formatCommentDate(comment.comment_created_at))}</small>
+          </div>
+          <div class="ticket-comment-body">${escapeHtml(comment.comment_body)}</div>
+          ${renderAttachmentLinks(comment.comment_attachments)}
+          <button type="button" class="btn btn-link btn-sm p-0 reply-btn" data-comment-id="${comment.comment_id}"
+            data-author="${escapeHtml(comment.comment_author_name)}">Responder</button>
+        </div>
+        ${renderCommentThread(byParent, comment.comment_id, depth + 1)}
+      `
+    )
+    .join("");
+}
+
+// Abre o painel de conversa de um ticket e carrega os comentários.
+// Com options.staff = true o formulário permite criar notas internas.
+async function abrirConversa(ticketId, ticketTitle, options = {}) {
+  const container = document.getElementById("ticket-conversation");
+  const token = localStorage.getItem("token");
+  if (!container) {
+    console.error("Elemento #ticket-conversation não encontrado no HTML!");
+    return;
+  }
+  if (!token) {
+    window.location.href = "/login";
+    return;
+  }
+
+  container.style.display = "block";
+  container.innerHTML = `
+    <div class="d-flex justify-content-between align-items-center mb-3">
+      <h4 class="m-0">Conversa do chamado #${ticketId} - ${escapeHtml(ticketTitle)}</h4>
+      <button type="button" class="btn btn-sm btn-secondary" id="conversation-close">Fechar conversa</button>
+    </div>
+    <div id="conversation-list">Carregando conversa...</div>
+    <form id="conversation-form" class="mt-3 text-start">
+      <div id="conversation-reply-to" class="mb-2" style="display: none"></div>
+      <textarea name="comment_body" class="form-control" rows="3" placeholder="Escreva uma mensagem..."></textarea>
+      <input type="file" name="attachments" class="form-control form-control-sm mt-2" multiple />
+      ${
+        options.staff
+          ? `<div class="form-check mt-2">
+              <input class="form-check-input" type="checkbox" name="comment_is_internal" id="conversation-internal" />
+              <label class="form-check-label" for="conversation-internal">Nota interna (não visível ao cliente)</label>
+            </div>`
+          : ""
+      }
+      <button type="submit" class="btn btn-primary btn-sm mt-2">Enviar</button>
+    </form>
+  `;
+
+  let replyTo = null;
+  const replyInfo = container.querySelector("#conversation-reply-to");
+  const setReplyTo = (commentId, author) => {
+    replyTo = commentId;
+    if (commentId === null) {
+      replyInfo.style.display = "none";
+      replyInfo.innerHTML = "";
+      return;
+    }
+    replyInfo.style.display = "block";
+    replyInfo.innerHTML = `Respondendo a <strong>${escapeHtml(author)}</strong>
+      <button type="button" class="btn btn-link btn-sm p-0" id="conversation-cancel-reply">cancelar</button>`;
+    replyInfo
+      .querySelector("#conversation-cancel-reply")
+      .addEventListener("click", () => setReplyTo(null));
+  };
+
+  container
+    .querySelector("#conversation-close")
+    .addEventListener("click", () => {
+      container.style.display = "none";
+      container.innerHTML = "";
+    });
+
+  async function carregarComentarios() {
+    const list = container.querySelector("#conversation-list");
+    try {
+      const response = await fetch(`/tickets/${ticketId}/comments`, {
+        headers: { Authorization: `Bearer ${token}` },
+      });
+      if (!response.ok) {
+        const errorData = await response.json().catch(() => ({}));
+        throw new Error(
+          errorData.erro || `Erro ao carregar conversa (Status: ${response.status})`
+        );
+      }
+      const data = await response.json();
+      if (!data.comments || data.comments.length === 0) {
+        list.innerHTML = "<p>Nenhuma mensagem neste chamado ainda.</p>";
+        return;
+      }
+      list.innerHTML = renderCommentThread(buildCommentTree(data.comments), null, 0);
+      list.querySelectorAll(".attachment-link").forEach((link) => {
+        link.addEventListener("click", (event) => {
+          event.preventDefault();
+          baixarAnexo(link.dataset.ticketId, link.dataset.attachmentId, link.dataset.fileName);
+        });
+      });
+      list.querySelectorAll(".reply-btn").forEach((button) => {
+        button.addEventListener("click", () =>
+          setReplyTo(Number(button.dataset.commentId), button.dataset.author)
+        );
+      });
+    } catch (error) {
+      list.innerHTML = `<p>${escapeHtml(error.message)}</p>`;
+    }
+  }
+
+  container
+    .querySelector("#conversation-form")
+    .addEventListener("submit", async function (e) {
+      e.preventDefault();
+      const body = this.querySelector('textarea[name="comment_body"]').value;
+      const files = Array.from(this.querySelector('input[name="attachments"]').files);
+      if (!body.trim() && files.length === 0) {
+        alert("Escreva uma mensagem ou anexe um ficheiro.");
+        return;
+      }
+      // Multipart: o JSON do comentário segue na parte "payload" e os ficheiros em "attachments".
+      const formData = new FormData();
+      formData.append(
+        "payload",
+        JSON.stringify({
+          comment_body: body,
+          comment_parent_id: replyTo,
+          comment_is_internal:
+            this.querySelector('input[name="comment_is_internal"]')?.checked ?? false,
+        })
+      );
+      files.forEach((file) => formData.append("attachments", file));
+      try {
+        const response = await fetch(`/tickets/${ticketId}/comments`, {
+          method: "POST",
+          headers: {
+            Authorization: `Bearer ${token}`,
+          },
+          body: formData,
+        });
+        if (!response.ok) {
+          const errorData = await response.json().catch(() => ({}));
+          throw new Error(errorData.erro || "Erro ao enviar mensagem");
+        }
+        this.reset();
+        setReplyTo(null);
+        carregarComentarios();
+      } catch (error) {
+        alert(error.message);
+      }
+    });
+
+  await carregarComentarios();
+  container.scrollIntoView({ behavior: "smooth" });
+}
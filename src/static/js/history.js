@@ -0,0 +1,85 @@
+// Linha do tempo (histórico de alterações) de um ticket no painel de admin.
+// A página deve ter um elemento com id="ticket-history" e incluir comments.js (escapeHtml).
+
+// Descrição legível de cada tipo de entrada do histórico.
+function describeHistoryEntry(entry) {
+  const oldValue = escapeHtml(entry.history_old_value ?? "—");
+  const newValue = escapeHtml(entry.history_new_value ?? "—");
+  switch (entry.history_action) {
+    case "criado":
+      return `Abriu o chamado: <em>${newValue}</em>`;
+    case "status":
+      return `Status: ${oldValue} → <strong>${newValue}</strong>`;
+    case "prioridade":
+      return `Prioridade: ${oldValue} → <strong>${newValue}</strong>`;
+    case "atribuicao":
+      return `Responsável: ${oldValue} → <strong>${newValue}</strong>`;
+    case "comentario":
+      return `Comentou: <em>${newValue}</em>`;
+    case "nota_interna":
+      return `Nota interna: <em>${newValue}</em>`;
+    case "anexo":
+      return `Anexou: ${newValue}`;
+    default:
+      return `${escapeHtml(entry.history_action)}: ${oldValue} → ${newValue}`;
+  }
+}
+
+// Abre o painel com o histórico do ticket.
+async function abrirHistorico(ticketId, ticketTitle) {
+  const container = document.getElementById("ticket-history");
+  const token = localStorage.getItem("token");
+  if (!container) {
+    console.error("Elemento #ticket-history não encontrado no HTML!");
+    return;
+  }
+  if (!token) {
+    window.location.href = "/login";
+    return;
+  }
+
+  container.style.display = "block";
+  container.innerHTML = `
+    <div class="d-flex justify-content-between align-items-center mb-3">
+      <h4 class="m-0">Histórico do chamado #${ticketId} - ${escapeHtml(ticketTitle)}</h4>
+      <button type="button" class="btn btn-sm btn-secondary" id="history-close">Fechar histórico</button>
+    </div>
+    <ul class="ticket-timeline" id="history-list"><li>Carregando histórico...</li></ul>
+  `;
+  container.querySelector("#history-close").addEventListener("click", () => {
+    container.style.display = "none";
+    container.innerHTML = "";
+  });
+
+  const list = container.querySelector("#history-list");
+  try {
+    const response = await fetch(`/tickets/${ticketId}/history`, {
+      headers: { Authorization: `Bearer ${token}` },
+    });
+    if (!response.ok) {
+      const errorData = await response.json().catch(() => ({}));
+      throw new Error(
+        errorData.erro || `Erro ao carregar histórico (Status: ${response.status})`
+      );
+    }
+    const data = await response.json();
+    if (!data.history || data.history.length === 0) {
+      list.innerHTML = "<li>Nenhuma alteração registada.</li>";
+    } else {
+      list.innerHTML = data.history
+        .map(
+          (entry) => `
+            <li class="${entry.history_is_internal ? "timeline-internal" : ""}">
+              <small>${escapeHtml(formatCommentDate(entry.history_created_at))} —
+                <strong>${escapeHtml(entry.history_actor_name ?? "Sistema")}</strong></small>
+              <div>${describeHistoryEntry(entry)}</div>
+            </li>
+          `
+        )
+        .join("");
+    }
+  } catch (error) {
+    list.innerHTML = `<li>${escapeHtml(error.message)}</li>`;
+  }
+  container.scrollIntoView({ behavior: "smooth" });
+}
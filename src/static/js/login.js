@@ -1,4 +1,10 @@
 document.addEventListener("DOMContentLoaded", () => {
+  // Regresso do login por SSO: a página já traz os tokens da sessão.
+  if (window.oidcLogin) {
+    concluirLogin(window.oidcLogin);
+    return;
+  }
+
   const loginForm = document.getElementById("login"); // Obtém o formulário de login pelo ID.
 
   if (loginForm) {
@@ -40,20 +46,10 @@ async function sendLoginData(login_obj) {
 
     // Verifica se a requisição foi bem-sucedida (status 2xx) e se um token foi retornado.
     if (response.ok && data.token) {
-      localStorage.setItem("token", data.token); // Armazena o token no localStorage.
-
-      // Redireciona o utilizador com base no seu papel (role).
-      if (data.role === "Administrador") {
-        window.location.href = "/admin/dashboard"; // Redireciona administradores.
-      } else if (data.role === "Cliente") {
-        window.location.href = "/new_ticket"; // Redireciona clientes.
-      } else {
-        // Fallback se o papel não for reconhecido.
-        alert(
-          "Login bem-sucedido, mas não foi possível determinar a sua página inicial."
-        );
-        window.location.href = "/";
-      }
+      concluirLogin(data);
+    } else if (response.ok && data.mfa_token) {
+      // A conta usa (ou tem de configurar) a verificação em duas etapas.
+      iniciarSegundaEtapa(data);
     } else {
       // Se a resposta não for 'ok' ou não houver token, exibe uma mensagem de erro.
       const errorMessage =
@@ -67,3 +63,93 @@ async function sendLoginData(login_obj) {
     alert("Erro ao conectar com o servidor durante o login!");
   }
 }
+
+// Guarda os tokens da sessão, abre a sessão do navegador (cookies das páginas protegidas)
+// e redireciona o utilizador para a página pedida ou com base no seu papel (role).
+async function concluirLogin(data) {
+  localStorage.setItem("token", data.token); // Armazena o token no localStorage.
+  localStorage.setItem("refresh_token", data.refresh_token); // Usado para renovar o token (session.js).
+
+  try {
+    const response = await fetch("/login/session", {
+      method: "POST",
+      headers: { Authorization: `Bearer ${data.token}` },
+    });
+    if (!response.ok) throw new Error(`Status: ${response.status}`);
+  } catch (error) {
+    console.error("Erro ao abrir a sessão do navegador:", error);
+    alert("Login bem-sucedido, mas não foi possível abrir a sessão. Tente novamente.");
+    return;
+  }
+
+  // Página protegida que levou ao login (já validada pelo servidor; só caminhos deste site)
+  const next = destinoInterno(document.getElementById("login").dataset.next);
+  if (next) {
+    window.location.href = next;
+  } else if (data.role === "Administrador" || data.role === "Tecnico") {
+    window.location.href = "/admin/dashboard"; // Redireciona a equipa de suporte.
+  } else if (data.role === "Cliente") {
+    window.location.href = "/new_ticket"; // Redireciona clientes.
+  } else {
+    // Fallback se o papel não for reconhecido.
+    alert(
+      "Login bem-sucedido, mas não foi possível determinar a sua página inicial."
+    );
+    window.location.href = "/";
+  }
+}
+
+// Devolve o caminho se ele apontar para este site, ou null. Os navegadores tratam "\"
+// como "/", por isso "/\evil.com" seria outro site; a origem é confirmada com URL.
+function destinoInterno(next) {
+  if (!next || !next.startsWith("/") || next.includes("\\")) return null;
+  try {
+    const url = new URL(next, window.location.origin);
+    if (url.origin !== window.location.origin) return null;
+    return url.pathname + url.search + url.hash;
+  } catch (error) {
+    return null;
+  }
+}
+
+// Segunda etapa do login: pede o código da aplicação autenticadora ou, se a
+// verificação for obrigatória e ainda não estiver configurada, mostra a configuração.
+function iniciarSegundaEtapa(data) {
+  const loginForm = document.getElementById("login");
+  const area = document.getElementById("mfa-area");
+  loginForm.style.display = "none";
+
+  if (data.mfa_setup_required) {
+    mostrarConfiguracao2FA(area, { mfaToken: data.mfa_token, onConcluido: concluirLogin });
+    return;
+  }
+
+  area.style.display = "block";
+  area.innerHTML = `
+    <h5>Verificação em duas etapas</h5>
+    <p class="small">Introduza o código da aplicação autenticadora ou um código de recuperação.</p>
+    <form id="mfa-form">
+      <input type="text" name="code" class="form-control mb-2" autocomplete="one-time-code" required placeholder="Código" />
+      <button type="submit" class="btn btn-primary w-100">Verificar</button>
+    </form>
+    <p class="small text-center mt-3"><a href="/login">Voltar</a></p>`;
+  document.getElementById("mfa-form").addEventListener("submit", async (e) => {
+    e.preventDefault();
+    try {
+      const response = await fetch("/login/2fa", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ mfa_token: data.mfa_token, code: e.target.elements.code.value.trim() }),
+      });
+      const result = await response.json().catch(() => ({}));
+      if (response.ok && result.token) {
+        concluirLogin(result);
+      } else {
+        alert(result.error || "Código inválido.");
+      }
+    } catch (error) {
+      console.error("Erro na verificação em duas etapas:", error);
+      alert("Erro ao conectar com o servidor durante o login!");
+    }
+  });
+}
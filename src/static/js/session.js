@@ -0,0 +1,97 @@
+// Gestão da sessão no navegador: o access token (curta duração) e o refresh token
+// ficam no localStorage. Quando uma chamada autenticada recebe 401, o access token é
+// renovado uma vez em POST /login/refresh e a chamada é repetida com o novo token.
+// As páginas usam o cookie da sessão (HttpOnly); os pedidos que alteram dados levam
+// também o token CSRF, lido do cookie helpdesk_csrf, no cabeçalho X-CSRF-Token.
+
+let renovacaoEmCurso = null;
+
+// Troca o refresh token por um novo par de tokens. Devolve true se a sessão continua válida.
+function renovarSessao() {
+  const refreshToken = localStorage.getItem("refresh_token");
+  if (!refreshToken) return Promise.resolve(false);
+
+  // Várias chamadas podem expirar ao mesmo tempo; o refresh token só pode ser usado uma vez.
+  if (!renovacaoEmCurso) {
+    renovacaoEmCurso = fetchOriginal("/login/refresh", {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ refresh_token: refreshToken }),
+    })
+      .then(async (response) => {
+        if (!response.ok) return false;
+        const data = await response.json();
+        localStorage.setItem("token", data.token);
+        localStorage.setItem("refresh_token", data.refresh_token);
+        return true;
+      })
+      .catch(() => false)
+      .finally(() => {
+        renovacaoEmCurso = null;
+      });
+  }
+  return renovacaoEmCurso;
+}
+
+const fetchOriginal = window.fetch.bind(window);
+
+function tokenCsrf() {
+  const cookie = document.cookie.split("; ").find((c) => c.startsWith("helpdesk_csrf="));
+  return cookie ? cookie.substring("helpdesk_csrf=".length) : "";
+}
+
+// Acrescenta o token CSRF aos pedidos para este site que alteram dados
+function comCsrf(input, init) {
+  const metodo = (init.method || (input instanceof Request ? input.method : "GET")).toUpperCase();
+  const url = new URL(input instanceof Request ? input.url : input, window.location.href);
+  const csrf = tokenCsrf();
+  if (["GET", "HEAD", "OPTIONS"].includes(metodo) || url.origin !== window.location.origin || !csrf) {
+    return init;
+  }
+  if (init.headers instanceof Headers) {
+    init.headers.set("X-CSRF-Token", csrf);
+    return init;
+  }
+  return { ...init, headers: { ...(init.headers || {}), "X-CSRF-Token": csrf } };
+}
+
+window.fetch = async (input, init = {}) => {
+  init = comCsrf(input, init);
+  const response = await fetchOriginal(input, init);
+  const headers = init.headers || {};
+  const autenticada =
+    typeof headers.Authorization === "string" &&
+    headers.Authorization.startsWith("Bearer ");
+
+  if (response.status !== 401 || !autenticada) return response;
+  if (!(await renovarSessao())) return response;
+
+  const novoToken = localStorage.getItem("token");
+  return fetchOriginal(input, {
+    ...init,
+    headers: { ...headers, Authorization: `Bearer ${novoToken}` },
+  });
+};
+
+// Encerra a sessão no servidor e volta para a página de login.
+async function encerrarSessao() {
+  const token = localStorage.getItem("token");
+  const refreshToken = localStorage.getItem("refresh_token");
+  const headers = { "Content-Type": "application/json", "X-CSRF-Token": tokenCsrf() };
+  if (token) headers.Authorization = `Bearer ${token}`;
+
+  try {
+    // Apaga também os cookies da sessão do navegador
+    await fetchOriginal("/logout", {
+      method: "POST",
+      headers,
+      body: JSON.stringify({ refresh_token: refreshToken || "" }),
+    });
+  } catch (error) {
+    // Mesmo sem resposta do servidor, a sessão local é descartada.
+    console.error("Erro ao encerrar a sessão:", error);
+  }
+  localStorage.removeItem("token");
+  localStorage.removeItem("refresh_token");
+  window.location.href = "/login";
+}
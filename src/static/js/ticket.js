@@ -71,17 +71,29 @@ document.addEventListener("DOMContentLoaded", () => {
             <td>
               <button class="btn btn-sm btn-outline-warning fw-bold close-btn" data-id="${
                 ticket.ticket_id
+              }" data-status="${
+                ticket.ticket_status === "Resolvido" ? "Reaberto" : "Fechado"
               }">
-                Fechar
+                ${ticket.ticket_status === "Resolvido" ? "Reabrir" : "Fechar"}
+              </button>
+              ${
+                ticket.ticket_status === "Resolvido"
+                  ? `<button class="btn btn-sm btn-outline-success fw-bold close-btn" data-id="${ticket.ticket_id}" data-status="Fechado">Confirmar</button>`
+                  : ""
+              }
+              <button class="btn btn-sm btn-outline-info fw-bold conversation-btn">
+                Conversa
               </button>
             </td>
           `;
           tbody.appendChild(tr);
-          // Adiciona o listener de evento para o botão "Fechar" de cada ticket.
-          const closeButton = tr.querySelector(".close-btn");
-          if (closeButton) {
-            closeButton.addEventListener("click", closeTicketHandler);
-          }
+          // Adiciona o listener de evento para os botões de transição de cada ticket.
+          tr.querySelectorAll(".close-btn").forEach((button) => {
+            button.addEventListener("click", closeTicketHandler);
+          });
+          tr.querySelector(".conversation-btn").addEventListener("click", () =>
+            abrirConversa(ticket.ticket_id, ticket.ticket_title)
+          );
         });
       }
     } catch (error) {
@@ -122,15 +134,21 @@ document.addEventListener("DOMContentLoaded", () => {
         ticket_client_id: 0, // O backend definirá o ID do cliente com base no JWT.
       };
 
+      // Com anexos o pedido vai em multipart: o JSON do ticket segue na parte "payload".
+      const formData = new FormData();
+      formData.append("payload", JSON.stringify(ticketParaEnviar));
+      Array.from(document.getElementById("anexos")?.files || []).forEach((file) =>
+        formData.append("attachments", file)
+      );
+
       try {
         // Envia os dados do novo ticket para o backend.
         const response = await fetch("/new_ticket", {
           method: "POST",
           headers: {
-            "Content-Type": "application/json",
             Authorization: `Bearer ${token}`,
           },
-          body: JSON.stringify(ticketParaEnviar),
+          body: formData,
         });
 
         if (!response.ok) {
@@ -156,13 +174,14 @@ document.addEventListener("DOMContentLoaded", () => {
     });
   }
 
-  // Função para lidar com o fechamento de um ticket.
+  // Função para lidar com a transição de status de um ticket (fechar, confirmar ou reabrir).
   async function closeTicketHandler(e) {
-    // Garante que o clique foi no botão de fechar.
+    // Garante que o clique foi num botão de transição.
     if (!e.target.classList.contains("close-btn")) return;
 
     const button = e.target;
     const ticketId = button.dataset.id;
+    const novoStatus = button.dataset.status;
 
     const token = localStorage.getItem("token");
     if (!token) {
@@ -172,12 +191,14 @@ document.addEventListener("DOMContentLoaded", () => {
     }
 
     try {
-      // Envia a requisição para fechar o ticket.
-      const response = await fetch(`/tickets/${ticketId}/close`, {
+      // Envia a requisição de transição de status do ticket.
+      const response = await fetch(`/tickets/${ticketId}/transition`, {
         method: "POST",
         headers: {
+          "Content-Type": "application/json",
           Authorization: `Bearer ${token}`,
         },
+        body: JSON.stringify({ status: novoStatus }),
       });
 
       if (!response.ok) {
@@ -187,10 +208,15 @@ document.addEventListener("DOMContentLoaded", () => {
           window.location.href = "/login";
           return;
         }
-        throw new Error("Erro ao fechar chamado");
+        const errorData = await response.json().catch(() => ({}));
+        throw new Error(errorData.erro || "Erro ao atualizar chamado");
       }
 
-      alert("Chamado fechado com sucesso!");
+      alert(
+        novoStatus === "Fechado"
+          ? "Chamado fechado com sucesso!"
+          : "Chamado reaberto com sucesso!"
+      );
       carregarMeusTickets(); // Recarrega a lista de tickets para refletir a mudança.
     } catch (error) {
       alert(error.message);
@@ -199,10 +225,7 @@ document.addEventListener("DOMContentLoaded", () => {
 
   // Adiciona listener para o botão de logout, se existir.
   if (logoutBtn) {
-    logoutBtn.addEventListener("click", () => {
-      localStorage.removeItem("token");
-      window.location.href = "/login";
-    });
+    logoutBtn.addEventListener("click", encerrarSessao);
   }
 
   // Carrega os tickets do utilizador se um token estiver presente.
@@ -0,0 +1,82 @@
+// Verificação em duas etapas (TOTP): configuração com QR code e códigos de recuperação.
+// Usado na página de login (configuração obrigatória, com o token intermédio) e no painel
+// (configuração opcional, com o access token da sessão).
+
+// POST em JSON para as rotas /login/2fa/*; com mfaToken o utilizador é identificado pelo
+// token intermédio do login, senão pelo access token guardado.
+async function pedido2FA(caminho, corpo, mfaToken) {
+  const headers = { "Content-Type": "application/json" };
+  const token = localStorage.getItem("token");
+  if (!mfaToken && token) headers.Authorization = `Bearer ${token}`;
+
+  const response = await fetch(caminho, {
+    method: "POST",
+    headers,
+    body: JSON.stringify(mfaToken ? { ...corpo, mfa_token: mfaToken } : corpo),
+  });
+  const data = await response.json().catch(() => ({}));
+  if (!response.ok) {
+    throw new Error(data.error || `Erro na verificação em duas etapas (Status: ${response.status})`);
+  }
+  return data;
+}
+
+// Mostra o QR code, pede o primeiro código e, depois de ativada, os códigos de recuperação.
+// onConcluido recebe a resposta da ativação (com os tokens da sessão, se mfaToken foi usado).
+async function mostrarConfiguracao2FA(container, { mfaToken = null, onConcluido }) {
+  container.style.display = "block";
+  container.innerHTML = "<p>A preparar a configuração...</p>";
+
+  let setup;
+  try {
+    setup = await pedido2FA("/login/2fa/setup", {}, mfaToken);
+  } catch (error) {
+    container.innerHTML = "";
+    alert(error.message);
+    return;
+  }
+
+  container.innerHTML = `
+    <h5>Configurar a verificação em duas etapas</h5>
+    <p class="small">Leia o QR code com uma aplicação autenticadora (Google Authenticator, Authy, FreeOTP...)
+      ou introduza a chave manualmente.</p>
+    <div class="text-center mb-2 two-factor-qr"></div>
+    <p class="small text-center"><code class="two-factor-secret"></code></p>
+    <form class="two-factor-confirm">
+      <input type="text" name="code" class="form-control mb-2" inputmode="numeric"
+        autocomplete="one-time-code" pattern="[0-9]{6}" maxlength="6" required placeholder="Código de 6 dígitos" />
+      <button type="submit" class="btn btn-primary w-100">Confirmar</button>
+    </form>`;
+  // O SVG é gerado pelo servidor a partir do segredo
+  container.querySelector(".two-factor-qr").innerHTML = setup.qr_svg;
+  container.querySelector(".two-factor-secret").textContent = setup.secret;
+
+  container.querySelector(".two-factor-confirm").addEventListener("submit", async (e) => {
+    e.preventDefault();
+    const code = e.target.elements.code.value.trim();
+    try {
+      const data = await pedido2FA("/login/2fa/enable", { code }, mfaToken);
+      mostrarCodigosRecuperacao(container, data, onConcluido);
+    } catch (error) {
+      alert(error.message);
+    }
+  });
+}
+
+function mostrarCodigosRecuperacao(container, data, onConcluido) {
+  container.innerHTML = `
+    <h5>Verificação em duas etapas ativada</h5>
+    <p class="small">Guarde estes códigos de recuperação num local seguro. Cada um permite entrar
+      uma vez se não tiver acesso à aplicação autenticadora. Eles não voltarão a ser mostrados.</p>
+    <ul class="list-unstyled text-center two-factor-codes"></ul>
+    <button type="button" class="btn btn-success w-100">Guardei os códigos</button>`;
+  const lista = container.querySelector(".two-factor-codes");
+  data.recovery_codes.forEach((codigo) => {
+    const item = document.createElement("li");
+    const code = document.createElement("code");
+    code.textContent = codigo;
+    item.appendChild(code);
+    lista.appendChild(item);
+  });
+  container.querySelector("button").addEventListener("click", () => onConcluido(data));
+}
@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequest, Multipart, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::models::{appstate::AppState, attachment::UploadedFile};
+
+// Extractor para endpoints que aceitam anexos.
+// - application/json: o corpo é o payload, sem anexos (compatível com os clientes existentes);
+// - multipart/form-data: a parte "payload" traz o mesmo JSON e cada parte
+//   "attachments" traz um ficheiro, validado contra os limites em AppState.
+pub struct WithAttachments<T>(pub T, pub Vec<UploadedFile>);
+
+fn upload_error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "erro": message.into() }))).into_response()
+}
+
+// Mantém apenas o nome do ficheiro (sem diretórios enviados por alguns navegadores)
+fn sanitize_file_name(raw: &str) -> String {
+    let name = raw.rsplit(['/', '\\']).next().unwrap_or("").trim();
+    let name: String = name.chars().filter(|c| !c.is_control()).take(255).collect();
+    if name.is_empty() { "anexo".to_string() } else { name }
+}
+
+// Tipo real do ficheiro, deduzido da assinatura dos primeiros bytes; o Content-Type
+// declarado pelo cliente não é usado. Sem assinatura conhecida, texto UTF-8 sem bytes
+// nulos é tratado como text/plain e o resto como application/octet-stream.
+pub fn sniff_content_type(bytes: &[u8]) -> &'static str {
+    match infer::get(bytes) {
+        Some(kind) => kind.mime_type(),
+        None if !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok() => "text/plain",
+        None => "application/octet-stream",
+    }
+}
+
+#[async_trait]
+impl<T> FromRequest<Arc<AppState>> for WithAttachments<T>
+where
+    T: DeserializeOwned + Send,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let is_multipart = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("multipart/form-data"));
+
+        if !is_multipart {
+            let Json(payload) = Json::<T>::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(WithAttachments(payload, Vec::new()));
+        }
+
+        let mut multipart = Multipart::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let limits = &state.attachment_limits;
+        let mut payload: Option<T> = None;
+        let mut files = Vec::new();
+
+        while let Some(mut field) = multipart
+            .next_field()
+            .await
+            .map_err(|_| upload_error(StatusCode::BAD_REQUEST, "Pedido multipart inválido."))?
+        {
+            match field.name() {
+                Some("payload") => {
+                    let text = field
+                        .text()
+                        .await
+                        .map_err(|_| upload_error(StatusCode::BAD_REQUEST, "Pedido multipart inválido."))?;
+                    payload = Some(serde_json::from_str(&text).map_err(|e| {
+                        upload_error(StatusCode::UNPROCESSABLE_ENTITY, format!("Payload inválido: {}", e))
+                    })?);
+                }
+                Some("attachments") => {
+                    // Um <input type="file"> vazio envia uma parte sem nome de ficheiro
+                    let file_name = match field.file_name() {
+                        Some(name) if !name.is_empty() => sanitize_file_name(name),
+                        _ => continue,
+                    };
+                    if files.len() >= limits.max_files {
+                        return Err(upload_error(
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            format!("Máximo de {} anexos por envio.", limits.max_files),
+                        ));
+                    }
+                    // Lê em blocos para abortar assim que o limite é ultrapassado
+                    let mut bytes = Vec::new();
+                    while let Some(chunk) = field
+                        .chunk()
+                        .await
+                        .map_err(|_| upload_error(StatusCode::BAD_REQUEST, "Falha ao ler o anexo."))?
+                    {
+                        if bytes.len() + chunk.len() > limits.max_file_bytes {
+                            return Err(upload_error(
+                                StatusCode::PAYLOAD_TOO_LARGE,
+                                format!("O anexo {} excede o limite de {} bytes.", file_name, limits.max_file_bytes),
+                            ));
+                        }
+                        bytes.extend_from_slice(&chunk);
+                    }
+
+                    let content_type = sniff_content_type(&bytes).to_string();
+                    if !limits.is_allowed_mime(&content_type) {
+                        return Err(upload_error(
+                            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                            format!("Tipo de ficheiro não permitido: {}", content_type),
+                        ));
+                    }
+
+                    files.push(UploadedFile { file_name, content_type, bytes });
+                }
+                _ => {} // Partes desconhecidas são ignoradas
+            }
+        }
+
+        let payload = payload
+            .ok_or_else(|| upload_error(StatusCode::BAD_REQUEST, "Parte 'payload' ausente no pedido."))?;
+        Ok(WithAttachments(payload, files))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_uses_the_file_signature() {
+        assert_eq!(sniff_content_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), "image/png");
+        assert_eq!(sniff_content_type(b"%PDF-1.7\n"), "application/pdf");
+    }
+
+    #[test]
+    fn sniff_falls_back_to_text_or_binary() {
+        assert_eq!(sniff_content_type("Olá, equipa".as_bytes()), "text/plain");
+        assert_eq!(sniff_content_type(b"MZ\x90\0\x03"), "application/vnd.microsoft.portable-executable");
+        assert_eq!(sniff_content_type(b"\0\x01\x02"), "application/octet-stream");
+    }
+}